@@ -0,0 +1,63 @@
+//! Protect an axum route with ASH v2.1 verification.
+//!
+//! Run with: `cargo run -p ash-core --example axum_verify --features axum`
+
+use std::sync::Arc;
+
+use ash_core::{
+    build_proof_v21, canonicalize_json, derive_client_secret, generate_context_id, generate_nonce,
+    hash_body, AshLayer, AshMode, MemoryContextStore, StoredContext, VerifiedAsh,
+};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use tower::ServiceExt;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    // Server: issue a context for the endpoint
+    let store = Arc::new(MemoryContextStore::new());
+    let context_id = generate_context_id();
+    let nonce = generate_nonce(32);
+    let binding = "POST /api/transfer";
+
+    store.insert(StoredContext {
+        context_id: context_id.clone(),
+        binding: binding.to_string(),
+        mode: AshMode::Balanced,
+        issued_at: 0,
+        expires_at: u64::MAX,
+        nonce: Some(nonce.clone()),
+        consumed_at: None,
+    });
+
+    let app =
+        Router::new()
+            .route(
+                "/api/transfer",
+                post(|ash: VerifiedAsh| async move {
+                    format!("verified request for {}", ash.context_id)
+                }),
+            )
+            .layer(AshLayer::new(store));
+
+    // Client: sign the request with the derived secret
+    let client_secret = derive_client_secret(&nonce, &context_id, binding);
+    let body = r#"{"amount":100,"recipient":"user123"}"#;
+    let timestamp = "1704067200000";
+    let body_hash = hash_body(&canonicalize_json(body).unwrap());
+    let proof = build_proof_v21(&client_secret, timestamp, binding, &body_hash);
+
+    let request = Request::post("/api/transfer")
+        .header("content-type", "application/json")
+        .header("x-ash-context", &context_id)
+        .header("x-ash-timestamp", timestamp)
+        .header("x-ash-proof", proof)
+        .body(Body::from(body))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    println!("status: {}", response.status());
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,22 @@
+//! actix-web integration.
+//!
+//! Enabled with the `actix` feature. Lets handlers return `AshError`
+//! directly; the response carries only the client-safe body from
+//! [`AshError::to_client_json`].
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+
+use crate::errors::AshError;
+
+impl ResponseError for AshError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type("application/json")
+            .body(self.to_client_json().to_string())
+    }
+}
@@ -0,0 +1,540 @@
+//! Aggregate proofs over a batch of payloads.
+//!
+//! A batch proof commits to every item of a bulk request with a single
+//! HMAC over the root of a Merkle tree of per-item body hashes:
+//!
+//! ```text
+//! leaf     = SHA256(0x00 || bodyHash)            // bodyHash as raw 32 bytes
+//! node     = SHA256(0x01 || left || right)
+//! proof    = HMAC-SHA256(clientSecret, timestamp|binding|root|itemCount)
+//! ```
+//!
+//! Leaves and inner nodes are domain-separated, and an unpaired node is
+//! promoted to the next level unchanged (never duplicated), so distinct
+//! batches cannot share a root. Inclusion proofs let the server later show
+//! that one item was part of the signed batch without the other items.
+//!
+//! [`verify_proof_v21_batch`] is the other kind of batch: many independent
+//! v2.1 requests (e.g. a webhook delivery array), each with its own proof.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::canonicalize::canonicalize_json;
+use crate::compare::timing_safe_equal;
+use crate::errors::{AshError, AshErrorCode};
+use crate::proof::{derive_client_secret, hash_body, verify_proof_v21_checked};
+
+type HmacSha256 = Hmac<Sha256>;
+type Node = [u8; 32];
+
+/// Domain separation prefix for leaf hashes.
+const LEAF_PREFIX: u8 = 0x00;
+/// Domain separation prefix for inner node hashes.
+const NODE_PREFIX: u8 = 0x01;
+
+/// Aggregate proof over a batch, built by [`build_batch_proof`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchProof {
+    /// HMAC over the Merkle root (64 hex chars).
+    pub proof: String,
+    /// Merkle root of the item body hashes (64 hex chars).
+    pub root: String,
+    /// Tree levels from leaves to root.
+    levels: Vec<Vec<Node>>,
+}
+
+/// Proof that a single item is included in a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchInclusionProof {
+    /// Position of the item in the batch.
+    pub index: usize,
+    /// Number of items in the batch.
+    pub item_count: usize,
+    /// Sibling hashes from the leaf level up (hex).
+    pub siblings: Vec<String>,
+}
+
+impl BatchProof {
+    /// Number of items committed to.
+    pub fn item_count(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Build the inclusion proof for the item at `index`.
+    ///
+    /// Returns `None` if `index` is out of range.
+    pub fn batch_inclusion_proof(&self, index: usize) -> Option<BatchInclusionProof> {
+        if index >= self.item_count() {
+            return None;
+        }
+
+        let mut siblings = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(hex::encode(sibling));
+            }
+            position /= 2;
+        }
+
+        Some(BatchInclusionProof {
+            index,
+            item_count: self.item_count(),
+            siblings,
+        })
+    }
+}
+
+/// Build an aggregate proof over a batch of JSON payloads (client-side).
+///
+/// Each payload is canonicalized and hashed as with
+/// `hash_body(&canonicalize_json(payload)?)`.
+///
+/// # Errors
+///
+/// Returns `MalformedRequest` for an empty batch and
+/// `CanonicalizationFailed` if any payload is not valid JSON.
+pub fn build_batch_proof(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    payloads: &[&str],
+) -> Result<BatchProof, AshError> {
+    let levels = build_tree(payloads)?;
+    let root = hex::encode(levels[levels.len() - 1][0]);
+    let proof = sign_root(client_secret, timestamp, binding, &root, payloads.len());
+
+    Ok(BatchProof {
+        proof,
+        root,
+        levels,
+    })
+}
+
+/// Verify an aggregate proof against the full batch (server-side).
+///
+/// # Errors
+///
+/// Same as [`build_batch_proof`].
+pub fn verify_batch_proof(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    payloads: &[&str],
+    client_proof: &str,
+) -> Result<bool, AshError> {
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected = build_batch_proof(&client_secret, timestamp, binding, payloads)?;
+    Ok(timing_safe_equal(
+        expected.proof.as_bytes(),
+        client_proof.as_bytes(),
+    ))
+}
+
+/// Verify that `payload` is included in a signed batch (server-side).
+///
+/// Checks that `client_proof` signs `root`, then recomputes the root from
+/// the payload and the inclusion path.
+///
+/// # Errors
+///
+/// Returns `MalformedRequest` if the inclusion proof is inconsistent
+/// (index out of range, wrong number of siblings, non-hex hashes) and
+/// `CanonicalizationFailed` if the payload is not valid JSON.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_batch_inclusion(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    root: &str,
+    client_proof: &str,
+    payload: &str,
+    inclusion: &BatchInclusionProof,
+) -> Result<bool, AshError> {
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected_proof = sign_root(
+        &client_secret,
+        timestamp,
+        binding,
+        root,
+        inclusion.item_count,
+    );
+    if !timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes()) {
+        return Ok(false);
+    }
+
+    let computed_root = hex::encode(root_from_path(&leaf(payload)?, inclusion)?);
+    Ok(timing_safe_equal(computed_root.as_bytes(), root.as_bytes()))
+}
+
+/// One request in a [`verify_proof_v21_batch`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct V21VerifyItem<'a> {
+    /// Server nonce of the item's context
+    pub nonce: &'a str,
+    /// Context ID
+    pub context_id: &'a str,
+    /// Normalized binding
+    pub binding: &'a str,
+    /// Request timestamp (milliseconds)
+    pub timestamp: &'a str,
+    /// Body hash computed by the server
+    pub body_hash: &'a str,
+    /// Proof sent by the client
+    pub client_proof: &'a str,
+}
+
+/// Outcome of [`verify_proof_v21_batch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchVerifyResult {
+    /// Every item verified.
+    AllValid,
+    /// The item at `index` was the first to fail.
+    Failed {
+        /// Position of the failing item in the batch
+        index: usize,
+        /// `IntegrityFailed` for a wrong proof, or the code of the input
+        /// validation error (e.g. `MalformedRequest` for a bad timestamp)
+        reason: AshErrorCode,
+    },
+}
+
+impl BatchVerifyResult {
+    /// Whether every item verified.
+    pub fn is_valid(&self) -> bool {
+        *self == BatchVerifyResult::AllValid
+    }
+}
+
+/// Verify independent v2.1 proofs in order, stopping at the first failure.
+///
+/// Each item is checked with [`verify_proof_v21_checked`] (constant-time
+/// proof comparison). Items after the first failure are not checked, so the
+/// time taken reveals the failing index; that index is returned anyway.
+///
+/// # Errors
+///
+/// Returns `MalformedRequest` for an empty batch. Per-item problems are
+/// reported as [`BatchVerifyResult::Failed`], never as errors.
+pub fn verify_proof_v21_batch(items: &[V21VerifyItem<'_>]) -> Result<BatchVerifyResult, AshError> {
+    if items.is_empty() {
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            "Batch must contain at least one item",
+        ));
+    }
+
+    for (index, item) in items.iter().enumerate() {
+        let reason = match verify_proof_v21_checked(
+            item.nonce,
+            item.context_id,
+            item.binding,
+            item.timestamp,
+            item.body_hash,
+            item.client_proof,
+        ) {
+            Ok(true) => continue,
+            Ok(false) => AshErrorCode::IntegrityFailed,
+            Err(e) => e.code(),
+        };
+        return Ok(BatchVerifyResult::Failed { index, reason });
+    }
+
+    Ok(BatchVerifyResult::AllValid)
+}
+
+/// Build all tree levels, leaves first.
+fn build_tree(payloads: &[&str]) -> Result<Vec<Vec<Node>>, AshError> {
+    if payloads.is_empty() {
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            "Batch must contain at least one item",
+        ));
+    }
+
+    let leaves = payloads
+        .iter()
+        .enumerate()
+        .map(|(i, payload)| {
+            leaf(payload)
+                .map_err(|e| AshError::new(e.code(), format!("Batch item {}: {}", i, e.message())))
+        })
+        .collect::<Result<Vec<Node>, AshError>>()?;
+
+    let mut levels = vec![leaves];
+    while levels[levels.len() - 1].len() > 1 {
+        let next = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node(left, right),
+                [single] => *single,
+                _ => unreachable!("chunks(2) yields one or two nodes"),
+            })
+            .collect();
+        levels.push(next);
+    }
+
+    Ok(levels)
+}
+
+/// Recompute the root from a leaf and its inclusion path.
+fn root_from_path(leaf: &Node, inclusion: &BatchInclusionProof) -> Result<Node, AshError> {
+    let malformed = |msg: &str| AshError::new(AshErrorCode::MalformedRequest, msg);
+
+    if inclusion.index >= inclusion.item_count {
+        return Err(malformed("Inclusion index is out of range"));
+    }
+
+    let mut siblings = inclusion.siblings.iter();
+    let mut current = *leaf;
+    let mut position = inclusion.index;
+    let mut level_len = inclusion.item_count;
+
+    while level_len > 1 {
+        let sibling_position = position ^ 1;
+        if sibling_position < level_len {
+            let sibling = siblings
+                .next()
+                .ok_or_else(|| malformed("Inclusion proof has too few siblings"))?;
+            let sibling = decode_node(sibling)?;
+            current = if position & 1 == 0 {
+                node(&current, &sibling)
+            } else {
+                node(&sibling, &current)
+            };
+        }
+        position /= 2;
+        level_len = level_len.div_ceil(2);
+    }
+
+    if siblings.next().is_some() {
+        return Err(malformed("Inclusion proof has too many siblings"));
+    }
+    Ok(current)
+}
+
+/// Leaf hash of a payload.
+fn leaf(payload: &str) -> Result<Node, AshError> {
+    let body_hash = decode_node(&hash_body(&canonicalize_json(payload)?))?;
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(body_hash);
+    Ok(hasher.finalize().into())
+}
+
+/// Inner node hash.
+fn node(left: &Node, right: &Node) -> Node {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn decode_node(hex_hash: &str) -> Result<Node, AshError> {
+    let mut out = [0u8; 32];
+    hex::decode_to_slice(hex_hash, &mut out).map_err(|_| {
+        AshError::new(
+            AshErrorCode::MalformedRequest,
+            "Batch hashes must be 64 hex characters",
+        )
+    })?;
+    Ok(out)
+}
+
+/// HMAC over `timestamp|binding|root|itemCount`.
+fn sign_root(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    root: &str,
+    item_count: usize,
+) -> String {
+    let message = format!("{}|{}|{}|{}", timestamp, binding, root, item_count);
+    let mut mac = HmacSha256::new_from_slice(client_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(message.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONCE: &str = "test_nonce_12345";
+    const CONTEXT_ID: &str = "ctx_abc123";
+    const BINDING: &str = "POST /api/bulk";
+    const TIMESTAMP: &str = "1700000000000";
+    const PAYLOADS: [&str; 4] = [
+        r#"{"id":1,"amount":10}"#,
+        r#"{"id":2,"amount":20}"#,
+        r#"{"id":3,"amount":30}"#,
+        r#"{"id":4,"amount":40}"#,
+    ];
+
+    fn batch(payloads: &[&str]) -> BatchProof {
+        let client_secret = derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+        build_batch_proof(&client_secret, TIMESTAMP, BINDING, payloads).unwrap()
+    }
+
+    fn verify_inclusion(batch: &BatchProof, payload: &str, index: usize) -> bool {
+        let inclusion = batch.batch_inclusion_proof(index).unwrap();
+        verify_batch_inclusion(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            TIMESTAMP,
+            &batch.root,
+            &batch.proof,
+            payload,
+            &inclusion,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_batch_proof_verifies_whole_set() {
+        let batch = batch(&PAYLOADS);
+        let verify = |payloads: &[&str]| {
+            verify_batch_proof(
+                NONCE,
+                CONTEXT_ID,
+                BINDING,
+                TIMESTAMP,
+                payloads,
+                &batch.proof,
+            )
+            .unwrap()
+        };
+
+        assert!(verify(&PAYLOADS));
+
+        let mut tampered = PAYLOADS;
+        tampered[2] = r#"{"id":3,"amount":3000}"#;
+        assert!(!verify(&tampered));
+        assert!(!verify(&PAYLOADS[..3]));
+    }
+
+    #[test]
+    fn test_batch_inclusion_valid_and_tampered() {
+        let batch = batch(&PAYLOADS);
+
+        for (i, payload) in PAYLOADS.iter().enumerate() {
+            assert!(verify_inclusion(&batch, payload, i));
+        }
+
+        // Tampered item, and a genuine item claimed at the wrong index
+        assert!(!verify_inclusion(&batch, r#"{"id":2,"amount":2000}"#, 1));
+        assert!(!verify_inclusion(&batch, PAYLOADS[0], 1));
+    }
+
+    #[test]
+    fn test_batch_inclusion_odd_sized_batch() {
+        let batch = batch(&PAYLOADS[..3]);
+
+        for (i, payload) in PAYLOADS[..3].iter().enumerate() {
+            assert!(verify_inclusion(&batch, payload, i));
+        }
+        // The unpaired last item is promoted, so it has one sibling fewer
+        assert_eq!(batch.batch_inclusion_proof(2).unwrap().siblings.len(), 1);
+        assert!(batch.batch_inclusion_proof(3).is_none());
+    }
+
+    #[test]
+    fn test_batch_inclusion_rejects_malformed_path() {
+        let batch = batch(&PAYLOADS);
+        let mut inclusion = batch.batch_inclusion_proof(0).unwrap();
+        inclusion.siblings.pop();
+
+        let err = verify_batch_inclusion(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            TIMESTAMP,
+            &batch.root,
+            &batch.proof,
+            PAYLOADS[0],
+            &inclusion,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+    }
+
+    #[test]
+    fn test_batch_proof_rejects_empty_and_invalid() {
+        let client_secret = derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+
+        let err = build_batch_proof(&client_secret, TIMESTAMP, BINDING, &[]).unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+
+        let err =
+            build_batch_proof(&client_secret, TIMESTAMP, BINDING, &["{}", "{oops"]).unwrap_err();
+        assert!(err.message().starts_with("Batch item 1: "));
+    }
+
+    /// `(body_hash, proof)` for each payload, signed as sent by the client.
+    fn signed_requests(payloads: &[&str]) -> Vec<(String, String)> {
+        let client_secret = derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+        payloads
+            .iter()
+            .map(|payload| {
+                let body_hash = hash_body(payload);
+                let proof =
+                    crate::proof::build_proof_v21(&client_secret, TIMESTAMP, BINDING, &body_hash);
+                (body_hash, proof)
+            })
+            .collect()
+    }
+
+    fn verify_items(signed: &[(String, String)]) -> Result<BatchVerifyResult, AshError> {
+        let items: Vec<_> = signed
+            .iter()
+            .map(|(body_hash, client_proof)| V21VerifyItem {
+                nonce: NONCE,
+                context_id: CONTEXT_ID,
+                binding: BINDING,
+                timestamp: TIMESTAMP,
+                body_hash,
+                client_proof,
+            })
+            .collect();
+        verify_proof_v21_batch(&items)
+    }
+
+    #[test]
+    fn test_verify_proof_v21_batch_all_valid() {
+        let result = verify_items(&signed_requests(&PAYLOADS)).unwrap();
+        assert_eq!(result, BatchVerifyResult::AllValid);
+        assert!(result.is_valid());
+    }
+
+    #[test]
+    fn test_verify_proof_v21_batch_reports_first_failure() {
+        let mut signed = signed_requests(&PAYLOADS);
+        // Item 1's body was tampered with after signing; item 3 is malformed
+        signed[1].0 = hash_body(r#"{"id":2,"amount":2000}"#);
+        signed[3].1 = "not-a-proof".to_string();
+
+        assert_eq!(
+            verify_items(&signed).unwrap(),
+            BatchVerifyResult::Failed {
+                index: 1,
+                reason: AshErrorCode::IntegrityFailed
+            }
+        );
+
+        signed[1] = signed_requests(&PAYLOADS)[1].clone();
+        let result = verify_items(&signed).unwrap();
+        assert!(!result.is_valid());
+        assert!(
+            matches!(result, BatchVerifyResult::Failed { index: 3, reason }
+            if reason != AshErrorCode::IntegrityFailed)
+        );
+
+        let err = verify_proof_v21_batch(&[]).unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+    }
+}
@@ -0,0 +1,142 @@
+//! Memoizing canonicalizer for servers that see the same payloads repeatedly.
+//!
+//! Enabled with the `cache` feature. Cached entries keep the original input
+//! and its canonical form in memory, so only enable it where holding request
+//! payloads is acceptable.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use lru::LruCache;
+
+use crate::canonicalize::canonicalize_json;
+use crate::errors::AshError;
+
+/// LRU cache in front of [`canonicalize_json`].
+///
+/// Entries are keyed by a fast 64-bit hash of the input. The original input
+/// is stored alongside the output and compared on every hit, so a hash
+/// collision falls back to canonicalizing instead of returning another
+/// payload's canonical form. Errors are never cached.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::CachingCanonicalizer;
+///
+/// let cache = CachingCanonicalizer::new(128);
+/// assert_eq!(cache.canonicalize_json(r#"{"z":1,"a":2}"#).unwrap(), r#"{"a":2,"z":1}"#);
+/// assert_eq!(cache.len(), 1);
+/// ```
+pub struct CachingCanonicalizer {
+    entries: Mutex<LruCache<u64, (String, String)>>,
+}
+
+impl CachingCanonicalizer {
+    /// Create a cache holding at most `capacity` payloads (minimum 1).
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Canonicalize `input`, returning the cached output when available.
+    ///
+    /// The output is identical to [`canonicalize_json`].
+    pub fn canonicalize_json(&self, input: &str) -> Result<String, AshError> {
+        let key = pre_hash(input);
+
+        if let Some((cached_input, canonical)) = self.lock().get(&key) {
+            if cached_input == input {
+                return Ok(canonical.clone());
+            }
+        }
+
+        // Canonicalize outside the lock so slow payloads don't block hits.
+        let canonical = canonicalize_json(input)?;
+        self.lock().put(key, (input.to_string(), canonical.clone()));
+        Ok(canonical)
+    }
+
+    /// Number of cached payloads.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Drop all cached payloads.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<u64, (String, String)>> {
+        // The cache holds no invariants a panicking holder could break.
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn pre_hash(input: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    input.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_repeated_calls_identical() {
+        let cache = CachingCanonicalizer::new(4);
+        let input = r#"{"z":1,"a":{"c":true,"b":null}}"#;
+
+        let first = cache.canonicalize_json(input).unwrap();
+        let second = cache.canonicalize_json(input).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first, canonicalize_json(input).unwrap());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_evicts_at_capacity() {
+        let cache = CachingCanonicalizer::new(2);
+        cache.canonicalize_json(r#"{"a":1}"#).unwrap();
+        cache.canonicalize_json(r#"{"a":2}"#).unwrap();
+        // Touch the first entry so the second becomes least recently used.
+        cache.canonicalize_json(r#"{"a":1}"#).unwrap();
+        cache.canonicalize_json(r#"{"a":3}"#).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        let entries = cache.lock();
+        assert!(entries.contains(&pre_hash(r#"{"a":1}"#)));
+        assert!(!entries.contains(&pre_hash(r#"{"a":2}"#)));
+        assert!(entries.contains(&pre_hash(r#"{"a":3}"#)));
+    }
+
+    #[test]
+    fn test_cache_collision_uses_original_input() {
+        let cache = CachingCanonicalizer::new(2);
+        // Simulate a collision: the slot for this input holds another payload.
+        let input = r#"{"b":2}"#;
+        cache.lock().put(
+            pre_hash(input),
+            ("other".to_string(), "{\"x\":0}".to_string()),
+        );
+
+        assert_eq!(cache.canonicalize_json(input).unwrap(), r#"{"b":2}"#);
+    }
+
+    #[test]
+    fn test_cache_errors_not_cached() {
+        let cache = CachingCanonicalizer::new(2);
+        assert!(cache.canonicalize_json("{invalid").is_err());
+        assert!(cache.is_empty());
+    }
+}
@@ -2,11 +2,239 @@
 //!
 //! This module ensures byte-identical output across all platforms and implementations.
 
+use std::io::Read as _;
+
+use std::collections::BTreeMap;
+
+use serde_json::value::RawValue;
 use serde_json::Value;
 use unicode_normalization::UnicodeNormalization;
 
 use crate::errors::{AshError, AshErrorCode};
 
+/// Unicode normalization applied to JSON strings (keys and values).
+///
+/// # Security
+///
+/// [`Nfkc`](Self::Nfkc) folds compatibility characters (full-width digits,
+/// ligatures, letterlike symbols such as `ⅿ`) into their plain forms. This
+/// makes look-alike identifiers hash identically, but it also means that
+/// two *distinct* strings can produce the same canonical form and the same
+/// proof. Only use it for fields whose meaning should survive that folding
+/// (usernames, account numbers), and apply the same choice on both sides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StringNormalization {
+    /// Keep strings exactly as decoded.
+    NfNone,
+    /// Canonical composition (NFC). The protocol default.
+    #[default]
+    Nfc,
+    /// Compatibility composition (NFKC).
+    Nfkc,
+}
+
+/// How JSON numbers are canonicalized.
+///
+/// # Security
+///
+/// With [`Numeric`](Self::Numeric) (the default) `1`, `1.0`, `1.00` and
+/// `1e0` all produce the same canonical form, so a proof does not pin the
+/// number's type: an intermediary may rewrite `1.0` as `1` without breaking
+/// it. If the server treats integers and floats differently (strict schema
+/// validation, typed deserialization), use [`PreserveType`](Self::PreserveType)
+/// on both sides. Clients must then emit the type the server expects, which
+/// JavaScript's `JSON.stringify` (no integer/float distinction) cannot do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberMode {
+    /// Collapse every representation of the same value: whole floats are
+    /// written as integers and `-0` becomes `0`. The protocol default.
+    #[default]
+    Numeric,
+    /// Keep integers and floats distinct: floats always keep a fractional
+    /// part (`1.0`), integers never have one (`1`). Zero is the exception
+    /// and is always `0`: the parser reads the integer `-0` as a float, so
+    /// its type cannot be preserved.
+    PreserveType,
+}
+
+/// Collation used to order object keys.
+///
+/// The default, [`Utf8Bytes`](Self::Utf8Bytes), is what every ASH SDK uses:
+/// the JavaScript SDK canonicalizes through the WASM build of this crate.
+/// [`Utf16CodeUnits`](Self::Utf16CodeUnits) matches JavaScript's default
+/// `Array.prototype.sort` (and RFC 8785), for peers that canonicalize in
+/// plain JS. The two only differ when keys mix characters above U+FFFF
+/// (emoji, `𝕏`; surrogate pairs in UTF-16) with characters in
+/// U+E000..=U+FFFF. [`CanonicalizeOptions::set_fields`] elements follow the
+/// same collation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeySort {
+    /// Compare keys by their UTF-8 bytes (equivalently, by code point).
+    #[default]
+    Utf8Bytes,
+    /// Compare keys by their UTF-16 code units.
+    Utf16CodeUnits,
+}
+
+/// Handling of invisible formatting characters in JSON strings.
+///
+/// Zero-width and bidirectional control characters survive NFC and NFKC,
+/// so `"admin"` and `"ad\u{200B}min"` look identical yet hash differently,
+/// and U+202E can make `"txt.exe"` render as `"exe.txt"`. The targeted
+/// code points are:
+///
+/// - U+200B zero width space
+/// - U+200E / U+200F left-to-right / right-to-left mark
+/// - U+202A..=U+202E bidi embeddings and overrides (LRE, RLE, PDF, LRO, RLO)
+/// - U+2060 word joiner
+/// - U+2066..=U+2069 bidi isolates (LRI, RLI, FSI, PDI)
+/// - U+061C Arabic letter mark
+/// - U+FEFF zero width no-break space (BOM)
+///
+/// U+200C / U+200D (zero width non-joiner / joiner) are left alone: they
+/// are required in Persian and Indic text and in emoji sequences.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvisibleChars {
+    /// Keep them. The protocol default.
+    #[default]
+    Keep,
+    /// Remove them from keys and values before normalization. An object
+    /// whose keys collide once stripped (`"amount"` and `"amount\u{200B}"`)
+    /// is rejected rather than merged.
+    Strip,
+    /// Reject any key or value containing one.
+    Reject,
+}
+
+/// Zero-width and bidi control characters targeted by [`InvisibleChars`].
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}'
+            | '\u{200E}'
+            | '\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{061C}'
+            | '\u{FEFF}'
+    )
+}
+
+/// Default input size limit for canonicalization (8 MiB).
+///
+/// Inputs longer than this are rejected before parsing so an oversized body
+/// cannot exhaust the verifier's memory.
+pub const DEFAULT_MAX_INPUT_BYTES: usize = 8 * 1024 * 1024;
+
+/// Reject `len` bytes of input when it exceeds `max`.
+fn check_input_size(len: usize, max: usize) -> Result<(), AshError> {
+    if len > max {
+        return Err(AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            format!(
+                "Input too large: {} bytes exceeds the {} byte limit",
+                len, max
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Options controlling JSON canonicalization.
+///
+/// `CanonicalizeOptions::default()` reproduces [`canonicalize_json`].
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::{canonicalize_json_with_options, CanonicalizeOptions};
+///
+/// let options = CanonicalizeOptions {
+///     reject_control_chars: true,
+///     ..Default::default()
+/// };
+/// assert!(canonicalize_json_with_options(r#"{"a":"x\u0000y"}"#, &options).is_err());
+/// ```
+///
+/// **Compatibility:** the struct is no longer `Copy` since it gained the
+/// `Vec` path lists (`numeric_string_fields` and later fields). Code that
+/// passed options by value more than once must now `.clone()` them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalizeOptions {
+    /// Reject strings (values and keys) containing C0 control characters
+    /// other than tab, line feed and carriage return.
+    ///
+    /// When `false`, control characters are kept and always serialized as
+    /// JSON escapes (`\u0000`, `\b`, ...), never as raw bytes.
+    pub reject_control_chars: bool,
+    /// Unicode normalization form applied to strings.
+    pub normalization: StringNormalization,
+    /// Integer/float handling for numbers.
+    pub number_mode: NumberMode,
+    /// Object key ordering.
+    pub key_sort: KeySort,
+    /// Largest accepted input in bytes ([`DEFAULT_MAX_INPUT_BYTES`] by
+    /// default). Raise it only for trusted internal callers.
+    pub max_input_bytes: usize,
+    /// Reject numbers with a fractional part (`10.5`), for endpoints whose
+    /// amounts must be integers (e.g. cents). Integral floats such as
+    /// `10.0` are still accepted.
+    pub reject_fractional_numbers: bool,
+    /// Scope paths (`user.id`, `items[0].id`) of fields carrying integers
+    /// as JSON strings, e.g. 64-bit IDs sent as strings for JavaScript.
+    ///
+    /// Each listed field that is present must be a non-empty string of
+    /// ASCII digits; leading zeros are stripped so `"007"` and `"7"`
+    /// canonicalize identically. Missing fields are left alone.
+    ///
+    /// Only canonicalization applies it: scoped hashing
+    /// ([`hash_scoped_body_with_options`](crate::hash_scoped_body_with_options))
+    /// copies values unchanged. To scope such a body, canonicalize it with
+    /// these options first and hash the scoped fields of the result.
+    pub numeric_string_fields: Vec<String>,
+    /// Scope paths (`roles`, `user.tags`) of arrays with set semantics.
+    ///
+    /// Arrays are ordered by default: `["a","b"]` and `["b","a"]` are
+    /// different payloads. Each listed array that is present has its
+    /// elements sorted by their canonical serialization, so any order a
+    /// client sends canonicalizes identically. Duplicates are kept. Missing
+    /// fields are left alone; a listed field that is not an array is an
+    /// error.
+    pub set_fields: Vec<String>,
+    /// Scope paths (`created_at`, `items[0].due`) of timestamp fields.
+    ///
+    /// Each listed field that is present must be an RFC 3339 date-time
+    /// string (any offset, optional fraction up to nanoseconds) or an
+    /// integer of Unix epoch milliseconds. It is rewritten as UTC RFC 3339
+    /// with a `Z` suffix and the fraction trimmed of trailing zeros, so
+    /// `"2024-01-01T03:00:00+03:00"`, `"2024-01-01T00:00:00.000Z"` and
+    /// `1704067200000` all become `"2024-01-01T00:00:00Z"`. Unparseable
+    /// values (including leap seconds and years outside 0000..=9999) are an
+    /// error; missing fields are left alone.
+    pub timestamp_fields: Vec<String>,
+    /// Zero-width and bidi control characters in keys and values: kept
+    /// (default), stripped or rejected. Apply the same choice on both sides.
+    pub invisible_chars: InvisibleChars,
+}
+
+impl Default for CanonicalizeOptions {
+    fn default() -> Self {
+        Self {
+            reject_control_chars: false,
+            normalization: StringNormalization::default(),
+            number_mode: NumberMode::default(),
+            key_sort: KeySort::default(),
+            max_input_bytes: DEFAULT_MAX_INPUT_BYTES,
+            reject_fractional_numbers: false,
+            numeric_string_fields: Vec::new(),
+            set_fields: Vec::new(),
+            timestamp_fields: Vec::new(),
+            invisible_chars: InvisibleChars::Keep,
+        }
+    }
+}
+
 /// Canonicalize a JSON string to deterministic form.
 ///
 /// # Canonicalization Rules
@@ -18,8 +246,13 @@ use crate::errors::{AshError, AshErrorCode};
 /// 5. **Numbers**:
 ///    - No scientific notation
 ///    - No trailing zeros after decimal
+///    - Whole floats are written as integers (`1.0` becomes `1`)
 ///    - `-0` becomes `0`
 /// 6. **Unsupported Values**: `NaN`, `Infinity` cause rejection
+/// 7. **Byte Order Mark**: a single leading UTF-8 BOM (`U+FEFF`) is
+///    ignored; a BOM inside a string is data and is kept
+/// 8. **Size**: inputs over [`DEFAULT_MAX_INPUT_BYTES`] are rejected (see
+///    [`CanonicalizeOptions::max_input_bytes`])
 ///
 /// # Example
 ///
@@ -36,17 +269,158 @@ use crate::errors::{AshError, AshErrorCode};
 /// Returns `AshError` with `CanonicalizationFailed` if:
 /// - Input is not valid JSON
 /// - JSON contains unsupported values (NaN, Infinity)
+/// - Input is larger than [`DEFAULT_MAX_INPUT_BYTES`]
 pub fn canonicalize_json(input: &str) -> Result<String, AshError> {
+    canonicalize_json_with_options(input, &CanonicalizeOptions::default())
+}
+
+/// Check whether a JSON string is already in canonical form.
+///
+/// Canonicalizes `input` and compares the result byte for byte, so servers
+/// can detect clients that submit non-canonical bodies (extra whitespace,
+/// unsorted keys, `1.0` for `1`, a leading BOM). Canonicalizing canonical
+/// output is always a no-op.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::is_canonical_json;
+///
+/// assert!(is_canonical_json(r#"{"a":1,"b":[true]}"#).unwrap());
+/// assert!(!is_canonical_json(r#"{"b":[true],"a":1}"#).unwrap());
+/// ```
+///
+/// # Errors
+///
+/// Same as [`canonicalize_json`].
+pub fn is_canonical_json(input: &str) -> Result<bool, AshError> {
+    Ok(canonicalize_json(input)? == input)
+}
+
+/// Top-level JSON type required by [`canonicalize_json_typed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonShape {
+    /// A JSON object (`{...}`).
+    Object,
+    /// A JSON array (`[...]`).
+    Array,
+    /// Any JSON value, including bare scalars.
+    Any,
+}
+
+/// Canonicalize JSON that must have a given top-level type.
+///
+/// Same output as [`canonicalize_json`], but rejects documents whose top
+/// level is not `expected`, such as a bare `5` sent to an endpoint that
+/// takes an object.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::{canonicalize_json_typed, JsonShape};
+///
+/// assert_eq!(canonicalize_json_typed(r#"{"b":1,"a":2}"#, JsonShape::Object).unwrap(),
+///            r#"{"a":2,"b":1}"#);
+/// assert!(canonicalize_json_typed("[1,2]", JsonShape::Object).is_err());
+/// ```
+///
+/// # Errors
+///
+/// Same as [`canonicalize_json`], plus `MalformedRequest` when the top-level
+/// type does not match `expected`.
+pub fn canonicalize_json_typed(input: &str, expected: JsonShape) -> Result<String, AshError> {
+    let canonical = canonicalize_json(input)?;
+
+    // Canonical output has no leading whitespace, so the first byte is the type
+    let matches = match expected {
+        JsonShape::Object => canonical.starts_with('{'),
+        JsonShape::Array => canonical.starts_with('['),
+        JsonShape::Any => true,
+    };
+    if !matches {
+        let name = if expected == JsonShape::Object {
+            "object"
+        } else {
+            "array"
+        };
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            format!("Expected a JSON {} at the top level", name),
+        ));
+    }
+
+    Ok(canonical)
+}
+
+/// Canonicalize JSON and hash the result.
+///
+/// Returns `(canonical, body_hash)`, i.e.
+/// `(canonicalize_json(input)?, hash_body(&canonical))`, for callers that
+/// need both.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::{canonicalize_and_digest_json, hash_body};
+///
+/// let (canonical, body_hash) = canonicalize_and_digest_json(r#"{"z":1,"a":2}"#).unwrap();
+/// assert_eq!(canonical, r#"{"a":2,"z":1}"#);
+/// assert_eq!(body_hash, hash_body(&canonical));
+/// ```
+///
+/// # Errors
+///
+/// Same as [`canonicalize_json`].
+pub fn canonicalize_and_digest_json(input: &str) -> Result<(String, String), AshError> {
+    let canonical = canonicalize_json(input)?;
+    let body_hash = crate::proof::hash_body(&canonical);
+    Ok((canonical, body_hash))
+}
+
+/// Canonicalize a JSON string with explicit [`CanonicalizeOptions`].
+///
+/// # Errors
+///
+/// Same as [`canonicalize_json`], plus `CanonicalizationFailed` for any
+/// input rejected by an enabled option.
+pub fn canonicalize_json_with_options(
+    input: &str,
+    options: &CanonicalizeOptions,
+) -> Result<String, AshError> {
+    check_input_size(input.len(), options.max_input_bytes)?;
+
+    // Some HTTP stacks prepend a BOM; it is not part of the JSON text
+    let input = input.strip_prefix('\u{FEFF}').unwrap_or(input);
+
     // Parse JSON
-    let value: Value = serde_json::from_str(input).map_err(|e| {
+    let mut value: Value = serde_json::from_str(input).map_err(|e| {
         AshError::new(
             AshErrorCode::CanonicalizationFailed,
             format!("Invalid JSON: {}", e),
         )
     })?;
 
+    for path in &options.numeric_string_fields {
+        normalize_numeric_string_field(&mut value, path)?;
+    }
+    for path in &options.timestamp_fields {
+        normalize_timestamp_field(&mut value, path)?;
+    }
+
     // Canonicalize recursively
-    let canonical = canonicalize_value(&value)?;
+    let mut canonical = canonicalize_value(&value, options)?;
+
+    for path in &options.set_fields {
+        sort_set_field(&mut canonical, path, options.key_sort)?;
+    }
+
+    // `serde_json::Map` always iterates in UTF-8 order, so UTF-16 key
+    // order needs its own writer
+    if options.key_sort == KeySort::Utf16CodeUnits {
+        let mut out = String::with_capacity(input.len());
+        write_utf16_sorted(&canonical, &mut out);
+        return Ok(out);
+    }
 
     // Serialize to minified JSON
     serde_json::to_string(&canonical).map_err(|e| {
@@ -57,289 +431,2924 @@ pub fn canonicalize_json(input: &str) -> Result<String, AshError> {
     })
 }
 
-/// Recursively canonicalize a JSON value.
-fn canonicalize_value(value: &Value) -> Result<Value, AshError> {
-    match value {
-        Value::Null => Ok(Value::Null),
-        Value::Bool(b) => Ok(Value::Bool(*b)),
-        Value::Number(n) => canonicalize_number(n),
-        Value::String(s) => Ok(Value::String(canonicalize_string(s))),
-        Value::Array(arr) => {
-            let canonical: Result<Vec<Value>, AshError> =
-                arr.iter().map(canonicalize_value).collect();
-            Ok(Value::Array(canonical?))
+/// Field at scope path `path`, or `None` if any segment is missing.
+fn field_at_path_mut<'a>(value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    let mut current = value;
+    for part in path.split('.') {
+        let (key, index) = crate::proof::parse_array_notation(part);
+        let next = match current {
+            Value::Object(map) => map.get_mut(key),
+            _ => None,
+        };
+        current = match (next, index) {
+            (Some(Value::Array(items)), Some(idx)) => items.get_mut(idx)?,
+            (Some(next), None) => next,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+/// Strip leading zeros from the digit string at `path`, if present.
+fn normalize_numeric_string_field(value: &mut Value, path: &str) -> Result<(), AshError> {
+    let Some(current) = field_at_path_mut(value, path) else {
+        return Ok(());
+    };
+
+    let digits = match current {
+        Value::String(s) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => s,
+        _ => {
+            return Err(AshError::canonicalization_failed(&format!(
+                "Field {} must be a string of decimal digits",
+                path
+            )))
         }
-        Value::Object(obj) => {
-            // Sort keys lexicographically
-            let mut sorted: Vec<(&String, &Value)> = obj.iter().collect();
-            sorted.sort_by(|a, b| a.0.cmp(b.0));
+    };
+    let trimmed = digits.trim_start_matches('0');
+    *digits = if trimmed.is_empty() {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    };
+    Ok(())
+}
 
-            let mut canonical = serde_json::Map::new();
-            for (key, val) in sorted {
-                let canonical_key = canonicalize_string(key);
-                let canonical_val = canonicalize_value(val)?;
-                canonical.insert(canonical_key, canonical_val);
+/// Rewrite the timestamp at `path`, if present, as canonical UTC RFC 3339.
+fn normalize_timestamp_field(value: &mut Value, path: &str) -> Result<(), AshError> {
+    let Some(current) = field_at_path_mut(value, path) else {
+        return Ok(());
+    };
+
+    let instant = match current {
+        Value::String(s) => parse_rfc3339(s),
+        Value::Number(n) => n.as_i64().map(|ms| {
+            (
+                ms.div_euclid(1000),
+                (ms.rem_euclid(1000) * 1_000_000) as u32,
+            )
+        }),
+        _ => None,
+    };
+    let formatted = instant.and_then(|(secs, nanos)| format_rfc3339_utc(secs, nanos));
+    let Some(formatted) = formatted else {
+        return Err(AshError::canonicalization_failed(&format!(
+            "Field {} must be an RFC 3339 timestamp or epoch milliseconds",
+            path
+        )));
+    };
+    *current = Value::String(formatted);
+    Ok(())
+}
+
+/// Parse an RFC 3339 date-time into Unix seconds and nanoseconds.
+fn parse_rfc3339(s: &str) -> Option<(i64, u32)> {
+    let b = s.as_bytes();
+    let digits = |range: core::ops::Range<usize>| -> Option<i64> {
+        let part = b.get(range)?;
+        if !part.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        part.iter()
+            .try_fold(0i64, |acc, d| Some(acc * 10 + i64::from(d - b'0')))
+    };
+
+    if b.len() < 20
+        || b[4] != b'-'
+        || b[7] != b'-'
+        || !matches!(b[10], b'T' | b't')
+        || b[13] != b':'
+        || b[16] != b':'
+    {
+        return None;
+    }
+    let (year, month, day) = (digits(0..4)?, digits(5..7)?, digits(8..10)?);
+    let (hour, minute, second) = (digits(11..13)?, digits(14..16)?, digits(17..19)?);
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+
+    let mut pos = 19;
+    let mut nanos = 0u32;
+    if b[pos] == b'.' {
+        let start = pos + 1;
+        pos = start;
+        while pos < b.len() && b[pos].is_ascii_digit() {
+            pos += 1;
+        }
+        let len = pos - start;
+        if len == 0 || len > 9 {
+            return None;
+        }
+        nanos = (digits(start..pos)? * 10i64.pow((9 - len) as u32)) as u32;
+    }
+
+    let offset_secs = match &b[pos..] {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let (oh, om) = (digits(pos + 1..pos + 3)?, digits(pos + 4..pos + 6)?);
+            if oh > 23 || om > 59 {
+                return None;
+            }
+            let offset = oh * 3600 + om * 60;
+            if *sign == b'+' {
+                offset
+            } else {
+                -offset
             }
-            Ok(Value::Object(canonical))
         }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second - offset_secs;
+    Some((secs, nanos))
+}
+
+/// Format Unix seconds and nanoseconds as `YYYY-MM-DDTHH:MM:SS[.f]Z`.
+fn format_rfc3339_utc(secs: i64, nanos: u32) -> Option<String> {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+
+    let mut out = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    );
+    if nanos > 0 {
+        let fraction = format!("{:09}", nanos);
+        out.push('.');
+        out.push_str(fraction.trim_end_matches('0'));
     }
+    out.push('Z');
+    Some(out)
 }
 
-/// Canonicalize a number value.
-fn canonicalize_number(n: &serde_json::Number) -> Result<Value, AshError> {
-    // Check for special values that shouldn't exist in valid JSON
-    // but handle edge cases
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
 
-    if let Some(i) = n.as_i64() {
-        // Handle -0 case (though rare in integers)
-        if i == 0 {
-            return Ok(Value::Number(serde_json::Number::from(0)));
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian date for a count of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Sort the already-canonical array at `path` by element serialization.
+fn sort_set_field(value: &mut Value, path: &str, key_sort: KeySort) -> Result<(), AshError> {
+    let Some(current) = field_at_path_mut(value, path) else {
+        return Ok(());
+    };
+    let Value::Array(items) = current else {
+        return Err(AshError::canonicalization_failed(&format!(
+            "Field {} must be an array",
+            path
+        )));
+    };
+
+    let mut keyed: Vec<(String, Value)> = items
+        .drain(..)
+        .map(|item| {
+            let key = if key_sort == KeySort::Utf16CodeUnits {
+                let mut out = String::new();
+                write_utf16_sorted(&item, &mut out);
+                out
+            } else {
+                item.to_string()
+            };
+            (key, item)
+        })
+        .collect();
+    if key_sort == KeySort::Utf16CodeUnits {
+        keyed.sort_by(|a, b| cmp_utf16(&a.0, &b.0));
+    } else {
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+    Ok(())
+}
+
+/// Canonicalize many JSON payloads (e.g. the elements of a batch request).
+///
+/// Stops at the first failure; its error keeps the original code and its
+/// message is prefixed with the failing index (`"Batch item 2: ..."`).
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::canonicalize_json_batch;
+///
+/// let out = canonicalize_json_batch(&[r#"{"b":1,"a":2}"#, "[1.50]"]).unwrap();
+/// assert_eq!(out, vec![r#"{"a":2,"b":1}"#, "[1.5]"]);
+///
+/// let err = canonicalize_json_batch(&["{}", "{}", "{oops"]).unwrap_err();
+/// assert!(err.message().starts_with("Batch item 2:"));
+/// ```
+pub fn canonicalize_json_batch(inputs: &[&str]) -> Result<Vec<String>, AshError> {
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            canonicalize_json(input)
+                .map_err(|e| AshError::new(e.code(), format!("Batch item {}: {}", i, e.message())))
+        })
+        .collect()
+}
+
+/// Canonicalize newline-delimited JSON (NDJSON).
+///
+/// Each non-blank line is canonicalized independently with
+/// [`canonicalize_json`] and the results are rejoined with `\n` (no
+/// trailing newline). Blank and whitespace-only lines are dropped; `\r\n`
+/// line endings are accepted.
+///
+/// Stops at the first invalid line; its error keeps the original code and
+/// its message is prefixed with the 1-based line number (`"Line 2: ..."`).
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::canonicalize_ndjson;
+///
+/// let out = canonicalize_ndjson("{\"b\":1,\"a\":2}\n\n[1.50]\n").unwrap();
+/// assert_eq!(out, "{\"a\":2,\"b\":1}\n[1.5]");
+///
+/// let err = canonicalize_ndjson("{}\n{oops}\n{}").unwrap_err();
+/// assert!(err.message().starts_with("Line 2:"));
+/// ```
+pub fn canonicalize_ndjson(input: &str) -> Result<String, AshError> {
+    check_input_size(input.len(), DEFAULT_MAX_INPUT_BYTES)?;
+
+    let mut lines = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
         }
-        return Ok(Value::Number(serde_json::Number::from(i)));
+        let canonical = canonicalize_json(line)
+            .map_err(|e| AshError::new(e.code(), format!("Line {}: {}", i + 1, e.message())))?;
+        lines.push(canonical);
     }
 
-    if let Some(u) = n.as_u64() {
-        return Ok(Value::Number(serde_json::Number::from(u)));
+    Ok(lines.join("\n"))
+}
+
+/// Canonicalize a JSON document from a reader into a writer.
+///
+/// Produces exactly the bytes of [`canonicalize_json`] without building a
+/// `serde_json::Value` tree: arrays are streamed element by element, and
+/// only the members of the object currently being closed are buffered so
+/// their keys can be sorted. Prefer [`canonicalize_json`] for small
+/// payloads; use this for large bodies read from a socket or file.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::canonicalize_json_streaming;
+///
+/// let mut out = Vec::new();
+/// canonicalize_json_streaming(&br#"[{"b":1,"a":2}, 1.0]"#[..], &mut out).unwrap();
+/// assert_eq!(out, br#"[{"a":2,"b":1},1]"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns `CanonicalizationFailed` for the same inputs as
+/// [`canonicalize_json`] (invalid JSON, `NaN`/`Infinity`, nesting deeper
+/// than 128 levels) and for read or write failures. Output may already be
+/// partially written when an error is returned.
+///
+/// The input is never held in memory as a whole, so no
+/// [`max_input_bytes`](CanonicalizeOptions::max_input_bytes) limit applies;
+/// bound the reader (e.g. with [`std::io::Read::take`]) if the total size
+/// matters.
+pub fn canonicalize_json_streaming<R: std::io::Read, W: std::io::Write>(
+    reader: R,
+    mut writer: W,
+) -> Result<(), AshError> {
+    let mut parser = StreamParser {
+        bytes: std::io::BufReader::new(reader).bytes(),
+        peeked: None,
+        depth: 0,
+    };
+
+    // Ignore a single leading UTF-8 BOM, as `canonicalize_json` does
+    if parser.peek()? == Some(0xEF) {
+        parser.next()?;
+        parser.expect(0xBB)?;
+        parser.expect(0xBF)?;
     }
 
-    if let Some(f) = n.as_f64() {
-        // Check for NaN and Infinity
-        if f.is_nan() {
-            return Err(AshError::new(
-                AshErrorCode::CanonicalizationFailed,
-                "NaN is not supported in ASH canonicalization",
-            ));
+    parser.skip_whitespace()?;
+    parser.value(&mut writer)?;
+    parser.skip_whitespace()?;
+    if parser.peek()?.is_some() {
+        return Err(AshError::canonicalization_failed(
+            "Invalid JSON: trailing characters",
+        ));
+    }
+
+    writer.flush().map_err(stream_io_error)
+}
+
+/// Maximum nesting depth, matching `serde_json`'s recursion limit.
+const STREAM_MAX_DEPTH: usize = 128;
+
+/// Pull parser emitting canonical JSON as it reads.
+struct StreamParser<R: std::io::Read> {
+    bytes: std::io::Bytes<std::io::BufReader<R>>,
+    peeked: Option<u8>,
+    depth: usize,
+}
+
+impl<R: std::io::Read> StreamParser<R> {
+    fn peek(&mut self) -> Result<Option<u8>, AshError> {
+        if self.peeked.is_none() {
+            self.peeked = self.bytes.next().transpose().map_err(stream_io_error)?;
         }
-        if f.is_infinite() {
-            return Err(AshError::new(
-                AshErrorCode::CanonicalizationFailed,
-                "Infinity is not supported in ASH canonicalization",
+        Ok(self.peeked)
+    }
+
+    fn next(&mut self) -> Result<u8, AshError> {
+        let byte = self.peek()?.ok_or_else(|| {
+            AshError::canonicalization_failed("Invalid JSON: unexpected end of input")
+        })?;
+        self.peeked = None;
+        Ok(byte)
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), AshError> {
+        if self.next()? == expected {
+            Ok(())
+        } else {
+            Err(AshError::canonicalization_failed(&format!(
+                "Invalid JSON: expected '{}'",
+                expected as char
+            )))
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), AshError> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.peeked = None;
+        }
+        Ok(())
+    }
+
+    /// Parse one value and write its canonical form.
+    fn value<W: std::io::Write>(&mut self, out: &mut W) -> Result<(), AshError> {
+        match self.peek()? {
+            Some(b'{') => self.nested(|p| p.object(out)),
+            Some(b'[') => self.nested(|p| p.array(out)),
+            Some(b'"') => {
+                let s = canonicalize_string(&self.string()?, &CanonicalizeOptions::default())?;
+                write_stream(out, Value::String(s).to_string().as_bytes())
+            }
+            Some(b't') => self.literal(b"true", out),
+            Some(b'f') => self.literal(b"false", out),
+            Some(b'n') => self.literal(b"null", out),
+            Some(b'-' | b'0'..=b'9') => self.number(out),
+            _ => Err(AshError::canonicalization_failed(
+                "Invalid JSON: expected value",
+            )),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<(), AshError>,
+    ) -> Result<(), AshError> {
+        self.depth += 1;
+        if self.depth >= STREAM_MAX_DEPTH {
+            return Err(AshError::canonicalization_failed(
+                "Invalid JSON: recursion limit exceeded",
             ));
         }
+        parse(self)?;
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn array<W: std::io::Write>(&mut self, out: &mut W) -> Result<(), AshError> {
+        self.expect(b'[')?;
+        write_stream(out, b"[")?;
+        self.skip_whitespace()?;
+
+        if self.peek()? == Some(b']') {
+            self.peeked = None;
+            return write_stream(out, b"]");
+        }
+
+        loop {
+            self.skip_whitespace()?;
+            self.value(out)?;
+            self.skip_whitespace()?;
+            match self.next()? {
+                b',' => write_stream(out, b",")?,
+                b']' => return write_stream(out, b"]"),
+                _ => {
+                    return Err(AshError::canonicalization_failed(
+                        "Invalid JSON: expected ',' or ']'",
+                    ))
+                }
+            }
+        }
+    }
 
-        // Handle -0
-        let f = if f == 0.0 && f.is_sign_negative() {
-            0.0
+    fn object<W: std::io::Write>(&mut self, out: &mut W) -> Result<(), AshError> {
+        self.expect(b'{')?;
+        self.skip_whitespace()?;
+
+        // Raw key -> canonical value; later duplicates win, as in serde_json
+        let mut members: std::collections::BTreeMap<String, Vec<u8>> = Default::default();
+
+        if self.peek()? == Some(b'}') {
+            self.peeked = None;
         } else {
-            f
-        };
+            loop {
+                self.skip_whitespace()?;
+                if self.peek()? != Some(b'"') {
+                    return Err(AshError::canonicalization_failed(
+                        "Invalid JSON: expected object key",
+                    ));
+                }
+                let key = self.string()?;
+                self.skip_whitespace()?;
+                self.expect(b':')?;
+                self.skip_whitespace()?;
 
-        // Convert back to Number
-        serde_json::Number::from_f64(f)
-            .map(Value::Number)
-            .ok_or_else(|| {
-                AshError::new(
-                    AshErrorCode::CanonicalizationFailed,
-                    "Failed to canonicalize number",
-                )
-            })
-    } else {
-        Err(AshError::new(
-            AshErrorCode::CanonicalizationFailed,
-            "Unsupported number format",
-        ))
+                let mut value = Vec::new();
+                self.value(&mut value)?;
+                members.insert(key, value);
+
+                self.skip_whitespace()?;
+                match self.next()? {
+                    b',' => {}
+                    b'}' => break,
+                    _ => {
+                        return Err(AshError::canonicalization_failed(
+                            "Invalid JSON: expected ',' or '}'",
+                        ))
+                    }
+                }
+            }
+        }
+
+        // Normalize keys in raw-key order so NFC collisions resolve like
+        // `canonicalize_value`, then emit in canonical key order
+        let mut canonical: std::collections::BTreeMap<String, Vec<u8>> = Default::default();
+        for (key, value) in members {
+            canonical.insert(
+                canonicalize_string(&key, &CanonicalizeOptions::default())?,
+                value,
+            );
+        }
+
+        write_stream(out, b"{")?;
+        for (i, (key, value)) in canonical.into_iter().enumerate() {
+            if i > 0 {
+                write_stream(out, b",")?;
+            }
+            write_stream(out, Value::String(key).to_string().as_bytes())?;
+            write_stream(out, b":")?;
+            write_stream(out, &value)?;
+        }
+        write_stream(out, b"}")
+    }
+
+    /// Parse a string literal, decoding escapes.
+    fn string(&mut self) -> Result<String, AshError> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.next()? {
+                b'"' => break,
+                b'\\' => {
+                    let decoded = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => {
+                            return Err(AshError::canonicalization_failed(
+                                "Invalid JSON: invalid escape",
+                            ))
+                        }
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(decoded.encode_utf8(&mut buf).as_bytes());
+                }
+                0x00..=0x1f => {
+                    return Err(AshError::canonicalization_failed(
+                        "Invalid JSON: control character in string",
+                    ))
+                }
+                byte => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes)
+            .map_err(|_| AshError::canonicalization_failed("Invalid JSON: invalid UTF-8"))
+    }
+
+    /// Decode the code point of a `\u` escape (after the `u`).
+    fn unicode_escape(&mut self) -> Result<char, AshError> {
+        let lone = || AshError::canonicalization_failed("Invalid JSON: lone surrogate");
+
+        let high = self.hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                self.expect(b'\\').map_err(|_| lone())?;
+                self.expect(b'u').map_err(|_| lone())?;
+                let low = self.hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(lone());
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            0xDC00..=0xDFFF => return Err(lone()),
+            _ => high,
+        };
+        char::from_u32(code).ok_or_else(lone)
+    }
+
+    fn hex4(&mut self) -> Result<u32, AshError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = (self.next()? as char).to_digit(16).ok_or_else(|| {
+                AshError::canonicalization_failed("Invalid JSON: invalid unicode escape")
+            })?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn literal<W: std::io::Write>(&mut self, word: &[u8], out: &mut W) -> Result<(), AshError> {
+        for expected in word {
+            if self.next()? != *expected {
+                return Err(AshError::canonicalization_failed(
+                    "Invalid JSON: invalid literal",
+                ));
+            }
+        }
+        write_stream(out, word)
+    }
+
+    /// Read a number token and canonicalize it exactly like the tree path.
+    fn number<W: std::io::Write>(&mut self, out: &mut W) -> Result<(), AshError> {
+        let mut token = String::new();
+        while let Some(byte @ (b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')) = self.peek()? {
+            token.push(byte as char);
+            self.peeked = None;
+        }
+
+        let n: serde_json::Number = serde_json::from_str(&token)
+            .map_err(|e| AshError::canonicalization_failed(&format!("Invalid JSON: {}", e)))?;
+        let canonical = canonicalize_number(&n, &CanonicalizeOptions::default())?;
+        write_stream(out, canonical.to_string().as_bytes())
+    }
+}
+
+fn write_stream<W: std::io::Write>(out: &mut W, bytes: &[u8]) -> Result<(), AshError> {
+    out.write_all(bytes).map_err(stream_io_error)
+}
+
+fn stream_io_error(e: std::io::Error) -> AshError {
+    AshError::canonicalization_failed(&format!("I/O error: {}", e))
+}
+
+/// Recursively canonicalize a JSON value.
+fn canonicalize_value(value: &Value, options: &CanonicalizeOptions) -> Result<Value, AshError> {
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Bool(b) => Ok(Value::Bool(*b)),
+        Value::Number(n) => canonicalize_number(n, options),
+        Value::String(s) => Ok(Value::String(canonicalize_string(s, options)?)),
+        Value::Array(arr) => {
+            let canonical: Result<Vec<Value>, AshError> =
+                arr.iter().map(|v| canonicalize_value(v, options)).collect();
+            Ok(Value::Array(canonical?))
+        }
+        Value::Object(obj) => {
+            // Sort keys lexicographically
+            let mut sorted: Vec<(&String, &Value)> = obj.iter().collect();
+            sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+            let mut canonical = serde_json::Map::new();
+            for (key, val) in sorted {
+                let canonical_key = canonicalize_string(key, options)?;
+                let canonical_val = canonicalize_value(val, options)?;
+                let replaced = canonical.insert(canonical_key, canonical_val);
+
+                // Never let stripping merge two keys and drop a value
+                if replaced.is_some() && options.invisible_chars == InvisibleChars::Strip {
+                    return Err(AshError::canonicalization_failed(
+                        "Object keys collide after stripping invisible characters",
+                    ));
+                }
+            }
+            Ok(Value::Object(canonical))
+        }
+    }
+}
+
+/// Serialize a canonical value with object keys in UTF-16 code unit order.
+fn write_utf16_sorted(value: &Value, out: &mut String) {
+    match value {
+        Value::Array(arr) => {
+            out.push('[');
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_utf16_sorted(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(obj) => {
+            let mut entries: Vec<(&String, &Value)> = obj.iter().collect();
+            entries.sort_by(|a, b| cmp_utf16(a.0, b.0));
+
+            out.push('{');
+            for (i, (key, val)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json_string(key, out);
+                out.push(':');
+                write_utf16_sorted(val, out);
+            }
+            out.push('}');
+        }
+        // Scalars serialize exactly as in the default writer
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Compare strings by UTF-16 code units, as JavaScript's default sort does.
+///
+/// Characters above U+FFFF (emoji, `𝕏`) are one `char` in Rust but a
+/// surrogate pair (`0xD800..=0xDFFF` lead unit) in JavaScript, so they sort
+/// *before* U+E000..=U+FFFF here although their code points are larger.
+fn cmp_utf16(a: &str, b: &str) -> core::cmp::Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+/// Serialize a string as a JSON string literal.
+fn write_json_string(s: &str, out: &mut String) {
+    out.push_str(&Value::String(s.to_string()).to_string());
+}
+
+/// Canonicalize a JSON string per RFC 8785 (JSON Canonicalization Scheme).
+///
+/// This is an interoperability mode for peers that implement JCS; it is
+/// **not** the ASH-native form produced by [`canonicalize_json`]:
+///
+/// - Strings are kept as-is (no Unicode normalization)
+/// - Every number is treated as an IEEE 754 double and serialized the way
+///   ECMAScript `Number.prototype.toString` does (`1e+30`, `0.002`, ...)
+/// - Object keys are sorted by their UTF-16 code units
+/// - Only `"`, `\` and control characters are escaped
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::canonicalize_json_jcs;
+///
+/// let output = canonicalize_json_jcs(r#"{"b":4.50,"a":1E30}"#).unwrap();
+/// assert_eq!(output, r#"{"a":1e+30,"b":4.5}"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns `AshError` with `CanonicalizationFailed` if the input is not
+/// valid JSON (including lone surrogates and numbers outside the double
+/// range).
+pub fn canonicalize_json_jcs(input: &str) -> Result<String, AshError> {
+    check_input_size(input.len(), DEFAULT_MAX_INPUT_BYTES)?;
+
+    let value: &RawValue = parse_jcs(input)?;
+
+    let mut out = String::with_capacity(input.len());
+    write_jcs_value(value, &mut out)?;
+    Ok(out)
+}
+
+/// Parse JCS input, mapping syntax errors to `CanonicalizationFailed`.
+fn parse_jcs<'a, T: serde::Deserialize<'a>>(input: &'a str) -> Result<T, AshError> {
+    serde_json::from_str(input).map_err(|e| {
+        AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            format!("Invalid JSON: {}", e),
+        )
+    })
+}
+
+/// Serialize a raw JSON value in JCS form.
+///
+/// Values are walked as raw text so each number is parsed from its source
+/// digits with correctly rounded `f64` parsing, as RFC 8785 requires. The
+/// native path keeps serde_json's default float parsing, so enabling JCS
+/// never changes [`canonicalize_json`] output.
+fn write_jcs_value(value: &RawValue, out: &mut String) -> Result<(), AshError> {
+    let text = value.get().trim();
+    match text.as_bytes().first() {
+        Some(b'{') => {
+            let obj: BTreeMap<String, &RawValue> = parse_jcs(text)?;
+
+            // Sort keys by UTF-16 code units, not UTF-8 bytes
+            let mut entries: Vec<(&String, &RawValue)> = obj.iter().map(|(k, v)| (k, *v)).collect();
+            entries.sort_by(|a, b| cmp_utf16(a.0, b.0));
+
+            out.push('{');
+            for (i, (key, val)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_jcs_string(key, out);
+                out.push(':');
+                write_jcs_value(val, out)?;
+            }
+            out.push('}');
+        }
+        Some(b'[') => {
+            let arr: Vec<&RawValue> = parse_jcs(text)?;
+
+            out.push('[');
+            for (i, item) in arr.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_jcs_value(item, out)?;
+            }
+            out.push(']');
+        }
+        Some(b'"') => write_jcs_string(&parse_jcs::<String>(text)?, out),
+        Some(b't' | b'f' | b'n') => out.push_str(text),
+        _ => {
+            let f: f64 = text.parse().map_err(|_| {
+                AshError::canonicalization_failed("Number is not representable as a double")
+            })?;
+            out.push_str(&jcs_number(f)?);
+        }
+    }
+    Ok(())
+}
+
+/// Serialize a string with JCS escaping.
+fn write_jcs_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < '\u{20}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Serialize a double like ECMAScript `Number.prototype.toString`.
+fn jcs_number(f: f64) -> Result<String, AshError> {
+    if !f.is_finite() {
+        return Err(AshError::canonicalization_failed(
+            "NaN and Infinity are not valid JSON numbers",
+        ));
+    }
+    if f == 0.0 {
+        // Covers -0
+        return Ok("0".to_string());
+    }
+
+    // Shortest round-trip digits, ties to even (as ECMAScript requires).
+    // serde_json formats floats with Ryu, e.g. "1.4249539237812062e15".
+    let shortest = serde_json::to_string(&f.abs()).map_err(|e| {
+        AshError::canonicalization_failed(&format!("Failed to serialize number: {}", e))
+    })?;
+    let (mantissa, exponent) = shortest.split_once('e').unwrap_or((&shortest, "0"));
+    let exponent: i32 = exponent
+        .parse()
+        .map_err(|_| AshError::canonicalization_failed("Unexpected number format"))?;
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    // ECMAScript: value = 0.digits * 10^n, with k significant digits
+    let all_digits = format!("{}{}", int_part, frac_part);
+    let leading_zeros = all_digits.len() - all_digits.trim_start_matches('0').len();
+    let digits = all_digits.trim_start_matches('0').trim_end_matches('0');
+    let k = digits.len() as i32;
+    let n = int_part.len() as i32 + exponent - leading_zeros as i32;
+
+    let mut out = String::new();
+    if f < 0.0 {
+        out.push('-');
+    }
+
+    if k <= n && n <= 21 {
+        out.push_str(digits);
+        out.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        let (int_part, frac_part) = digits.split_at(n as usize);
+        out.push_str(int_part);
+        out.push('.');
+        out.push_str(frac_part);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat((-n) as usize));
+        out.push_str(digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.push_str(first);
+        if !rest.is_empty() {
+            out.push('.');
+            out.push_str(rest);
+        }
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+
+    Ok(out)
+}
+
+/// Canonicalize a CBOR document to canonical JSON.
+///
+/// Requires the `cbor` feature.
+///
+/// The output is the **canonical JSON projection** of the CBOR data (a
+/// string, not CBOR bytes), produced with exactly the same rules as
+/// [`canonicalize_json`]. A CBOR payload and a JSON payload carrying the
+/// same data therefore canonicalize, hash and prove identically.
+///
+/// # Mapping Rules
+///
+/// - Maps become objects; keys must be text strings and are sorted like JSON keys
+/// - Integers must fit in `i64`/`u64`
+/// - Floats of any width (half, single, double) map to the same JSON number;
+///   `NaN` and `Infinity` are rejected, `-0.0` becomes `0`
+/// - Text strings are NFC normalized
+/// - Byte strings, tags and undefined/simple values are rejected
+///
+/// # Errors
+///
+/// Returns `AshError` with `CanonicalizationFailed` if the input is not a
+/// single well-formed CBOR item or contains unsupported values.
+#[cfg(feature = "cbor")]
+pub fn canonicalize_cbor(input: &[u8]) -> Result<String, AshError> {
+    check_input_size(input.len(), DEFAULT_MAX_INPUT_BYTES)?;
+
+    let mut reader = input;
+    let value: ciborium::Value = ciborium::de::from_reader(&mut reader).map_err(|e| {
+        AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            format!("Invalid CBOR: {}", e),
+        )
+    })?;
+
+    if !reader.is_empty() {
+        return Err(AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            "Invalid CBOR: trailing data after first item",
+        ));
+    }
+
+    let json = cbor_to_json(value)?;
+    let canonical = canonicalize_value(&json, &CanonicalizeOptions::default())?;
+
+    serde_json::to_string(&canonical).map_err(|e| {
+        AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            format!("Failed to serialize: {}", e),
+        )
+    })
+}
+
+/// Convert a CBOR value into its JSON data model equivalent.
+#[cfg(feature = "cbor")]
+fn cbor_to_json(value: ciborium::Value) -> Result<Value, AshError> {
+    use ciborium::Value as Cbor;
+
+    let unsupported = |what: &str| {
+        AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            format!("CBOR {} is not supported in ASH canonicalization", what),
+        )
+    };
+
+    match value {
+        Cbor::Null => Ok(Value::Null),
+        Cbor::Bool(b) => Ok(Value::Bool(b)),
+        Cbor::Text(s) => Ok(Value::String(s)),
+        Cbor::Integer(i) => {
+            let i = i128::from(i);
+            if let Ok(n) = i64::try_from(i) {
+                Ok(Value::Number(n.into()))
+            } else if let Ok(n) = u64::try_from(i) {
+                Ok(Value::Number(n.into()))
+            } else {
+                Err(unsupported("integer outside 64-bit range"))
+            }
+        }
+        Cbor::Float(f) => {
+            if f.is_nan() {
+                return Err(AshError::new(
+                    AshErrorCode::CanonicalizationFailed,
+                    "NaN is not supported in ASH canonicalization",
+                ));
+            }
+            if f.is_infinite() {
+                return Err(AshError::new(
+                    AshErrorCode::CanonicalizationFailed,
+                    "Infinity is not supported in ASH canonicalization",
+                ));
+            }
+            serde_json::Number::from_f64(f)
+                .map(Value::Number)
+                .ok_or_else(|| unsupported("float"))
+        }
+        Cbor::Array(items) => items
+            .into_iter()
+            .map(cbor_to_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Cbor::Map(entries) => {
+            let mut map = serde_json::Map::new();
+            for (key, val) in entries {
+                let key = match key {
+                    Cbor::Text(k) => k,
+                    _ => return Err(unsupported("non-text map key")),
+                };
+                if map.insert(key, cbor_to_json(val)?).is_some() {
+                    return Err(unsupported("duplicate map key"));
+                }
+            }
+            Ok(Value::Object(map))
+        }
+        Cbor::Bytes(_) => Err(unsupported("byte string")),
+        Cbor::Tag(_, _) => Err(unsupported("tag")),
+        _ => Err(unsupported("simple value")),
+    }
+}
+
+/// Canonicalize relaxed JSON5 input to strict canonical JSON.
+///
+/// Requires the `json5` feature. Accepts JSON5 syntax (comments, trailing
+/// commas, unquoted keys, single-quoted strings, hex integers) and emits
+/// exactly what [`canonicalize_json`] produces for the equivalent strict
+/// JSON. This is **input-side only**: the canonical form, and therefore
+/// the body hash, is always strict JSON, so clients must prove over that
+/// same strict canonical form.
+///
+/// # Errors
+///
+/// Returns `AshError` with `CanonicalizationFailed` if the input is not
+/// valid JSON5, contains `NaN` or `Infinity`, has an integer outside the
+/// `i64` range, or is larger than [`DEFAULT_MAX_INPUT_BYTES`].
+#[cfg(feature = "json5")]
+pub fn canonicalize_json5(input: &str) -> Result<String, AshError> {
+    check_input_size(input.len(), DEFAULT_MAX_INPUT_BYTES)?;
+    let input = input.strip_prefix('\u{FEFF}').unwrap_or(input);
+
+    let Json5Value(value) = json5::from_str(input).map_err(|e| {
+        AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            format!("Invalid JSON5: {}", e),
+        )
+    })?;
+    let canonical = canonicalize_value(&value, &CanonicalizeOptions::default())?;
+
+    serde_json::to_string(&canonical).map_err(|e| {
+        AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            format!("Failed to serialize: {}", e),
+        )
+    })
+}
+
+/// JSON value deserialized from JSON5, rejecting non-finite numbers.
+///
+/// `serde_json::Value` would silently turn JSON5 `NaN` and `Infinity` into
+/// `null`.
+#[cfg(feature = "json5")]
+struct Json5Value(Value);
+
+#[cfg(feature = "json5")]
+impl<'de> serde::Deserialize<'de> for Json5Value {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, MapAccess, SeqAccess, Visitor};
+
+        struct Json5Visitor;
+
+        impl<'de> Visitor<'de> for Json5Visitor {
+            type Value = Json5Value;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a JSON5 value")
+            }
+
+            fn visit_unit<E: Error>(self) -> Result<Json5Value, E> {
+                Ok(Json5Value(Value::Null))
+            }
+
+            fn visit_bool<E: Error>(self, v: bool) -> Result<Json5Value, E> {
+                Ok(Json5Value(Value::Bool(v)))
+            }
+
+            fn visit_i64<E: Error>(self, v: i64) -> Result<Json5Value, E> {
+                Ok(Json5Value(Value::from(v)))
+            }
+
+            fn visit_u64<E: Error>(self, v: u64) -> Result<Json5Value, E> {
+                Ok(Json5Value(Value::from(v)))
+            }
+
+            fn visit_f64<E: Error>(self, v: f64) -> Result<Json5Value, E> {
+                serde_json::Number::from_f64(v)
+                    .map(|n| Json5Value(Value::Number(n)))
+                    .ok_or_else(|| E::custom("NaN and Infinity are not supported"))
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Json5Value, E> {
+                Ok(Json5Value(Value::String(v.to_string())))
+            }
+
+            fn visit_string<E: Error>(self, v: String) -> Result<Json5Value, E> {
+                Ok(Json5Value(Value::String(v)))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Json5Value, A::Error> {
+                let mut items = Vec::new();
+                while let Some(Json5Value(item)) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(Json5Value(Value::Array(items)))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Json5Value, A::Error> {
+                let mut object = serde_json::Map::new();
+                while let Some((key, Json5Value(value))) = map.next_entry::<String, Json5Value>()? {
+                    object.insert(key, value);
+                }
+                Ok(Json5Value(Value::Object(object)))
+            }
+        }
+
+        deserializer.deserialize_any(Json5Visitor)
+    }
+}
+
+/// Canonicalize a protobuf message to a canonical JSON-like string.
+///
+/// Requires the `protobuf` feature. `input` is the binary encoding of a
+/// message described by `descriptor`.
+///
+/// # Mapping Rules
+///
+/// - Messages become objects keyed by **field number** (`{"1":...,"2":...}`),
+///   in ascending field-number order, so renaming a field in the schema does
+///   not change the canonical form
+/// - Fields are included exactly when they are present on the wire: unset
+///   fields, implicit-presence (proto3) fields holding their default value
+///   and empty repeated/map fields are omitted; explicit-presence fields
+///   (`optional`, oneof members, messages) are kept even when default
+/// - Repeated fields become arrays in wire order
+/// - Map fields become objects with stringified keys, sorted by key value
+///   (numerically for integer keys)
+/// - Integers and enums are written as numbers, floats with the same rules
+///   as [`canonicalize_json`] (`NaN` and `Infinity` are rejected)
+/// - Strings are NFC normalized; bytes are standard base64 strings
+///
+/// # Unknown Fields
+///
+/// Fields not described by `descriptor` (unknown fields and extensions)
+/// are **rejected** rather than dropped: dropping them would leave data the
+/// server may still act on outside the proof.
+///
+/// # Errors
+///
+/// Returns `AshError` with `CanonicalizationFailed` if the input does not
+/// decode as the described message or contains unknown fields, extensions
+/// or unsupported floats.
+#[cfg(feature = "protobuf")]
+pub fn canonicalize_protobuf(
+    input: &[u8],
+    descriptor: &prost_reflect::MessageDescriptor,
+) -> Result<String, AshError> {
+    check_input_size(input.len(), DEFAULT_MAX_INPUT_BYTES)?;
+
+    let message =
+        prost_reflect::DynamicMessage::decode(descriptor.clone(), input).map_err(|e| {
+            AshError::new(
+                AshErrorCode::CanonicalizationFailed,
+                format!("Invalid protobuf: {}", e),
+            )
+        })?;
+
+    let mut out = String::new();
+    write_proto_message(&message, &mut out)?;
+    Ok(out)
+}
+
+/// Serialize a dynamic protobuf message in field-number order.
+#[cfg(feature = "protobuf")]
+fn write_proto_message(
+    message: &prost_reflect::DynamicMessage,
+    out: &mut String,
+) -> Result<(), AshError> {
+    use prost_reflect::ReflectMessage;
+
+    if message.unknown_fields().next().is_some() || message.extensions().next().is_some() {
+        return Err(AshError::canonicalization_failed(&format!(
+            "Protobuf message {} contains unknown fields",
+            message.descriptor().full_name()
+        )));
+    }
+
+    let mut fields: Vec<_> = message.fields().collect();
+    fields.sort_by_key(|(field, _)| field.number());
+
+    out.push('{');
+    for (i, (field, value)) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!("\"{}\":", field.number()));
+        write_proto_value(value, out)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+/// Serialize a single protobuf field value.
+#[cfg(feature = "protobuf")]
+fn write_proto_value(value: &prost_reflect::Value, out: &mut String) -> Result<(), AshError> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use prost_reflect::{MapKey, Value as Proto};
+
+    match value {
+        Proto::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Proto::I32(n) | Proto::EnumNumber(n) => out.push_str(&n.to_string()),
+        Proto::I64(n) => out.push_str(&n.to_string()),
+        Proto::U32(n) => out.push_str(&n.to_string()),
+        Proto::U64(n) => out.push_str(&n.to_string()),
+        // Shortest f32 representation, not the widened f64 digits
+        Proto::F32(f) => write_proto_float(f.to_string().parse().unwrap_or(f64::NAN), out)?,
+        Proto::F64(f) => write_proto_float(*f, out)?,
+        Proto::String(s) => write_json_string(&s.nfc().collect::<String>(), out),
+        Proto::Bytes(bytes) => write_json_string(&STANDARD.encode(bytes), out),
+        Proto::Message(message) => write_proto_message(message, out)?,
+        Proto::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_proto_value(item, out)?;
+            }
+            out.push(']');
+        }
+        Proto::Map(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            out.push('{');
+            for (i, (key, val)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let key = match key {
+                    MapKey::Bool(b) => b.to_string(),
+                    MapKey::I32(n) => n.to_string(),
+                    MapKey::I64(n) => n.to_string(),
+                    MapKey::U32(n) => n.to_string(),
+                    MapKey::U64(n) => n.to_string(),
+                    MapKey::String(s) => s.nfc().collect(),
+                };
+                write_json_string(&key, out);
+                out.push(':');
+                write_proto_value(val, out)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+/// Serialize a protobuf float with the JSON number rules.
+#[cfg(feature = "protobuf")]
+fn write_proto_float(f: f64, out: &mut String) -> Result<(), AshError> {
+    let n = serde_json::Number::from_f64(f).ok_or_else(|| {
+        AshError::canonicalization_failed("NaN and Infinity are not supported in protobuf floats")
+    })?;
+    let canonical = canonicalize_number(&n, &CanonicalizeOptions::default())?;
+    out.push_str(&canonical.to_string());
+    Ok(())
+}
+
+/// Canonicalize a number value.
+fn canonicalize_number(
+    n: &serde_json::Number,
+    options: &CanonicalizeOptions,
+) -> Result<Value, AshError> {
+    // Check for special values that shouldn't exist in valid JSON
+    // but handle edge cases
+
+    if let Some(i) = n.as_i64() {
+        // Handle -0 case (though rare in integers)
+        if i == 0 {
+            return Ok(Value::Number(serde_json::Number::from(0)));
+        }
+        return Ok(Value::Number(serde_json::Number::from(i)));
+    }
+
+    if let Some(u) = n.as_u64() {
+        return Ok(Value::Number(serde_json::Number::from(u)));
+    }
+
+    if let Some(f) = n.as_f64() {
+        // Check for NaN and Infinity
+        if f.is_nan() {
+            return Err(AshError::new(
+                AshErrorCode::CanonicalizationFailed,
+                "NaN is not supported in ASH canonicalization",
+            ));
+        }
+        if f.is_infinite() {
+            return Err(AshError::new(
+                AshErrorCode::CanonicalizationFailed,
+                "Infinity is not supported in ASH canonicalization",
+            ));
+        }
+
+        if options.reject_fractional_numbers && f.fract() != 0.0 {
+            return Err(AshError::new(
+                AshErrorCode::CanonicalizationFailed,
+                "Fractional numbers are not allowed",
+            ));
+        }
+
+        // Zero is always the integer `0`: `-0` parses as a float, so `0`,
+        // `-0`, `0.0` and `-0.0` could otherwise canonicalize differently
+        if f == 0.0 {
+            return Ok(Value::Number(serde_json::Number::from(0)));
+        }
+
+        // Whole floats in i64 range collapse to the integer form
+        const I64_BOUND: f64 = 9_223_372_036_854_775_808.0; // 2^63
+        if options.number_mode == NumberMode::Numeric
+            && f.fract() == 0.0
+            && (-I64_BOUND..I64_BOUND).contains(&f)
+        {
+            return Ok(Value::Number(serde_json::Number::from(f as i64)));
+        }
+
+        // Convert back to Number
+        serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .ok_or_else(|| {
+                AshError::new(
+                    AshErrorCode::CanonicalizationFailed,
+                    "Failed to canonicalize number",
+                )
+            })
+    } else {
+        Err(AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            "Unsupported number format",
+        ))
+    }
+}
+
+/// Canonicalize a string with the configured Unicode normalization.
+fn canonicalize_string(s: &str, options: &CanonicalizeOptions) -> Result<String, AshError> {
+    if options.reject_control_chars {
+        if let Some(c) = s.chars().find(|c| is_rejected_control(*c)) {
+            return Err(AshError::canonicalization_failed(&format!(
+                "String contains control character U+{:04X}",
+                c as u32
+            )));
+        }
+    }
+    let stripped;
+    let s = match options.invisible_chars {
+        InvisibleChars::Keep => s,
+        InvisibleChars::Strip => {
+            stripped = s.chars().filter(|c| !is_invisible(*c)).collect::<String>();
+            stripped.as_str()
+        }
+        InvisibleChars::Reject => {
+            if let Some(c) = s.chars().find(|c| is_invisible(*c)) {
+                return Err(AshError::canonicalization_failed(&format!(
+                    "String contains invisible character U+{:04X}",
+                    c as u32
+                )));
+            }
+            s
+        }
+    };
+    Ok(match options.normalization {
+        StringNormalization::NfNone => s.to_string(),
+        StringNormalization::Nfc => s.nfc().collect(),
+        StringNormalization::Nfkc => s.nfkc().collect(),
+    })
+}
+
+/// C0 control characters, excluding the whitespace controls tab, LF and CR.
+fn is_rejected_control(c: char) -> bool {
+    c <= '\u{1f}' && !matches!(c, '\t' | '\n' | '\r')
+}
+
+/// Handling of malformed percent sequences in URL-encoded input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PercentMode {
+    /// Reject any `%` not followed by two hex digits. The default.
+    #[default]
+    Strict,
+    /// Keep a `%` that cannot be decoded as a literal `%` (re-encoded as
+    /// `%25`), so `a=50%` canonicalizes to `a=50%25`.
+    ///
+    /// This weakens determinism when inputs are inconsistent: `50%` and
+    /// `50%25` canonicalize identically, so a proof cannot tell which one
+    /// the client sent. Enable it only for upstreams known to send stray
+    /// `%` characters, and on both sides.
+    Lenient,
+}
+
+/// Pair separators accepted in URL-encoded input.
+///
+/// Output is always joined with `&`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PairSeparators {
+    /// Split on `&` only; `;` is a literal character. The default.
+    #[default]
+    Ampersand,
+    /// Split on `;` only (legacy APIs); `&` is a literal character.
+    Semicolon,
+    /// Split on either `&` or `;`.
+    Both,
+}
+
+impl PairSeparators {
+    fn matches(self, c: char) -> bool {
+        match self {
+            PairSeparators::Ampersand => c == '&',
+            PairSeparators::Semicolon => c == ';',
+            PairSeparators::Both => c == '&' || c == ';',
+        }
+    }
+}
+
+/// Case handling when sorting URL-encoded keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyCase {
+    /// Sort keys by their bytes, so `Name` sorts before `name`. The default.
+    #[default]
+    Sensitive,
+    /// Sort keys by their lowercased form, so `Name` and `name` sort
+    /// together. Keys are emitted with their original case.
+    ///
+    /// Keys that differ only by case compare equal, so they keep their
+    /// input order (like duplicate keys): `Name=1&name=2` and
+    /// `name=2&Name=1` canonicalize differently. Enable
+    /// [`sort_duplicate_values`](UrlEncodedOptions::sort_duplicate_values)
+    /// to order them by original key instead.
+    Insensitive,
+}
+
+/// Options controlling URL-encoded canonicalization.
+///
+/// `UrlEncodedOptions::default()` reproduces [`canonicalize_urlencoded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UrlEncodedOptions {
+    /// Also sort the values of duplicate keys, so `a=2&a=1` and `a=1&a=2`
+    /// canonicalize identically.
+    ///
+    /// Defaults to `false`: duplicate values keep their input order, which
+    /// order-sensitive APIs rely on. Enable it when intermediaries may
+    /// reorder repeated parameters.
+    pub sort_duplicate_values: bool,
+    /// Malformed percent sequence handling.
+    pub percent_mode: PercentMode,
+    /// Characters that separate pairs (`&` by default).
+    pub separators: PairSeparators,
+    /// Case handling when sorting keys.
+    pub key_case: KeyCase,
+    /// Largest accepted input in bytes ([`DEFAULT_MAX_INPUT_BYTES`] by
+    /// default). Raise it only for trusted internal callers.
+    pub max_input_bytes: usize,
+}
+
+impl Default for UrlEncodedOptions {
+    fn default() -> Self {
+        Self {
+            sort_duplicate_values: false,
+            percent_mode: PercentMode::default(),
+            separators: PairSeparators::default(),
+            key_case: KeyCase::default(),
+            max_input_bytes: DEFAULT_MAX_INPUT_BYTES,
+        }
+    }
+}
+
+/// Canonicalize URL-encoded form data.
+///
+/// # Canonicalization Rules
+///
+/// 1. Parse key=value pairs (split on `&` by default, then on first `=`)
+/// 2. Percent-decode all values
+/// 3. Apply Unicode NFC normalization
+/// 4. Sort pairs by key lexicographically
+/// 5. For duplicate keys, preserve value order (see [`UrlEncodedOptions`])
+/// 6. Re-encode with percent encoding
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::canonicalize_urlencoded;
+///
+/// let input = "z=3&a=1&a=2&b=hello%20world";
+/// let output = canonicalize_urlencoded(input).unwrap();
+/// assert_eq!(output, "a=1&a=2&b=hello%20world&z=3");
+/// ```
+pub fn canonicalize_urlencoded(input: &str) -> Result<String, AshError> {
+    canonicalize_urlencoded_with_options(input, &UrlEncodedOptions::default())
+}
+
+/// Canonicalize URL-encoded form data with explicit [`UrlEncodedOptions`].
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::{canonicalize_urlencoded_with_options, UrlEncodedOptions};
+///
+/// let options = UrlEncodedOptions {
+///     sort_duplicate_values: true,
+///     ..Default::default()
+/// };
+/// let output = canonicalize_urlencoded_with_options("b=x&a=2&a=1", &options).unwrap();
+/// assert_eq!(output, "a=1&a=2&b=x");
+/// ```
+pub fn canonicalize_urlencoded_with_options(
+    input: &str,
+    options: &UrlEncodedOptions,
+) -> Result<String, AshError> {
+    canonicalize_pairs(input, true, options)
+}
+
+/// Canonicalize a URL query string.
+///
+/// Follows RFC 3986 query semantics rather than form semantics:
+/// `+` is a literal plus sign and `%20` is the only encoding of a space.
+/// A leading `?` is ignored. Otherwise the rules match
+/// [`canonicalize_urlencoded`].
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::canonicalize_query;
+///
+/// let output = canonicalize_query("?z=3&a=1+2").unwrap();
+/// assert_eq!(output, "a=1%2B2&z=3");
+/// ```
+pub fn canonicalize_query(input: &str) -> Result<String, AshError> {
+    let input = input.strip_prefix('?').unwrap_or(input);
+    canonicalize_pairs(input, false, &UrlEncodedOptions::default())
+}
+
+/// Canonicalize a selected subset of HTTP headers.
+///
+/// # Canonicalization Rules
+///
+/// 1. Header names are lowercased; only names in `include` (matched
+///    case-insensitively) are kept
+/// 2. Values are trimmed of surrounding whitespace and NFC normalized
+/// 3. Repeated headers are combined in input order, joined with `,`
+///    (RFC 7230, section 3.2.2)
+/// 4. Headers are sorted by name and emitted as `name:value` lines joined
+///    with `\n`
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::canonicalize_headers;
+///
+/// let headers = vec![
+///     ("X-Request-Id".to_string(), "abc".to_string()),
+///     ("Content-Type".to_string(), " application/json ".to_string()),
+///     ("Cookie".to_string(), "session=1".to_string()),
+/// ];
+/// let output = canonicalize_headers(&headers, &["content-type", "x-request-id"]);
+/// assert_eq!(output, "content-type:application/json\nx-request-id:abc");
+/// ```
+pub fn canonicalize_headers(headers: &[(String, String)], include: &[&str]) -> String {
+    let include: Vec<String> = include.iter().map(|name| name.to_lowercase()).collect();
+
+    // Combine by lowercased name (BTreeMap keeps names sorted)
+    let mut combined: std::collections::BTreeMap<String, Vec<String>> = Default::default();
+    for (name, value) in headers {
+        let name = name.to_lowercase();
+        if include.contains(&name) {
+            combined
+                .entry(name)
+                .or_default()
+                .push(value.trim().nfc().collect());
+        }
+    }
+
+    let lines: Vec<String> = combined
+        .into_iter()
+        .map(|(name, values)| format!("{}:{}", name, values.join(",")))
+        .collect();
+
+    lines.join("\n")
+}
+
+/// Parse, normalize, sort and re-encode `key=value` pairs.
+///
+/// `plus_as_space` selects form semantics (`+` decodes to space).
+fn canonicalize_pairs(
+    input: &str,
+    plus_as_space: bool,
+    options: &UrlEncodedOptions,
+) -> Result<String, AshError> {
+    check_input_size(input.len(), options.max_input_bytes)?;
+
+    if input.is_empty() {
+        return Ok(String::new());
+    }
+
+    // Parse pairs
+    let mut pairs: Vec<(String, String)> = Vec::new();
+
+    for part in input.split(|c| options.separators.matches(c)) {
+        if part.is_empty() {
+            continue;
+        }
+
+        let (key, value) = match part.find('=') {
+            Some(pos) => (&part[..pos], &part[pos + 1..]),
+            None => (part, ""),
+        };
+
+        // Percent-decode
+        let decoded_key = percent_decode(key, plus_as_space, options.percent_mode)?;
+        let decoded_value = percent_decode(value, plus_as_space, options.percent_mode)?;
+
+        // NFC normalize
+        let normalized_key: String = decoded_key.nfc().collect();
+        let normalized_value: String = decoded_value.nfc().collect();
+
+        pairs.push((normalized_key, normalized_value));
+    }
+
+    match options.key_case {
+        KeyCase::Sensitive if options.sort_duplicate_values => pairs.sort(),
+        // Sort by key (stable sort preserves order of duplicate keys)
+        KeyCase::Sensitive => pairs.sort_by(|a, b| a.0.cmp(&b.0)),
+        KeyCase::Insensitive => {
+            // Sort on the folded key, keeping the original key in the output
+            pairs.sort_by_cached_key(|(key, value)| {
+                let tiebreak = if options.sort_duplicate_values {
+                    Some((key.clone(), value.clone()))
+                } else {
+                    None
+                };
+                (key.to_lowercase(), tiebreak)
+            });
+        }
+    }
+
+    // Re-encode and join
+    let encoded: Vec<String> = pairs
+        .into_iter()
+        .map(|(k, v)| format!("{}={}", percent_encode(&k), percent_encode(&v)))
+        .collect();
+
+    Ok(encoded.join("&"))
+}
+
+/// Percent-decode a string.
+///
+/// When `plus_as_space` is set, `+` decodes to a space (form data).
+/// Decoded bytes are collected first and must form valid UTF-8, so
+/// `%C3%A9` and a literal `é` decode identically.
+fn percent_decode(input: &str, plus_as_space: bool, mode: PercentMode) -> Result<String, AshError> {
+    let bytes = input.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3);
+                let byte = hex
+                    .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|h| u8::from_str_radix(core::str::from_utf8(h).ok()?, 16).ok());
+
+                match (byte, mode) {
+                    (Some(byte), _) => {
+                        result.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    (None, PercentMode::Lenient) => result.push(b'%'),
+                    (None, PercentMode::Strict) if hex.is_none() => {
+                        return Err(AshError::new(
+                            AshErrorCode::CanonicalizationFailed,
+                            "Invalid percent encoding",
+                        ))
+                    }
+                    (None, PercentMode::Strict) => {
+                        return Err(AshError::new(
+                            AshErrorCode::CanonicalizationFailed,
+                            "Invalid percent encoding hex",
+                        ))
+                    }
+                }
+            }
+            // Plus is space in form data
+            b'+' if plus_as_space => result.push(b' '),
+            other => result.push(other),
+        }
+        i += 1;
+    }
+
+    String::from_utf8(result).map_err(|_| {
+        AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            "Percent encoding does not decode to valid UTF-8",
+        )
+    })
+}
+
+/// Percent-encode a string for URL form data.
+fn percent_encode(input: &str) -> String {
+    let mut result = String::with_capacity(input.len() * 3);
+
+    for ch in input.chars() {
+        match ch {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => {
+                result.push(ch);
+            }
+            ' ' => {
+                // Use %20 for spaces (more universal than +)
+                result.push_str("%20");
+            }
+            _ => {
+                // Percent-encode
+                for byte in ch.to_string().as_bytes() {
+                    result.push('%');
+                    result.push_str(&format!("{:02X}", byte));
+                }
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // JSON Canonicalization Tests
+
+    #[test]
+    fn test_canonicalize_json_simple_object() {
+        let input = r#"{"z":1,"a":2}"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"{"a":2,"z":1}"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_nested_object() {
+        let input = r#"{"b":{"d":4,"c":3},"a":1}"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"{"a":1,"b":{"c":3,"d":4}}"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_with_whitespace() {
+        let input = r#"{ "z" : 1 , "a" : 2 }"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"{"a":2,"z":1}"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_array_preserved() {
+        let input = r#"{"arr":[3,1,2]}"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"{"arr":[3,1,2]}"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_null() {
+        let input = r#"{"a":null}"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"{"a":null}"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_boolean() {
+        let input = r#"{"b":true,"a":false}"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"{"a":false,"b":true}"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_empty_object() {
+        let input = r#"{}"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"{}"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_empty_array() {
+        let input = r#"[]"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"[]"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_mixed_type_array() {
+        // Decomposed "cafe\u0301" must come out NFC-composed
+        let input = "[{\"z\":1,\"a\":2},\"cafe\u{0301}\",-0,1.50,null,true,[\"b\",\"a\"]]";
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(
+            output,
+            "[{\"a\":2,\"z\":1},\"caf\u{e9}\",0,1.5,null,true,[\"b\",\"a\"]]"
+        );
+        assert_eq!(canonicalize_json(&output).unwrap(), output);
+    }
+
+    #[test]
+    fn test_canonicalize_json_mixed_type_array_nested() {
+        let input = r#"{"items":[1,{"y":[{"d":1,"c":2.0}],"x":"s"},"t",[],{}]}"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(
+            output,
+            r#"{"items":[1,{"x":"s","y":[{"c":2,"d":1}]},"t",[],{}]}"#
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_json_unicode() {
+        // Test with Unicode characters
+        let input = r#"{"name":"café"}"#;
+        let output = canonicalize_json(input).unwrap();
+        assert_eq!(output, r#"{"name":"café"}"#);
+    }
+
+    #[test]
+    fn test_canonicalize_json_invalid() {
+        let input = r#"{"a":}"#;
+        assert!(canonicalize_json(input).is_err());
+    }
+
+    #[test]
+    fn test_edge_bom_handling() {
+        let plain = r#"{"z":1,"a":[true]}"#;
+        let with_bom = format!("\u{FEFF}{}", plain);
+        assert_eq!(
+            canonicalize_json(&with_bom).unwrap(),
+            canonicalize_json(plain).unwrap()
+        );
+
+        // Only one leading BOM is stripped
+        assert!(canonicalize_json(&format!("\u{FEFF}{}", with_bom)).is_err());
+    }
+
+    #[test]
+    fn test_is_canonical_json() {
+        for canonical in [r#"{"a":1,"b":{"c":[1,"x",null]}}"#, "[]", "1.5", r#""é""#] {
+            assert!(is_canonical_json(canonical).unwrap(), "{}", canonical);
+            let output = canonicalize_json(canonical).unwrap();
+            assert_eq!(canonicalize_json(&output).unwrap(), output);
+        }
+
+        for non_canonical in [
+            r#"{ "a": 1 }"#,
+            "{\"a\":1}\n",
+            r#"{"b":1,"a":2}"#,
+            "[1.0]",
+            "\u{FEFF}{}",
+            "\"e\u{301}\"",
+        ] {
+            assert!(
+                !is_canonical_json(non_canonical).unwrap(),
+                "{}",
+                non_canonical
+            );
+        }
+
+        let err = is_canonical_json("{oops").unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed);
+    }
+
+    #[test]
+    fn test_canonicalize_json_bom_inside_string_kept() {
+        let output = canonicalize_json("{\"a\":\"\u{FEFF}x\"}").unwrap();
+        assert_eq!(output, "{\"a\":\"\u{FEFF}x\"}");
+    }
+
+    const REJECT_CONTROL: CanonicalizeOptions = CanonicalizeOptions {
+        reject_control_chars: true,
+        normalization: StringNormalization::Nfc,
+        number_mode: NumberMode::Numeric,
+        key_sort: KeySort::Utf8Bytes,
+        max_input_bytes: DEFAULT_MAX_INPUT_BYTES,
+        reject_fractional_numbers: false,
+        numeric_string_fields: Vec::new(),
+        set_fields: Vec::new(),
+        timestamp_fields: Vec::new(),
+        invisible_chars: InvisibleChars::Keep,
+    };
+
+    #[test]
+    fn test_canonicalize_json_control_chars_escaped_by_default() {
+        let output = canonicalize_json(r#"{"a":"x\u0000y","b":"\u0008","c":"\t"}"#).unwrap();
+        assert_eq!(output, r#"{"a":"x\u0000y","b":"\b","c":"\t"}"#);
+        assert!(!output.contains('\0'));
+    }
+
+    #[test]
+    fn test_canonicalize_json_rejects_nul() {
+        let err =
+            canonicalize_json_with_options(r#"{"a":"x\u0000y"}"#, &REJECT_CONTROL).unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed);
+
+        // Keys are checked too
+        assert!(canonicalize_json_with_options(r#"{"\u0000":1}"#, &REJECT_CONTROL).is_err());
+    }
+
+    #[test]
+    fn test_canonicalize_json_rejects_backspace() {
+        assert!(canonicalize_json_with_options(r#"["\u0008"]"#, &REJECT_CONTROL).is_err());
+        assert!(canonicalize_json_with_options(r#"["\b"]"#, &REJECT_CONTROL).is_err());
+    }
+
+    fn with_normalization(normalization: StringNormalization) -> CanonicalizeOptions {
+        CanonicalizeOptions {
+            normalization,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_json_nfkc_collapses_compatibility_chars() {
+        let nfc = with_normalization(StringNormalization::Nfc);
+        let nfkc = with_normalization(StringNormalization::Nfkc);
+
+        // U+217F SMALL ROMAN NUMERAL ONE THOUSAND vs LATIN SMALL LETTER M
+        let roman = canonicalize_json_with_options(r#"{"u":"\u217f"}"#, &nfc).unwrap();
+        let latin = canonicalize_json_with_options(r#"{"u":"m"}"#, &nfc).unwrap();
+        assert_ne!(roman, latin);
+
+        let roman = canonicalize_json_with_options(r#"{"u":"\u217f"}"#, &nfkc).unwrap();
+        let latin = canonicalize_json_with_options(r#"{"u":"m"}"#, &nfkc).unwrap();
+        assert_eq!(roman, latin);
+    }
+
+    #[test]
+    fn test_canonicalize_json_nfkc_full_width_digits() {
+        let input = r#"{"account":"１２３"}"#;
+
+        let nfkc = with_normalization(StringNormalization::Nfkc);
+        assert_eq!(
+            canonicalize_json_with_options(input, &nfkc).unwrap(),
+            r#"{"account":"123"}"#
+        );
+        assert_eq!(canonicalize_json(input).unwrap(), input);
+    }
+
+    #[test]
+    fn test_canonicalize_json_no_normalization() {
+        // "e" + COMBINING ACUTE ACCENT stays decomposed
+        let input = "{\"name\":\"cafe\u{301}\"}";
+        let none = with_normalization(StringNormalization::NfNone);
+        assert_eq!(canonicalize_json_with_options(input, &none).unwrap(), input);
+        assert_ne!(canonicalize_json(input).unwrap(), input);
+    }
+
+    #[test]
+    fn test_canonicalize_json_allows_tab() {
+        let output = canonicalize_json_with_options(r#"{"a":"x\ty\n"}"#, &REJECT_CONTROL).unwrap();
+        assert_eq!(output, r#"{"a":"x\ty\n"}"#);
+    }
+
+    // Number Mode Tests
+
+    const REPRESENTATIONS: [&str; 4] = ["1", "1.0", "1.00", "1e0"];
+
+    #[test]
+    fn test_canon_number_representation_attack() {
+        for input in REPRESENTATIONS {
+            assert_eq!(canonicalize_json(input).unwrap(), "1", "{}", input);
+        }
+        assert_eq!(canonicalize_json("-0.0").unwrap(), "0");
+        assert_eq!(canonicalize_json("[2.50,1e2]").unwrap(), "[2.5,100]");
+    }
+
+    #[test]
+    fn test_canon_zero_sign_and_type_collapse() {
+        // `-0` parses as a float while `0` parses as an integer; both paths
+        // must give the same canonical zero and the same proof
+        let inputs = [
+            r#"{"n":0}"#,
+            r#"{"n":-0}"#,
+            r#"{"n":0.0}"#,
+            r#"{"n":-0.0}"#,
+            r#"{"n":-0.000}"#,
+            r#"{"n":0e5}"#,
+            r#"{"n":-0E-3}"#,
+        ];
+        let secret = crate::derive_client_secret("nonce", "ctx", "POST /api");
+        let proof = |canonical: &str| {
+            crate::build_proof_v21(&secret, "1", "POST /api", &crate::hash_body(canonical))
+        };
+
+        for input in inputs {
+            let canonical = canonicalize_json(input).unwrap();
+            assert_eq!(canonical, r#"{"n":0}"#, "{}", input);
+            assert_eq!(stream(input).unwrap(), canonical, "{}", input);
+            assert_eq!(
+                canonicalize_and_digest_json(input).unwrap().0,
+                canonical,
+                "{}",
+                input
+            );
+            assert_eq!(proof(&canonical), proof(r#"{"n":0}"#), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_json_number_mode_preserve_type() {
+        let options = CanonicalizeOptions {
+            number_mode: NumberMode::PreserveType,
+            ..Default::default()
+        };
+        let canonical: Vec<String> = REPRESENTATIONS
+            .iter()
+            .map(|input| canonicalize_json_with_options(input, &options).unwrap())
+            .collect();
+
+        assert_eq!(canonical, ["1", "1.0", "1.0", "1.0"]);
+        // Zero is always `0`, whatever its sign or type
+        for zero in ["0", "-0", "0.0", "-0.0"] {
+            assert_eq!(
+                canonicalize_json_with_options(zero, &options).unwrap(),
+                "0",
+                "{}",
+                zero
+            );
+        }
+        assert_eq!(
+            canonicalize_json_with_options("2.50", &options).unwrap(),
+            "2.5"
+        );
+    }
+
+    // Streaming Canonicalization Tests
+
+    fn stream(input: &str) -> Result<String, AshError> {
+        let mut out = Vec::new();
+        canonicalize_json_streaming(input.as_bytes(), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    /// A valid JSON array of roughly `len` bytes.
+    fn large_json(len: usize) -> String {
+        let mut json = String::with_capacity(len + 2);
+        json.push('[');
+        while json.len() < len {
+            json.push_str("1,");
+        }
+        json.push('1');
+        json.push(']');
+        json
+    }
+
+    fn with_numeric_strings(paths: &[&str]) -> CanonicalizeOptions {
+        CanonicalizeOptions {
+            numeric_string_fields: paths.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_numeric_string_fields_strip_leading_zeros() {
+        let options = with_numeric_strings(&["id", "items[0].ref"]);
+        let canonical = |input| canonicalize_json_with_options(input, &options).unwrap();
+
+        assert_eq!(canonical(r#"{"id":"007"}"#), canonical(r#"{"id":"7"}"#));
+        assert_eq!(canonical(r#"{"id":"007"}"#), r#"{"id":"7"}"#);
+        assert_eq!(canonical(r#"{"id":"000"}"#), r#"{"id":"0"}"#);
+        assert_eq!(
+            canonical(r#"{"id":"018446744073709551616"}"#),
+            r#"{"id":"18446744073709551616"}"#
+        );
+        assert_eq!(
+            canonical(r#"{"items":[{"ref":"0042"}]}"#),
+            r#"{"items":[{"ref":"42"}]}"#
+        );
+        // Missing fields are left alone
+        assert_eq!(canonical(r#"{"other":1}"#), r#"{"other":1}"#);
+    }
+
+    #[test]
+    fn test_numeric_string_fields_reject_non_digits() {
+        let options = with_numeric_strings(&["user.id"]);
+
+        for bad in [
+            r#"{"user":{"id":"12a"}}"#,
+            r#"{"user":{"id":""}}"#,
+            r#"{"user":{"id":"-7"}}"#,
+            r#"{"user":{"id":" 7"}}"#,
+            r#"{"user":{"id":7}}"#,
+        ] {
+            let err = canonicalize_json_with_options(bad, &options).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_numeric_string_fields_leave_unlisted_fields() {
+        let options = with_numeric_strings(&["id"]);
+        let output =
+            canonicalize_json_with_options(r#"{"id":"01","code":"007","n":"x"}"#, &options)
+                .unwrap();
+        assert_eq!(output, r#"{"code":"007","id":"1","n":"x"}"#);
+
+        // Off by default
+        assert_eq!(
+            canonicalize_json(r#"{"id":"007"}"#).unwrap(),
+            r#"{"id":"007"}"#
+        );
+    }
+
+    fn with_sets(paths: &[&str]) -> CanonicalizeOptions {
+        CanonicalizeOptions {
+            set_fields: paths.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_set_fields_ignore_element_order() {
+        let options = with_sets(&["roles", "user.tags"]);
+        let canonical = |input| canonicalize_json_with_options(input, &options).unwrap();
+
+        assert_eq!(
+            canonical(r#"{"roles":["user","admin"]}"#),
+            canonical(r#"{"roles":["admin","user"]}"#)
+        );
+        assert_eq!(
+            canonical(r#"{"roles":["user","admin"]}"#),
+            r#"{"roles":["admin","user"]}"#
+        );
+        assert_eq!(
+            canonical(r#"{"user":{"tags":[{"b":2,"a":1},3,"x"]}}"#),
+            r#"{"user":{"tags":["x",3,{"a":1,"b":2}]}}"#
+        );
+        // Missing fields are left alone
+        assert_eq!(canonical(r#"{"other":[2,1]}"#), r#"{"other":[2,1]}"#);
+    }
+
+    #[test]
+    fn test_set_fields_leave_ordered_arrays() {
+        let options = with_sets(&["roles"]);
+        let output = canonicalize_json_with_options(
+            r#"{"roles":["user","admin"],"steps":["user","admin"]}"#,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            r#"{"roles":["admin","user"],"steps":["user","admin"]}"#
+        );
+
+        // Off by default: arrays are ordered
+        assert_ne!(
+            canonicalize_json(r#"{"roles":["user","admin"]}"#).unwrap(),
+            canonicalize_json(r#"{"roles":["admin","user"]}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_set_fields_reject_non_array() {
+        let err = canonicalize_json_with_options(r#"{"roles":"admin"}"#, &with_sets(&["roles"]))
+            .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed);
+    }
+
+    fn with_invisible(invisible_chars: InvisibleChars) -> CanonicalizeOptions {
+        CanonicalizeOptions {
+            invisible_chars,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_invisible_chars_strip() {
+        let options = with_invisible(InvisibleChars::Strip);
+        let input = "{\"user\":\"ad\u{200B}min\",\"file\":\"txt\u{202E}.exe\"}";
+        let output = canonicalize_json_with_options(input, &options).unwrap();
+        assert_eq!(output, r#"{"file":"txt.exe","user":"admin"}"#);
+
+        // Keys are stripped too
+        let output = canonicalize_json_with_options("{\"a\u{2060}\":1}", &options).unwrap();
+        assert_eq!(output, r#"{"a":1}"#);
+    }
+
+    #[test]
+    fn test_invisible_chars_strip_rejects_key_collision() {
+        let options = with_invisible(InvisibleChars::Strip);
+        let input = "{\"amount\":1,\"amount\u{200B}\":1000}";
+        let err = canonicalize_json_with_options(input, &options).unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed);
+
+        // Nested objects too
+        let input = "{\"a\":{\"k\u{202E}\":1,\"k\":2}}";
+        assert!(canonicalize_json_with_options(input, &options).is_err());
+    }
+
+    #[test]
+    fn test_invisible_chars_reject() {
+        let options = with_invisible(InvisibleChars::Reject);
+        let err =
+            canonicalize_json_with_options("{\"user\":\"ad\u{200B}min\"}", &options).unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed);
+        assert!(err.message().contains("U+200B"));
+
+        // Normal values, and joiners inside emoji sequences, are untouched
+        let input = "{\"user\":\"admin\",\"family\":\"\u{1F468}\u{200D}\u{1F469}\"}";
+        let output = canonicalize_json_with_options(input, &options).unwrap();
+        assert_eq!(output, canonicalize_json(input).unwrap());
+    }
+
+    #[test]
+    fn test_invisible_chars_kept_by_default() {
+        let output = canonicalize_json("{\"user\":\"ad\u{200B}min\"}").unwrap();
+        assert_eq!(output, "{\"user\":\"ad\u{200B}min\"}");
+    }
+
+    fn with_timestamps(paths: &[&str]) -> CanonicalizeOptions {
+        CanonicalizeOptions {
+            timestamp_fields: paths.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_timestamp_fields_normalize_forms() {
+        let options = with_timestamps(&["at", "items[0].due"]);
+        let canonical = |input| canonicalize_json_with_options(input, &options).unwrap();
+        let expected = r#"{"at":"2024-01-01T00:00:00Z"}"#;
+
+        assert_eq!(canonical(r#"{"at":"2024-01-01T00:00:00Z"}"#), expected);
+        assert_eq!(canonical(r#"{"at":"2024-01-01T00:00:00.000Z"}"#), expected);
+        assert_eq!(canonical(r#"{"at":1704067200000}"#), expected);
+        assert_eq!(canonical(r#"{"at":"2024-01-01T03:30:00+03:30"}"#), expected);
+        assert_eq!(canonical(r#"{"at":"2023-12-31t19:00:00-05:00"}"#), expected);
+
+        assert_eq!(
+            canonical(r#"{"at":"2024-02-29T12:00:00.120Z"}"#),
+            r#"{"at":"2024-02-29T12:00:00.12Z"}"#
+        );
+        assert_eq!(
+            canonical(r#"{"at":-1}"#),
+            r#"{"at":"1969-12-31T23:59:59.999Z"}"#
+        );
+        assert_eq!(
+            canonical(r#"{"items":[{"due":"2024-06-30T23:59:59.123456789Z"}]}"#),
+            r#"{"items":[{"due":"2024-06-30T23:59:59.123456789Z"}]}"#
+        );
+        // Missing fields are left alone
+        assert_eq!(canonical(r#"{"other":"x"}"#), r#"{"other":"x"}"#);
+    }
+
+    #[test]
+    fn test_timestamp_fields_reject_invalid() {
+        let options = with_timestamps(&["at"]);
+
+        for bad in [
+            r#"{"at":"2023-02-29T00:00:00Z"}"#,
+            r#"{"at":"2024-13-01T00:00:00Z"}"#,
+            r#"{"at":"2024-01-01T24:00:00Z"}"#,
+            r#"{"at":"2024-01-01T23:59:60Z"}"#,
+            r#"{"at":"2024-01-01T00:00:00"}"#,
+            r#"{"at":"2024-01-01 00:00:00Z"}"#,
+            r#"{"at":"2024-01-01T00:00:00.Z"}"#,
+            r#"{"at":"2024-01-01T00:00:00.1234567890Z"}"#,
+            r#"{"at":"2024-01-01T00:00:00+0300"}"#,
+            r#"{"at":"yesterday"}"#,
+            r#"{"at":1.5}"#,
+            r#"{"at":null}"#,
+        ] {
+            let err = canonicalize_json_with_options(bad, &options).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_reject_fractional_numbers() {
+        let options = CanonicalizeOptions {
+            reject_fractional_numbers: true,
+            ..Default::default()
+        };
+        let canonical = |input| canonicalize_json_with_options(input, &options);
+
+        assert_eq!(canonical(r#"{"amount":10}"#).unwrap(), r#"{"amount":10}"#);
+        assert_eq!(canonical(r#"{"amount":10.0}"#).unwrap(), r#"{"amount":10}"#);
+        assert_eq!(
+            canonical(r#"{"amount":1e3}"#).unwrap(),
+            r#"{"amount":1000}"#
+        );
+
+        for bad in [r#"{"amount":10.5}"#, r#"{"amount":10.001}"#, "[1,[-0.25]]"] {
+            let err = canonical(bad).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed, "{}", bad);
+        }
+
+        // Off by default
+        assert_eq!(
+            canonicalize_json(r#"{"amount":10.5}"#).unwrap(),
+            r#"{"amount":10.5}"#
+        );
+    }
+
+    #[test]
+    fn test_edge_max_length_input() {
+        let input = large_json(9 * 1024 * 1024);
+
+        let err = canonicalize_json(&input).unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed);
+        assert!(err.message().contains("too large"));
+
+        let raised = CanonicalizeOptions {
+            max_input_bytes: 16 * 1024 * 1024,
+            ..Default::default()
+        };
+        assert!(canonicalize_json_with_options(&input, &raised).is_ok());
+    }
+
+    #[test]
+    fn test_max_input_bytes_boundary() {
+        let options = CanonicalizeOptions {
+            max_input_bytes: 7,
+            ..Default::default()
+        };
+        assert!(canonicalize_json_with_options(r#"{"a":1}"#, &options).is_ok());
+        assert!(canonicalize_json_with_options(r#"{"a":10}"#, &options).is_err());
+    }
+
+    #[test]
+    fn test_urlencoded_max_input_bytes() {
+        let input = "a=1&".repeat(9 * 1024 * 1024 / 4);
+
+        let err = canonicalize_urlencoded(&input).unwrap_err();
+        assert!(err.message().contains("too large"));
+        assert!(canonicalize_query(&input).is_err());
+
+        let raised = UrlEncodedOptions {
+            max_input_bytes: 16 * 1024 * 1024,
+            ..Default::default()
+        };
+        assert!(canonicalize_urlencoded_with_options(&input, &raised).is_ok());
+    }
+
+    // NDJSON Tests
+
+    #[test]
+    fn test_canonicalize_ndjson_valid_stream() {
+        let input = "{\"b\":1,\"a\":2}\n[1.0, 2]\n\"caf\\u00e9\"\n";
+        assert_eq!(
+            canonicalize_ndjson(input).unwrap(),
+            "{\"a\":2,\"b\":1}\n[1,2]\n\"caf\u{e9}\""
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_ndjson_blank_lines() {
+        let input = "\n{\"a\":1}\r\n   \n\t\n{\"b\":2}\r\n\n";
+        assert_eq!(canonicalize_ndjson(input).unwrap(), "{\"a\":1}\n{\"b\":2}");
+        assert_eq!(canonicalize_ndjson("").unwrap(), "");
+        assert_eq!(canonicalize_ndjson("\n \n").unwrap(), "");
+    }
+
+    #[test]
+    fn test_canonicalize_ndjson_reports_line_number() {
+        let err = canonicalize_ndjson("{\"a\":1}\n{\"a\":\n{\"c\":3}").unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed);
+        assert!(err.message().starts_with("Line 2:"), "{}", err.message());
+
+        // Blank lines still count towards the line number
+        let err = canonicalize_ndjson("{}\n\n[1,]").unwrap_err();
+        assert!(err.message().starts_with("Line 3:"), "{}", err.message());
+    }
+
+    #[test]
+    fn test_canonicalize_json_typed() {
+        let typed = canonicalize_json_typed;
+
+        for bad in ["[1,2]", "5", r#""s""#, "null"] {
+            let err = typed(bad, JsonShape::Object).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::MalformedRequest, "{}", bad);
+        }
+        assert_eq!(
+            typed(r#" {"b":1,"a":2} "#, JsonShape::Object).unwrap(),
+            r#"{"a":2,"b":1}"#
+        );
+
+        assert_eq!(typed(" [1,2]", JsonShape::Array).unwrap(), "[1,2]");
+        assert!(typed("{}", JsonShape::Array).is_err());
+
+        for input in ["{}", "[1,2]", "5", r#""s""#, "true", "null"] {
+            assert_eq!(
+                typed(input, JsonShape::Any).unwrap(),
+                canonicalize_json(input).unwrap()
+            );
+        }
+
+        // Invalid JSON and trailing data keep their canonicalization error
+        for bad in ["{bad", r#"{"a":1} x"#, "[1]]"] {
+            let err = typed(bad, JsonShape::Object).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed, "{}", bad);
+        }
     }
-}
 
-/// Canonicalize a string with Unicode NFC normalization.
-fn canonicalize_string(s: &str) -> String {
-    s.nfc().collect()
-}
+    #[test]
+    fn test_canonicalize_and_digest_json() {
+        let inputs = [
+            r#"{"z":1,"a":{"c":[3,1.0,-0],"b":"caf\u00e9"}}"#,
+            "[]",
+            "\u{FEFF}{\"b\":null}",
+            "\"text\"",
+        ];
+        for input in inputs {
+            let (canonical, body_hash) = canonicalize_and_digest_json(input).unwrap();
+            assert_eq!(canonical, canonicalize_json(input).unwrap());
+            assert_eq!(body_hash, crate::hash_body(&canonical));
+        }
 
-/// Canonicalize URL-encoded form data.
-///
-/// # Canonicalization Rules
-///
-/// 1. Parse key=value pairs (split on `&`, then on first `=`)
-/// 2. Percent-decode all values
-/// 3. Apply Unicode NFC normalization
-/// 4. Sort pairs by key lexicographically
-/// 5. For duplicate keys, preserve value order
-/// 6. Re-encode with percent encoding
-///
-/// # Example
-///
-/// ```rust
-/// use ash_core::canonicalize_urlencoded;
-///
-/// let input = "z=3&a=1&a=2&b=hello%20world";
-/// let output = canonicalize_urlencoded(input).unwrap();
-/// assert_eq!(output, "a=1&a=2&b=hello%20world&z=3");
-/// ```
-pub fn canonicalize_urlencoded(input: &str) -> Result<String, AshError> {
-    if input.is_empty() {
-        return Ok(String::new());
+        assert_eq!(
+            canonicalize_and_digest_json("{bad").unwrap_err().code(),
+            AshErrorCode::CanonicalizationFailed
+        );
     }
 
-    // Parse pairs
-    let mut pairs: Vec<(String, String)> = Vec::new();
+    #[test]
+    fn test_canonicalize_json_streaming_matches_tree() {
+        let fixtures = [
+            r#"{ "z": 1, "a": { "c": [3, {"y": null, "x": true}], "b": 2 } }"#,
+            r#"[1.0, -0.0, 1e2, 2.50, 12345678901234567890, -9223372036854775808, 1.5e-7]"#,
+            r#"{"a":1,"b":2,"a":3}"#,
+            "{\"cafe\u{301}\":1,\"caf\u{e9}\":2,\"b\":[\"e\u{301}\"]}",
+            r#"{"s":"tab\t nl\n quote\" slash\/ bs\\ \u0001 \ud83d\ude00 \u00e9"}"#,
+            r#"  [ [ ], { }, "", false ]  "#,
+            "\"plain\"",
+            "0",
+        ];
 
-    for part in input.split('&') {
-        if part.is_empty() {
-            continue;
+        for input in fixtures {
+            assert_eq!(
+                stream(input).unwrap(),
+                canonicalize_json(input).unwrap(),
+                "{}",
+                input
+            );
         }
+    }
 
-        let (key, value) = match part.find('=') {
-            Some(pos) => (&part[..pos], &part[pos + 1..]),
-            None => (part, ""),
+    #[test]
+    fn test_canonicalize_json_streaming_rejects_like_tree() {
+        let invalid = [
+            "",
+            "{",
+            r#"{"a":}"#,
+            r#"{"a":1,}"#,
+            "[1,]",
+            "[1 2]",
+            "01",
+            "1.",
+            "-",
+            "tru",
+            "nul",
+            r#""\ud800""#,
+            r#""\udc00""#,
+            "\"a\u{1}b\"",
+            r#""\x""#,
+            "{} {}",
+            "1e400",
+        ];
+
+        for input in invalid {
+            assert!(
+                canonicalize_json(input).is_err(),
+                "tree accepted {:?}",
+                input
+            );
+            assert!(stream(input).is_err(), "stream accepted {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_json_streaming_bom() {
+        let input = "\u{FEFF}{\"b\":1,\"a\":\"\u{FEFF}x\"}";
+        assert_eq!(stream(input).unwrap(), canonicalize_json(input).unwrap());
+        assert!(stream("\u{FEFF}\u{FEFF}{}").is_err());
+    }
+
+    #[test]
+    fn test_canonicalize_json_streaming_depth_limit() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert_eq!(
+            stream(&nested(127)).unwrap(),
+            canonicalize_json(&nested(127)).unwrap()
+        );
+        assert!(canonicalize_json(&nested(128)).is_err());
+        assert!(stream(&nested(128)).is_err());
+    }
+
+    #[test]
+    fn test_canonicalize_json_streaming_invalid_utf8() {
+        let mut out = Vec::new();
+        assert!(canonicalize_json_streaming(&b"[\"\xff\"]"[..], &mut out).is_err());
+    }
+
+    // Key Sort Tests
+
+    #[test]
+    fn test_canonicalize_json_key_sort_utf16() {
+        // U+1F600 is F0 9F 98 80 in UTF-8 but D83D DE00 in UTF-16, so it
+        // sorts after U+FF5A in byte order and before it in UTF-16 order
+        let input = "{\"z\":1,\"\u{1F600}\":2,\"\u{FF5A}\":3}";
+
+        assert_eq!(
+            canonicalize_json(input).unwrap(),
+            "{\"z\":1,\"\u{FF5A}\":3,\"\u{1F600}\":2}"
+        );
+
+        let options = CanonicalizeOptions {
+            key_sort: KeySort::Utf16CodeUnits,
+            ..Default::default()
         };
+        assert_eq!(
+            canonicalize_json_with_options(input, &options).unwrap(),
+            "{\"z\":1,\"\u{1F600}\":2,\"\u{FF5A}\":3}"
+        );
+    }
 
-        // Percent-decode
-        let decoded_key = percent_decode(key)?;
-        let decoded_value = percent_decode(value)?;
+    #[test]
+    fn test_canonicalize_json_astral_keys_match_js() {
+        // Code points: a < U+E000 < U+FF5A < U+1D54F (𝕏) < U+1F600 (😀).
+        // UTF-16 (JS): 𝕏 is D835 DD4F and 😀 is D83D DE00, so both sort
+        // before U+E000.
+        let input = "{\"\u{1F600}\":1,\"\u{FF5A}\":2,\"\u{1D54F}\":3,\"\u{E000}\":4,\"a\":5}";
 
-        // NFC normalize
-        let normalized_key: String = decoded_key.nfc().collect();
-        let normalized_value: String = decoded_value.nfc().collect();
+        assert_eq!(
+            canonicalize_json(input).unwrap(),
+            "{\"a\":5,\"\u{E000}\":4,\"\u{FF5A}\":2,\"\u{1D54F}\":3,\"\u{1F600}\":1}"
+        );
 
-        pairs.push((normalized_key, normalized_value));
+        let js_order = "{\"a\":5,\"\u{1D54F}\":3,\"\u{1F600}\":1,\"\u{E000}\":4,\"\u{FF5A}\":2}";
+        let options = CanonicalizeOptions {
+            key_sort: KeySort::Utf16CodeUnits,
+            ..Default::default()
+        };
+        assert_eq!(
+            canonicalize_json_with_options(input, &options).unwrap(),
+            js_order
+        );
+        assert_eq!(canonicalize_json_jcs(input).unwrap(), js_order);
+    }
+
+    #[test]
+    fn test_canonicalize_json_astral_strings_kept_raw() {
+        // Escaped surrogate pairs decode to one character and are emitted
+        // unescaped, as JSON.stringify does
+        assert_eq!(
+            canonicalize_json(r#"{"k":"\ud83d\ude00 \ud835\udd4f"}"#).unwrap(),
+            "{\"k\":\"\u{1F600} \u{1D54F}\"}"
+        );
+        assert_eq!(
+            canonicalize_json("{\"\u{1F600}\":\"\u{1D54F}\"}").unwrap(),
+            "{\"\u{1F600}\":\"\u{1D54F}\"}"
+        );
     }
 
-    // Sort by key (stable sort preserves order of duplicate keys)
-    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    #[test]
+    fn test_set_fields_astral_elements_follow_key_sort() {
+        let input = "{\"tags\":[\"\u{FF5A}\",\"\u{1F600}\"]}";
+        let mut options = with_sets(&["tags"]);
 
-    // Re-encode and join
-    let encoded: Vec<String> = pairs
-        .into_iter()
-        .map(|(k, v)| format!("{}={}", percent_encode(&k), percent_encode(&v)))
-        .collect();
+        assert_eq!(
+            canonicalize_json_with_options(input, &options).unwrap(),
+            "{\"tags\":[\"\u{FF5A}\",\"\u{1F600}\"]}"
+        );
 
-    Ok(encoded.join("&"))
-}
+        options.key_sort = KeySort::Utf16CodeUnits;
+        assert_eq!(
+            canonicalize_json_with_options(input, &options).unwrap(),
+            "{\"tags\":[\"\u{1F600}\",\"\u{FF5A}\"]}"
+        );
+    }
 
-/// Percent-decode a string.
-fn percent_decode(input: &str) -> Result<String, AshError> {
-    let mut result = String::with_capacity(input.len());
-    let mut chars = input.chars().peekable();
-
-    while let Some(ch) = chars.next() {
-        if ch == '%' {
-            // Read two hex digits
-            let hex: String = chars.by_ref().take(2).collect();
-            if hex.len() != 2 {
-                return Err(AshError::new(
-                    AshErrorCode::CanonicalizationFailed,
-                    "Invalid percent encoding",
-                ));
-            }
-            let byte = u8::from_str_radix(&hex, 16).map_err(|_| {
-                AshError::new(
-                    AshErrorCode::CanonicalizationFailed,
-                    "Invalid percent encoding hex",
-                )
-            })?;
-            result.push(byte as char);
-        } else if ch == '+' {
-            // Plus is space in form data
-            result.push(' ');
-        } else {
-            result.push(ch);
-        }
+    #[test]
+    fn test_canonicalize_json_key_sort_utf16_same_format() {
+        let options = CanonicalizeOptions {
+            key_sort: KeySort::Utf16CodeUnits,
+            ..Default::default()
+        };
+        let input = r#"{"b":[1.0,-0.0,"x\u0001"],"a":{"d":null,"c":true},"e":1.5e3}"#;
+        assert_eq!(
+            canonicalize_json_with_options(input, &options).unwrap(),
+            canonicalize_json(input).unwrap()
+        );
     }
 
-    Ok(result)
-}
+    // JCS (RFC 8785) Tests
 
-/// Percent-encode a string for URL form data.
-fn percent_encode(input: &str) -> String {
-    let mut result = String::with_capacity(input.len() * 3);
+    #[test]
+    fn test_jcs_rfc8785_example() {
+        // RFC 8785 section 3.2.2
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        let expected = concat!(
+            r#"{"literals":[null,true,false],"#,
+            r#""numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"#,
+            r#""string":"€$\u000f\nA'B\"\\\\\"/"}"#,
+        );
+        assert_eq!(canonicalize_json_jcs(input).unwrap(), expected);
+    }
 
-    for ch in input.chars() {
-        match ch {
-            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => {
-                result.push(ch);
-            }
-            ' ' => {
-                // Use %20 for spaces (more universal than +)
-                result.push_str("%20");
-            }
-            _ => {
-                // Percent-encode
-                for byte in ch.to_string().as_bytes() {
-                    result.push('%');
-                    result.push_str(&format!("{:02X}", byte));
-                }
-            }
+    #[test]
+    fn test_jcs_rfc8785_key_sorting() {
+        // RFC 8785 section 3.2.3: UTF-16 code unit order
+        let input = r#"{
+            "\u20ac": "Euro Sign",
+            "\r": "Carriage Return",
+            "\ufb33": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\ud83d\ude00": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "\u00f6": "Latin Small Letter O With Diaeresis"
+        }"#;
+        let expected = concat!(
+            r#"{"\r":"Carriage Return","1":"One","#,
+            "\"\u{80}\":\"Control\",",
+            "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",",
+            "\"\u{20ac}\":\"Euro Sign\",",
+            "\"\u{1f600}\":\"Emoji: Grinning Face\",",
+            "\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}",
+        );
+        assert_eq!(canonicalize_json_jcs(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_jcs_rfc8785_number_vectors() {
+        // RFC 8785 appendix B
+        let vectors: [(u64, &str); 24] = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, expected) in vectors {
+            assert_eq!(
+                jcs_number(f64::from_bits(bits)).unwrap(),
+                expected,
+                "{:#x}",
+                bits
+            );
         }
+
+        assert!(jcs_number(f64::from_bits(0x7fffffffffffffff)).is_err());
+        assert!(jcs_number(f64::from_bits(0x7ff0000000000000)).is_err());
     }
 
-    result
-}
+    #[test]
+    fn test_jcs_parses_numbers_exactly() {
+        // serde_json's default float parsing is off by one ULP here; JCS
+        // must round correctly, the native form must keep its output
+        let input = "[77946897817735677e-9,36705911238380268e-19]";
+        assert_eq!(
+            canonicalize_json_jcs(input).unwrap(),
+            "[77946897.81773567,0.0036705911238380266]"
+        );
+        assert_eq!(
+            canonicalize_json(input).unwrap(),
+            "[77946897.81773569,0.003670591123838027]"
+        );
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_jcs_differs_from_native() {
+        // No NFC normalization in JCS
+        let decomposed = "[\"cafe\u{301}\"]";
+        assert_eq!(canonicalize_json_jcs(decomposed).unwrap(), decomposed);
+        assert_ne!(canonicalize_json(decomposed).unwrap(), decomposed);
 
-    // JSON Canonicalization Tests
+        assert!(canonicalize_json_jcs("1E400").is_err());
+        assert!(canonicalize_json_jcs(r#"["\ud800"]"#).is_err());
+    }
 
+    // CBOR Canonicalization Tests
+
+    #[cfg(feature = "json5")]
     #[test]
-    fn test_canonicalize_json_simple_object() {
-        let input = r#"{"z":1,"a":2}"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"{"a":2,"z":1}"#);
+    fn test_canonicalize_json5_trailing_commas() {
+        assert_eq!(
+            canonicalize_json5(r#"{"b": [1, 2,], "a": 1,}"#).unwrap(),
+            canonicalize_json(r#"{"b":[1,2],"a":1}"#).unwrap()
+        );
     }
 
+    #[cfg(feature = "json5")]
     #[test]
-    fn test_canonicalize_json_nested_object() {
-        let input = r#"{"b":{"d":4,"c":3},"a":1}"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"{"a":1,"b":{"c":3,"d":4}}"#);
+    fn test_canonicalize_json5_comments() {
+        let input = "{\n  // amount in cents\n  \"amount\": 100, /* block */ \"to\": \"x\"\n}";
+        assert_eq!(
+            canonicalize_json5(input).unwrap(),
+            r#"{"amount":100,"to":"x"}"#
+        );
     }
 
+    #[cfg(feature = "json5")]
     #[test]
-    fn test_canonicalize_json_with_whitespace() {
-        let input = r#"{ "z" : 1 , "a" : 2 }"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"{"a":2,"z":1}"#);
+    fn test_canonicalize_json5_unquoted_keys() {
+        let relaxed = "{z: 'caf\u{0065}\u{0301}', a: {y: 1.50, x: 0x10}, n: null}";
+        let strict = r#"{"z":"caf\u0065\u0301","a":{"y":1.5,"x":16},"n":null}"#;
+        assert_eq!(
+            canonicalize_json5(relaxed).unwrap(),
+            canonicalize_json(strict).unwrap()
+        );
+        assert_eq!(
+            canonicalize_json5(relaxed).unwrap(),
+            "{\"a\":{\"x\":16,\"y\":1.5},\"n\":null,\"z\":\"caf\u{e9}\"}"
+        );
     }
 
+    #[cfg(feature = "json5")]
     #[test]
-    fn test_canonicalize_json_array_preserved() {
-        let input = r#"{"arr":[3,1,2]}"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"{"arr":[3,1,2]}"#);
+    fn test_canonicalize_json5_rejects_non_finite_and_invalid() {
+        for bad in ["{a: NaN}", "[Infinity]", "[-Infinity]", "{a: }", "{a: 1"] {
+            let err = canonicalize_json5(bad).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed, "{}", bad);
+        }
     }
 
+    #[cfg(feature = "cbor")]
+    fn cbor(value: &ciborium::Value) -> Vec<u8> {
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(value, &mut out).unwrap();
+        out
+    }
+
+    #[cfg(feature = "cbor")]
     #[test]
-    fn test_canonicalize_json_null() {
-        let input = r#"{"a":null}"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"{"a":null}"#);
+    fn test_canonicalize_cbor_map_key_reordering() {
+        use ciborium::Value as Cbor;
+
+        let a = Cbor::Map(vec![
+            (Cbor::Text("z".into()), Cbor::Integer(1.into())),
+            (
+                Cbor::Text("a".into()),
+                Cbor::Text("caf\u{0065}\u{0301}".into()),
+            ),
+        ]);
+        let b = Cbor::Map(vec![
+            (Cbor::Text("a".into()), Cbor::Text("caf\u{00e9}".into())),
+            (Cbor::Text("z".into()), Cbor::Integer(1.into())),
+        ]);
+
+        let canonical = canonicalize_cbor(&cbor(&a)).unwrap();
+        assert_eq!(canonical, canonicalize_cbor(&cbor(&b)).unwrap());
+        assert_eq!(
+            canonical,
+            canonicalize_json(r#"{"z":1,"a":"café"}"#).unwrap()
+        );
     }
 
+    #[cfg(feature = "cbor")]
     #[test]
-    fn test_canonicalize_json_boolean() {
-        let input = r#"{"b":true,"a":false}"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"{"a":false,"b":true}"#);
+    fn test_canonicalize_cbor_float_normalization() {
+        // 1.5 as half-precision (f9 3e00) and as double (fb 3ff8...)
+        let half = [0xf9, 0x3e, 0x00];
+        let double = [0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0];
+        assert_eq!(canonicalize_cbor(&half).unwrap(), "1.5");
+        assert_eq!(canonicalize_cbor(&double).unwrap(), "1.5");
+
+        // -0.0 (f9 8000) becomes 0
+        assert_eq!(
+            canonicalize_cbor(&[0xf9, 0x80, 0x00]).unwrap(),
+            canonicalize_json("0.0").unwrap()
+        );
+
+        // NaN (f9 7e00) and Infinity (f9 7c00) are rejected
+        assert!(canonicalize_cbor(&[0xf9, 0x7e, 0x00]).is_err());
+        assert!(canonicalize_cbor(&[0xf9, 0x7c, 0x00]).is_err());
     }
 
+    #[cfg(feature = "cbor")]
     #[test]
-    fn test_canonicalize_json_empty_object() {
-        let input = r#"{}"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"{}"#);
+    fn test_canonicalize_cbor_rejects_unsupported() {
+        use ciborium::Value as Cbor;
+
+        let int_key = Cbor::Map(vec![(Cbor::Integer(1.into()), Cbor::Null)]);
+        assert!(canonicalize_cbor(&cbor(&int_key)).is_err());
+        assert!(canonicalize_cbor(&cbor(&Cbor::Bytes(vec![1, 2]))).is_err());
+
+        // Trailing data after the first item
+        assert!(canonicalize_cbor(&[0xf6, 0xf6]).is_err());
+        assert!(canonicalize_cbor(&[]).is_err());
+    }
+
+    // Protobuf Canonicalization Tests
+
+    /// `message Transfer { string recipient = 1; int64 amount = 2;
+    /// map<string, int32> limits = 3; }`
+    #[cfg(feature = "protobuf")]
+    fn transfer_descriptor() -> prost_reflect::MessageDescriptor {
+        use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+        use prost_reflect::prost_types::{
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MessageOptions,
+        };
+
+        let field = |name: &str, number, label: Label, ty: Type, type_name: Option<&str>| {
+            FieldDescriptorProto {
+                name: Some(name.to_string()),
+                number: Some(number),
+                label: Some(label as i32),
+                r#type: Some(ty as i32),
+                type_name: type_name.map(str::to_string),
+                ..Default::default()
+            }
+        };
+
+        let limits_entry = DescriptorProto {
+            name: Some("LimitsEntry".to_string()),
+            field: vec![
+                field("key", 1, Label::Optional, Type::String, None),
+                field("value", 2, Label::Optional, Type::Int32, None),
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let transfer = DescriptorProto {
+            name: Some("Transfer".to_string()),
+            field: vec![
+                field("recipient", 1, Label::Optional, Type::String, None),
+                field("amount", 2, Label::Optional, Type::Int64, None),
+                field(
+                    "limits",
+                    3,
+                    Label::Repeated,
+                    Type::Message,
+                    Some(".test.Transfer.LimitsEntry"),
+                ),
+            ],
+            nested_type: vec![limits_entry],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("transfer.proto".to_string()),
+            package: Some("test".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![transfer],
+            ..Default::default()
+        };
+
+        let mut pool = prost_reflect::DescriptorPool::new();
+        pool.add_file_descriptor_proto(file).unwrap();
+        pool.get_message_by_name("test.Transfer").unwrap()
     }
 
+    // Wire-format fields of a Transfer message
+    #[cfg(feature = "protobuf")]
+    const PB_RECIPIENT: &[u8] = &[0x0a, 0x03, b'b', b'o', b'b'];
+    #[cfg(feature = "protobuf")]
+    const PB_AMOUNT: &[u8] = &[0x10, 0x64];
+    #[cfg(feature = "protobuf")]
+    const PB_LIMIT_A: &[u8] = &[0x1a, 0x05, 0x0a, 0x01, b'a', 0x10, 0x01];
+    #[cfg(feature = "protobuf")]
+    const PB_LIMIT_B: &[u8] = &[0x1a, 0x05, 0x0a, 0x01, b'b', 0x10, 0x02];
+
+    #[cfg(feature = "protobuf")]
     #[test]
-    fn test_canonicalize_json_empty_array() {
-        let input = r#"[]"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"[]"#);
+    fn test_canonicalize_protobuf_field_and_map_order() {
+        let descriptor = transfer_descriptor();
+
+        let forward = [PB_RECIPIENT, PB_AMOUNT, PB_LIMIT_A, PB_LIMIT_B].concat();
+        let shuffled = [PB_LIMIT_B, PB_AMOUNT, PB_LIMIT_A, PB_RECIPIENT].concat();
+
+        let canonical = canonicalize_protobuf(&forward, &descriptor).unwrap();
+        assert_eq!(canonical, r#"{"1":"bob","2":100,"3":{"a":1,"b":2}}"#);
+        assert_eq!(
+            canonicalize_protobuf(&shuffled, &descriptor).unwrap(),
+            canonical
+        );
     }
 
+    #[cfg(feature = "protobuf")]
     #[test]
-    fn test_canonicalize_json_unicode() {
-        // Test with Unicode characters
-        let input = r#"{"name":"café"}"#;
-        let output = canonicalize_json(input).unwrap();
-        assert_eq!(output, r#"{"name":"café"}"#);
+    fn test_canonicalize_protobuf_defaults_omitted() {
+        let descriptor = transfer_descriptor();
+
+        // amount = 0 sent explicitly is the same as amount unset
+        let explicit_zero = [PB_RECIPIENT, &[0x10, 0x00]].concat();
+        assert_eq!(
+            canonicalize_protobuf(&explicit_zero, &descriptor).unwrap(),
+            canonicalize_protobuf(PB_RECIPIENT, &descriptor).unwrap()
+        );
+        assert_eq!(canonicalize_protobuf(&[], &descriptor).unwrap(), "{}");
     }
 
+    #[cfg(feature = "protobuf")]
     #[test]
-    fn test_canonicalize_json_invalid() {
-        let input = r#"{"a":}"#;
-        assert!(canonicalize_json(input).is_err());
+    fn test_canonicalize_protobuf_rejects_unknown_and_invalid() {
+        let descriptor = transfer_descriptor();
+
+        // Field 9 (varint) is not in the descriptor
+        let unknown = [PB_RECIPIENT, &[0x48, 0x01]].concat();
+        assert!(canonicalize_protobuf(&unknown, &descriptor).is_err());
+
+        // Truncated length-delimited field
+        assert!(canonicalize_protobuf(&[0x0a, 0x05, b'b'], &descriptor).is_err());
     }
 
     // URL-Encoded Canonicalization Tests
@@ -359,6 +3368,26 @@ mod tests {
         assert_eq!(output, "a=2&a=1&b=3");
     }
 
+    #[test]
+    fn test_canonicalize_urlencoded_sort_duplicate_values() {
+        let options = UrlEncodedOptions {
+            sort_duplicate_values: true,
+            ..Default::default()
+        };
+
+        let forward = canonicalize_urlencoded_with_options("b=3&a=1&a=2", &options).unwrap();
+        let reversed = canonicalize_urlencoded_with_options("a=2&b=3&a=1", &options).unwrap();
+        assert_eq!(forward, "a=1&a=2&b=3");
+        assert_eq!(forward, reversed);
+
+        // Default keeps input order for duplicates
+        let default = UrlEncodedOptions::default();
+        assert_eq!(
+            canonicalize_urlencoded_with_options("a=2&b=3&a=1", &default).unwrap(),
+            "a=2&a=1&b=3"
+        );
+    }
+
     #[test]
     fn test_canonicalize_urlencoded_encoded_space() {
         let input = "a=hello%20world";
@@ -373,6 +3402,25 @@ mod tests {
         assert_eq!(output, "a=hello%20world");
     }
 
+    // Query String Canonicalization Tests
+
+    #[test]
+    fn test_canonicalize_query_plus_is_literal() {
+        assert_eq!(canonicalize_urlencoded("a=1+2").unwrap(), "a=1%202");
+        assert_eq!(canonicalize_query("a=1+2").unwrap(), "a=1%2B2");
+    }
+
+    #[test]
+    fn test_canonicalize_query_encoded_space() {
+        assert_eq!(canonicalize_query("a=1%202").unwrap(), "a=1%202");
+    }
+
+    #[test]
+    fn test_canonicalize_query_sorted_and_prefix_stripped() {
+        assert_eq!(canonicalize_query("?z=3&a=1&b=2").unwrap(), "a=1&b=2&z=3");
+        assert_eq!(canonicalize_query("?").unwrap(), "");
+    }
+
     #[test]
     fn test_canonicalize_urlencoded_empty() {
         let input = "";
@@ -386,4 +3434,182 @@ mod tests {
         let output = canonicalize_urlencoded(input).unwrap();
         assert_eq!(output, "a=&b=2");
     }
+
+    #[test]
+    fn test_canonicalize_urlencoded_equivalent_encodings_collapse() {
+        // Every spelling of key "a~-_" and value "A~-_é" (literal, upper
+        // and lower case hex, mixed) must canonicalize identically
+        let keys = ["a~-_", "%61%7E%2D%5F", "%61%7e%2d%5f", "a%7E-%5f"];
+        let values = [
+            "A~-_\u{e9}",
+            "%41%7E%2D%5F%C3%A9",
+            "%41%7e%2d%5f%c3%a9",
+            "A%7e-_%C3%a9",
+        ];
+
+        for key in keys {
+            for value in values {
+                let input = format!("{}={}", key, value);
+                assert_eq!(
+                    canonicalize_urlencoded(&input).unwrap(),
+                    "a~-_=A~-_%C3%A9",
+                    "{}",
+                    input
+                );
+                assert_eq!(canonicalize_query(&input).unwrap(), "a~-_=A~-_%C3%A9");
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_urlencoded_rejects_invalid_utf8() {
+        assert!(canonicalize_urlencoded("a=%C3").is_err());
+        assert!(canonicalize_urlencoded("a=%FF").is_err());
+    }
+
+    #[test]
+    fn test_canonicalize_urlencoded_percent_strict() {
+        assert!(canonicalize_urlencoded("a=50%").is_err());
+        assert!(canonicalize_urlencoded("a=%zz").is_err());
+        assert!(canonicalize_urlencoded("a=%+1").is_err());
+    }
+
+    #[test]
+    fn test_canonicalize_urlencoded_percent_lenient() {
+        let options = UrlEncodedOptions {
+            percent_mode: PercentMode::Lenient,
+            ..Default::default()
+        };
+        let canonical = |input| canonicalize_urlencoded_with_options(input, &options).unwrap();
+
+        assert_eq!(canonical("a=50%"), "a=50%25");
+        assert_eq!(canonical("a=%zz"), "a=%25zz");
+        assert_eq!(canonical("a=%4"), "a=%254");
+        // Valid sequences still decode
+        assert_eq!(canonical("a=%41%"), "a=A%25");
+    }
+
+    #[test]
+    fn test_canonicalize_urlencoded_semicolon_separators() {
+        let canonical = |separators, input| {
+            let options = UrlEncodedOptions {
+                separators,
+                ..Default::default()
+            };
+            canonicalize_urlencoded_with_options(input, &options).unwrap()
+        };
+
+        assert_eq!(canonical(PairSeparators::Semicolon, "b=2;a=1"), "a=1&b=2");
+        assert_eq!(canonical(PairSeparators::Both, "a=1;b=2"), "a=1&b=2");
+        assert_eq!(
+            canonical(PairSeparators::Both, "c=3;b=2&a=1"),
+            "a=1&b=2&c=3"
+        );
+        // Semicolon-only treats `&` as literal
+        assert_eq!(
+            canonical(PairSeparators::Semicolon, "a=1&b=2"),
+            "a=1%26b%3D2"
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_urlencoded_key_case() {
+        let canonical = |key_case, sort_duplicate_values, input| {
+            let options = UrlEncodedOptions {
+                key_case,
+                sort_duplicate_values,
+                ..Default::default()
+            };
+            canonicalize_urlencoded_with_options(input, &options).unwrap()
+        };
+
+        // Byte order: uppercase sorts before lowercase
+        assert_eq!(
+            canonical(KeyCase::Sensitive, false, "name=2&Name=1"),
+            "Name=1&name=2"
+        );
+        assert_eq!(
+            canonical(KeyCase::Sensitive, false, "b=3&Name=1&a=0"),
+            "Name=1&a=0&b=3"
+        );
+
+        // Folded order keeps original case and input order among case variants
+        assert_eq!(
+            canonical(KeyCase::Insensitive, false, "Name=1&name=2"),
+            "Name=1&name=2"
+        );
+        assert_eq!(
+            canonical(KeyCase::Insensitive, false, "name=2&Name=1"),
+            "name=2&Name=1"
+        );
+        assert_eq!(
+            canonical(KeyCase::Insensitive, false, "b=3&Name=1&a=0"),
+            "a=0&b=3&Name=1"
+        );
+
+        // Sorting duplicates makes case variants order-independent
+        assert_eq!(
+            canonical(KeyCase::Insensitive, true, "name=2&Name=1"),
+            "Name=1&name=2"
+        );
+        assert_eq!(
+            canonical(KeyCase::Insensitive, true, "Name=1&name=2"),
+            "Name=1&name=2"
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_urlencoded_default_semicolon_literal() {
+        assert_eq!(canonicalize_urlencoded("a=1;b=2").unwrap(), "a=1%3Bb%3D2");
+        assert_eq!(
+            canonicalize_urlencoded("a=1;b=2&c=3").unwrap(),
+            "a=1%3Bb%3D2&c=3"
+        );
+    }
+
+    // Header Canonicalization Tests
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_canonicalize_headers_case_insensitive_names() {
+        let upper = headers(&[("Content-Type", "application/json")]);
+        let lower = headers(&[("content-type", "application/json")]);
+
+        assert_eq!(
+            canonicalize_headers(&upper, &["content-type"]),
+            "content-type:application/json"
+        );
+        assert_eq!(
+            canonicalize_headers(&upper, &["Content-Type"]),
+            canonicalize_headers(&lower, &["content-type"])
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_headers_repeated_values_joined() {
+        let input = headers(&[
+            ("Accept", "text/html"),
+            ("X-Trace", "1"),
+            ("accept", " application/json"),
+        ]);
+        assert_eq!(
+            canonicalize_headers(&input, &["accept", "x-trace"]),
+            "accept:text/html,application/json\nx-trace:1"
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_headers_excluded_dropped() {
+        let input = headers(&[("Cookie", "session=1"), ("Authorization", "Bearer t")]);
+        assert_eq!(canonicalize_headers(&input, &["content-type"]), "");
+
+        let input = headers(&[("Cookie", "session=1"), ("X-Id", "caf\u{0065}\u{0301}")]);
+        assert_eq!(canonicalize_headers(&input, &["x-id"]), "x-id:caf\u{00e9}");
+    }
 }
@@ -39,6 +39,43 @@ pub fn timing_safe_equal(a: &[u8], b: &[u8]) -> bool {
     a.ct_eq(b).into()
 }
 
+/// Perform a constant-time comparison that does not leak length differences.
+///
+/// Unlike [`timing_safe_equal`], this does not return early when the inputs
+/// have different lengths. It always compares `max(a.len(), b.len())` bytes,
+/// treating the shorter input as zero-padded, and folds the length
+/// difference into the result, so the work done depends only on the
+/// longer input.
+///
+/// # When to Use
+///
+/// Prefer this when either value's length is secret or attacker-controlled,
+/// e.g. comparing a client-supplied token against a stored one. For proofs,
+/// whose lengths are fixed and public, [`timing_safe_equal`] is sufficient
+/// and cheaper.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::timing_safe_equal_padded;
+///
+/// assert!(timing_safe_equal_padded(b"token", b"token"));
+/// assert!(!timing_safe_equal_padded(b"token", b"token\0"));
+/// ```
+pub fn timing_safe_equal_padded(a: &[u8], b: &[u8]) -> bool {
+    let max_len = a.len().max(b.len());
+
+    let mut diff: u8 = 0;
+    for i in 0..max_len {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= x ^ y;
+    }
+
+    let same_len = (a.len() as u64).ct_eq(&(b.len() as u64));
+    (same_len & diff.ct_eq(&0)).into()
+}
+
 /// Compare two strings in constant time.
 ///
 /// Convenience wrapper around `timing_safe_equal` for string comparison.
@@ -90,9 +127,127 @@ mod tests {
         assert!(timing_safe_equal(a, b));
     }
 
+    #[test]
+    fn test_timing_safe_equal_padded_same() {
+        assert!(timing_safe_equal_padded(b"hello world", b"hello world"));
+        assert!(timing_safe_equal_padded(b"", b""));
+    }
+
+    #[test]
+    fn test_timing_safe_equal_padded_different_length() {
+        let pairs: [(&[u8], &[u8]); 5] = [
+            (b"hello", b"hello world"),
+            (b"hello world", b"hello"),
+            (b"", b"a"),
+            (b"abc", b""),
+            // Zero padding must not make a prefix look equal
+            (b"abc", b"abc\0\0"),
+        ];
+
+        for (a, b) in pairs {
+            assert!(!timing_safe_equal_padded(a, b));
+        }
+    }
+
+    #[test]
+    fn test_timing_safe_equal_padded_different_content() {
+        assert!(!timing_safe_equal_padded(b"hello world", b"hello worle"));
+    }
+
     #[test]
     fn test_ash_timing_safe_compare() {
         assert!(ash_timing_safe_compare("test", "test"));
         assert!(!ash_timing_safe_compare("test", "Test"));
     }
 }
+
+/// Timing regression harness for the constant-time comparisons.
+///
+/// Run with: `cargo test -p ash-core --features timing_tests --release -- --ignored`
+///
+/// The tests are `#[ignore]`d so `cargo test --all-features` on shared CI
+/// runners never runs them; invoke them explicitly on a quiet machine.
+///
+/// # Method
+///
+/// Two input pairs of equal length are compared repeatedly: one differs in
+/// the first byte (early mismatch), the other in the last byte (late
+/// mismatch). Measurements are taken in batches, alternating between the
+/// two pairs so that frequency scaling and cache effects hit both equally.
+/// The median batch time of each pair is used, since medians are robust to
+/// scheduler preemption outliers that skew means.
+///
+/// # Threshold
+///
+/// The relative difference of the medians must stay below 20%. Inputs are
+/// 4 KiB so that an early-exit comparison (`==` / `memcmp`) would finish the
+/// early-mismatch case orders of magnitude faster and fail clearly, while
+/// ordinary scheduling noise stays well inside the tolerance.
+#[cfg(all(test, feature = "timing_tests"))]
+mod timing_tests {
+    use super::*;
+    use std::hint::black_box;
+    use std::time::Instant;
+
+    const INPUT_LEN: usize = 4096;
+    const ROUNDS: usize = 301;
+    const BATCH: usize = 500;
+    const TOLERANCE: f64 = 0.20;
+
+    fn median_nanos(mut samples: Vec<u128>) -> f64 {
+        samples.sort_unstable();
+        samples[samples.len() / 2] as f64
+    }
+
+    fn assert_timing_independent(compare: fn(&[u8], &[u8]) -> bool) {
+        let reference = vec![0xA5u8; INPUT_LEN];
+        let mut early = reference.clone();
+        early[0] ^= 0xFF;
+        let mut late = reference.clone();
+        late[INPUT_LEN - 1] ^= 0xFF;
+
+        let mut early_samples = Vec::with_capacity(ROUNDS);
+        let mut late_samples = Vec::with_capacity(ROUNDS);
+
+        for round in 0..ROUNDS {
+            // Alternate which pair goes first to cancel ordering bias
+            let order: [(&[u8], &mut Vec<u128>); 2] = if round % 2 == 0 {
+                [(&early, &mut early_samples), (&late, &mut late_samples)]
+            } else {
+                [(&late, &mut late_samples), (&early, &mut early_samples)]
+            };
+
+            for (candidate, samples) in order {
+                let start = Instant::now();
+                for _ in 0..BATCH {
+                    black_box(compare(black_box(&reference), black_box(candidate)));
+                }
+                samples.push(start.elapsed().as_nanos());
+            }
+        }
+
+        let early_median = median_nanos(early_samples);
+        let late_median = median_nanos(late_samples);
+        let diff = (early_median - late_median).abs() / early_median.max(late_median);
+
+        assert!(
+            diff < TOLERANCE,
+            "timing differs by {:.1}% (early {:.0}ns, late {:.0}ns per batch)",
+            diff * 100.0,
+            early_median,
+            late_median,
+        );
+    }
+
+    #[test]
+    #[ignore = "statistical timing test; run explicitly with --ignored"]
+    fn test_timing_safe_equal_is_timing_independent() {
+        assert_timing_independent(timing_safe_equal);
+    }
+
+    #[test]
+    #[ignore = "statistical timing test; run explicitly with --ignored"]
+    fn test_timing_safe_equal_padded_is_timing_independent() {
+        assert_timing_independent(timing_safe_equal_padded);
+    }
+}
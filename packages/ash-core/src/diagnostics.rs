@@ -0,0 +1,139 @@
+//! Debugging helpers for investigating verification failures.
+//!
+//! Enabled with the `diagnostics` feature. Nothing here is used by the
+//! verification functions, and none of it is constant-time: these helpers
+//! are for explaining a failure after the fact, never for deciding one.
+
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use crate::canonicalize::canonicalize_json;
+use crate::errors::{AshError, AshErrorCode};
+
+/// Compare two JSON payloads after canonicalization and list the differences.
+///
+/// Each entry names a JSON path (`amount`, `user.name`, `items[2]`) and
+/// describes how the two sides differ. An empty result means both inputs
+/// have the same canonical form.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::canonical_diff;
+///
+/// let diff = canonical_diff(
+///     r#"{"amount":1000,"notes":"hi"}"#,
+///     r#"{"amount":9999}"#,
+/// ).unwrap();
+/// assert_eq!(diff, vec!["amount: 1000 != 9999", "notes: present in a only"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns `CanonicalizationFailed` if either input cannot be canonicalized.
+pub fn canonical_diff(a: &str, b: &str) -> Result<Vec<String>, AshError> {
+    let a = parse_canonical(a)?;
+    let b = parse_canonical(b)?;
+
+    let mut diffs = Vec::new();
+    diff_values("", &a, &b, &mut diffs);
+    Ok(diffs)
+}
+
+fn parse_canonical(input: &str) -> Result<Value, AshError> {
+    let canonical = canonicalize_json(input)?;
+    serde_json::from_str(&canonical).map_err(|e| {
+        AshError::new(
+            AshErrorCode::CanonicalizationFailed,
+            format!("Invalid JSON: {}", e),
+        )
+    })
+}
+
+fn diff_values(path: &str, a: &Value, b: &Value, diffs: &mut Vec<String>) {
+    match (a, b) {
+        (Value::Object(a_map), Value::Object(b_map)) => {
+            let keys: BTreeSet<&String> = a_map.keys().chain(b_map.keys()).collect();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (a_map.get(key), b_map.get(key)) {
+                    (Some(a_val), Some(b_val)) => diff_values(&child, a_val, b_val, diffs),
+                    (Some(_), None) => diffs.push(format!("{}: present in a only", child)),
+                    (None, Some(_)) => diffs.push(format!("{}: present in b only", child)),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(a_arr), Value::Array(b_arr)) => {
+            for i in 0..a_arr.len().max(b_arr.len()) {
+                let child = format!("{}[{}]", path, i);
+                match (a_arr.get(i), b_arr.get(i)) {
+                    (Some(a_val), Some(b_val)) => diff_values(&child, a_val, b_val, diffs),
+                    (Some(_), None) => diffs.push(format!("{}: present in a only", child)),
+                    (None, Some(_)) => diffs.push(format!("{}: present in b only", child)),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if a != b => {
+            let label = if path.is_empty() { "(root)" } else { path };
+            diffs.push(format!("{}: {} != {}", label, a, b));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_diff_identical() {
+        let diff = canonical_diff(r#"{"b":1,"a":2}"#, r#"{ "a": 2, "b": 1 }"#).unwrap();
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_canonical_diff_value_change() {
+        let diff = canonical_diff(
+            r#"{"amount":1000,"user":{"name":"a"}}"#,
+            r#"{"amount":9999,"user":{"name":"b"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            diff,
+            vec!["amount: 1000 != 9999", r#"user.name: "a" != "b""#]
+        );
+    }
+
+    #[test]
+    fn test_canonical_diff_added_and_removed_keys() {
+        let diff = canonical_diff(r#"{"a":1,"notes":"x"}"#, r#"{"a":1,"extra":true}"#).unwrap();
+        assert_eq!(
+            diff,
+            vec!["extra: present in b only", "notes: present in a only"]
+        );
+    }
+
+    #[test]
+    fn test_canonical_diff_arrays_and_root() {
+        let diff = canonical_diff(r#"{"items":[1,2]}"#, r#"{"items":[1,3,4]}"#).unwrap();
+        assert_eq!(
+            diff,
+            vec!["items[1]: 2 != 3", "items[2]: present in b only"]
+        );
+
+        let diff = canonical_diff("1", r#""1""#).unwrap();
+        assert_eq!(diff, vec![r#"(root): 1 != "1""#]);
+    }
+
+    #[test]
+    fn test_canonical_diff_invalid_json() {
+        assert!(canonical_diff("{", "{}").is_err());
+    }
+}
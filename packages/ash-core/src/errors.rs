@@ -1,7 +1,10 @@
 //! Error types for ASH protocol.
 
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+
 use serde::{Deserialize, Serialize};
-use std::fmt;
 
 /// Error codes for ASH protocol.
 ///
@@ -27,6 +30,14 @@ pub enum AshErrorCode {
     MalformedRequest,
     /// Payload cannot be canonicalized
     CanonicalizationFailed,
+    /// Secure random source is unavailable
+    RngUnavailable,
+    /// Proof is not in a recognized format
+    InvalidProofFormat,
+    /// Proof verification could not be completed
+    VerificationFailed,
+    /// Context was already consumed
+    ContextConsumed,
 }
 
 impl AshErrorCode {
@@ -42,6 +53,10 @@ impl AshErrorCode {
             AshErrorCode::UnsupportedContentType => 400,
             AshErrorCode::MalformedRequest => 400,
             AshErrorCode::CanonicalizationFailed => 400,
+            AshErrorCode::RngUnavailable => 500,
+            AshErrorCode::InvalidProofFormat => 400,
+            AshErrorCode::VerificationFailed => 400,
+            AshErrorCode::ContextConsumed => 409,
         }
     }
 
@@ -57,6 +72,29 @@ impl AshErrorCode {
             AshErrorCode::UnsupportedContentType => "ASH_UNSUPPORTED_CONTENT_TYPE",
             AshErrorCode::MalformedRequest => "ASH_MALFORMED_REQUEST",
             AshErrorCode::CanonicalizationFailed => "ASH_CANONICALIZATION_FAILED",
+            AshErrorCode::RngUnavailable => "ASH_RNG_UNAVAILABLE",
+            AshErrorCode::InvalidProofFormat => "ASH_INVALID_PROOF_FORMAT",
+            AshErrorCode::VerificationFailed => "ASH_VERIFICATION_FAILED",
+            AshErrorCode::ContextConsumed => "ASH_CONTEXT_CONSUMED",
+        }
+    }
+
+    /// Get a short, fixed, client-safe title for this error.
+    pub fn title(&self) -> &'static str {
+        match self {
+            AshErrorCode::InvalidContext => "Invalid context",
+            AshErrorCode::ContextExpired => "Context expired",
+            AshErrorCode::ReplayDetected => "Replay detected",
+            AshErrorCode::IntegrityFailed => "Integrity check failed",
+            AshErrorCode::EndpointMismatch => "Endpoint mismatch",
+            AshErrorCode::ModeViolation => "Mode violation",
+            AshErrorCode::UnsupportedContentType => "Unsupported content type",
+            AshErrorCode::MalformedRequest => "Malformed request",
+            AshErrorCode::CanonicalizationFailed => "Canonicalization failed",
+            AshErrorCode::RngUnavailable => "Random source unavailable",
+            AshErrorCode::InvalidProofFormat => "Invalid proof format",
+            AshErrorCode::VerificationFailed => "Verification failed",
+            AshErrorCode::ContextConsumed => "Context consumed",
         }
     }
 }
@@ -102,6 +140,31 @@ impl AshError {
     pub fn http_status(&self) -> u16 {
         self.code.http_status()
     }
+
+    /// Build a client-safe JSON response body.
+    ///
+    /// Produces `{"error": "ASH_REPLAY_DETECTED", "status": 409}`. The
+    /// message is deliberately omitted: it may carry parser or platform
+    /// details that are useful in logs but should not reach clients.
+    pub fn to_client_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": self.code.as_str(),
+            "status": self.http_status(),
+        })
+    }
+
+    /// Build an RFC 7807 `application/problem+json` response body.
+    ///
+    /// Produces `type` (`urn:ash:error:<CODE>`), a fixed `title` per code
+    /// and `status`. Like [`to_client_json`](Self::to_client_json), the
+    /// message is never included.
+    pub fn to_problem_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": format!("urn:ash:error:{}", self.code.as_str()),
+            "title": self.code.title(),
+            "status": self.http_status(),
+        })
+    }
 }
 
 impl fmt::Display for AshError {
@@ -110,6 +173,7 @@ impl fmt::Display for AshError {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for AshError {}
 
 /// Convenience functions for creating common errors.
@@ -142,6 +206,27 @@ impl AshError {
         )
     }
 
+    /// Proof format invalid.
+    pub fn invalid_proof_format(reason: &str) -> Self {
+        Self::new(
+            AshErrorCode::InvalidProofFormat,
+            format!("Invalid proof format: {}", reason),
+        )
+    }
+
+    /// Verification could not be completed.
+    pub fn verification_failed(reason: &str) -> Self {
+        Self::new(
+            AshErrorCode::VerificationFailed,
+            format!("Verification failed: {}", reason),
+        )
+    }
+
+    /// Context already consumed.
+    pub fn context_consumed() -> Self {
+        Self::new(AshErrorCode::ContextConsumed, "Context already consumed")
+    }
+
     /// Canonicalization failed.
     pub fn canonicalization_failed(reason: &str) -> Self {
         Self::new(
@@ -160,6 +245,7 @@ mod tests {
         assert_eq!(AshErrorCode::InvalidContext.http_status(), 400);
         assert_eq!(AshErrorCode::ContextExpired.http_status(), 410);
         assert_eq!(AshErrorCode::ReplayDetected.http_status(), 409);
+        assert_eq!(AshErrorCode::RngUnavailable.http_status(), 500);
     }
 
     #[test]
@@ -168,6 +254,87 @@ mod tests {
         assert_eq!(AshErrorCode::ReplayDetected.as_str(), "ASH_REPLAY_DETECTED");
     }
 
+    #[test]
+    fn test_unified_error_codes() {
+        assert_eq!(
+            AshErrorCode::InvalidProofFormat.as_str(),
+            "ASH_INVALID_PROOF_FORMAT"
+        );
+        assert_eq!(
+            AshErrorCode::VerificationFailed.as_str(),
+            "ASH_VERIFICATION_FAILED"
+        );
+        assert_eq!(
+            AshErrorCode::ContextConsumed.as_str(),
+            "ASH_CONTEXT_CONSUMED"
+        );
+
+        assert_eq!(AshErrorCode::InvalidProofFormat.http_status(), 400);
+        assert_eq!(AshErrorCode::VerificationFailed.http_status(), 400);
+        assert_eq!(AshErrorCode::ContextConsumed.http_status(), 409);
+
+        assert_eq!(
+            AshError::invalid_proof_format("bad").code(),
+            AshErrorCode::InvalidProofFormat
+        );
+        assert_eq!(
+            AshError::verification_failed("bad").code(),
+            AshErrorCode::VerificationFailed
+        );
+        assert_eq!(
+            AshError::context_consumed().code(),
+            AshErrorCode::ContextConsumed
+        );
+    }
+
+    #[test]
+    fn test_to_client_json_shape() {
+        let body = AshError::replay_detected().to_client_json();
+        assert_eq!(
+            body,
+            serde_json::json!({"error": "ASH_REPLAY_DETECTED", "status": 409})
+        );
+    }
+
+    #[test]
+    fn test_to_problem_json_shape() {
+        let body = AshError::context_expired().to_problem_json();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "urn:ash:error:ASH_CONTEXT_EXPIRED",
+                "title": "Context expired",
+                "status": 410,
+            })
+        );
+    }
+
+    #[test]
+    fn test_client_json_omits_message() {
+        let secret = "nonce=deadbeef proof=cafebabe";
+        let err = AshError::canonicalization_failed(secret);
+
+        for body in [err.to_client_json(), err.to_problem_json()] {
+            let text = body.to_string();
+            assert!(!text.contains("deadbeef"));
+            assert!(!text.contains("cafebabe"));
+
+            let keys: Vec<&String> = body.as_object().unwrap().keys().collect();
+            for forbidden in ["message", "detail", "nonce", "proof", "secret"] {
+                assert!(!keys.iter().any(|k| k.as_str() == forbidden));
+            }
+        }
+    }
+
+    #[test]
+    fn test_error_is_std_error() {
+        let err: Box<dyn std::error::Error> = Box::new(AshError::context_consumed());
+        assert_eq!(
+            err.to_string(),
+            "ASH_CONTEXT_CONSUMED: Context already consumed"
+        );
+    }
+
     #[test]
     fn test_error_display() {
         let err = AshError::invalid_context();
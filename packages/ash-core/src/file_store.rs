@@ -0,0 +1,302 @@
+//! JSON file-backed context store for single-node deployments.
+//!
+//! Enabled with the `file-store` feature. Unlike [`MemoryContextStore`],
+//! consumed contexts survive a restart, so a replay captured before the
+//! restart is still rejected after it.
+//!
+//! [`MemoryContextStore`]: crate::MemoryContextStore
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::store::{ContextStore, ContextUse};
+use crate::types::StoredContext;
+
+/// Context store persisted to a JSON file.
+///
+/// The whole map is rewritten on every change: the new contents go to a
+/// temporary file next to `path`, which is then renamed over it, so a crash
+/// mid-write leaves either the old or the new file, never a torn one.
+/// Suitable for modest context volumes on one node; shared deployments need
+/// a database-backed [`ContextStore`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ash_core::{ContextStore, FileContextStore};
+///
+/// let store = FileContextStore::open("/var/lib/app/ash-contexts.json", 1_700_000_000_000)?;
+/// let context = store.get("ctx_abc");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct FileContextStore {
+    path: PathBuf,
+    contexts: RwLock<HashMap<String, StoredContext>>,
+}
+
+impl FileContextStore {
+    /// Open the store at `path`, creating it if the file does not exist.
+    ///
+    /// Contexts already expired at `now_ms` are pruned on load.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file cannot be read or written, or an
+    /// `InvalidData` error if it is not a valid context map.
+    pub fn open(path: impl AsRef<Path>, now_ms: u64) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let mut contexts: HashMap<String, StoredContext> = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        contexts.retain(|_, context| !context.is_expired(now_ms));
+
+        persist(&path, &contexts)?;
+        Ok(Self {
+            path,
+            contexts: RwLock::new(contexts),
+        })
+    }
+
+    /// Insert or replace a context and persist the store.
+    ///
+    /// If the file cannot be written the in-memory map is rolled back, so
+    /// the store never serves a context that would not survive a restart.
+    pub fn insert(&self, context: StoredContext) -> io::Result<()> {
+        let mut contexts = self.contexts.write().expect("context store lock poisoned");
+        let context_id = context.context_id.clone();
+        let previous = contexts.insert(context_id.clone(), context);
+
+        if let Err(e) = persist(&self.path, &contexts) {
+            match previous {
+                Some(previous) => contexts.insert(context_id, previous),
+                None => contexts.remove(&context_id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Drop all contexts expired at `now_ms` and persist the store.
+    ///
+    /// Returns the number of contexts removed.
+    pub fn purge_expired(&self, now_ms: u64) -> io::Result<usize> {
+        let mut contexts = self.contexts.write().expect("context store lock poisoned");
+        let before = contexts.len();
+        contexts.retain(|_, context| !context.is_expired(now_ms));
+
+        let removed = before - contexts.len();
+        if removed > 0 {
+            persist(&self.path, &contexts)?;
+        }
+        Ok(removed)
+    }
+
+    /// Number of stored contexts (including expired, unpurged ones).
+    pub fn len(&self) -> usize {
+        self.contexts
+            .read()
+            .expect("context store lock poisoned")
+            .len()
+    }
+
+    /// Whether the store holds no contexts.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ContextStore for FileContextStore {
+    fn get(&self, context_id: &str) -> Option<StoredContext> {
+        self.contexts
+            .read()
+            .expect("context store lock poisoned")
+            .get(context_id)
+            .cloned()
+    }
+
+    /// Consume a context and persist the change before reporting `FirstUse`.
+    ///
+    /// If the file cannot be written the context stays consumed in memory
+    /// and `AlreadyConsumed` is returned: a consumption that would not
+    /// survive a restart must not be accepted.
+    fn consume(&self, context_id: &str, now_ms: u64) -> ContextUse {
+        let mut contexts = self.contexts.write().expect("context store lock poisoned");
+
+        let Some(context) = contexts.get_mut(context_id) else {
+            return ContextUse::NotFound;
+        };
+        if context.is_consumed() {
+            return ContextUse::AlreadyConsumed;
+        }
+        if context.is_expired(now_ms) {
+            return ContextUse::Expired;
+        }
+
+        context.consumed_at = Some(now_ms);
+        match persist(&self.path, &contexts) {
+            Ok(()) => ContextUse::FirstUse,
+            Err(_) => ContextUse::AlreadyConsumed,
+        }
+    }
+}
+
+/// Write `contexts` to a temporary file and atomically rename it to `path`.
+///
+/// On Unix the parent directory is synced after the rename, so the new
+/// directory entry itself is durable.
+fn persist(path: &Path, contexts: &HashMap<String, StoredContext>) -> io::Result<()> {
+    let json =
+        serde_json::to_vec(contexts).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(&json)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    sync_parent_dir(path)
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(parent)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AshMode;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "ash-file-store-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn context(id: &str, expires_at: u64) -> StoredContext {
+        StoredContext {
+            context_id: id.to_string(),
+            binding: "POST /api".to_string(),
+            mode: AshMode::Balanced,
+            issued_at: 1000,
+            expires_at,
+            nonce: Some("nonce".to_string()),
+            consumed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_file_store_persists_across_reopen() {
+        let path = temp_path("reopen");
+
+        {
+            let store = FileContextStore::open(&path, 1000).unwrap();
+            store.insert(context("ctx_1", 5000)).unwrap();
+            store.insert(context("ctx_2", 5000)).unwrap();
+            assert_eq!(store.consume("ctx_1", 1500), ContextUse::FirstUse);
+        }
+
+        let store = FileContextStore::open(&path, 2000).unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get("ctx_1").unwrap().consumed_at, Some(1500));
+        // Replay after restart is still rejected
+        assert_eq!(store.consume("ctx_1", 2000), ContextUse::AlreadyConsumed);
+        assert_eq!(store.consume("ctx_2", 2000), ContextUse::FirstUse);
+        assert_eq!(store.consume("ctx_3", 2000), ContextUse::NotFound);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_prunes_expired_on_load() {
+        let path = temp_path("prune");
+
+        {
+            let store = FileContextStore::open(&path, 1000).unwrap();
+            store.insert(context("ctx_old", 2000)).unwrap();
+            store.insert(context("ctx_new", 9000)).unwrap();
+        }
+
+        let store = FileContextStore::open(&path, 3000).unwrap();
+        assert!(store.get("ctx_old").is_none());
+        assert!(store.get("ctx_new").is_some());
+
+        // Pruning was written back
+        drop(store);
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("ctx_old"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_purge_and_expire() {
+        let path = temp_path("purge");
+        let store = FileContextStore::open(&path, 1000).unwrap();
+        store.insert(context("ctx_1", 2000)).unwrap();
+        store.insert(context("ctx_2", 9000)).unwrap();
+
+        assert_eq!(store.consume("ctx_1", 2500), ContextUse::Expired);
+        assert_eq!(store.purge_expired(2500).unwrap(), 1);
+        assert_eq!(store.purge_expired(2500).unwrap(), 0);
+        assert_eq!(store.len(), 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_insert_rolls_back_on_write_failure() {
+        let path = temp_path("rollback");
+        let store = FileContextStore::open(&path, 1000).unwrap();
+        store.insert(context("ctx_1", 5000)).unwrap();
+
+        // A directory in place of the temporary file makes every write fail
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+        fs::create_dir(&tmp_path).unwrap();
+
+        assert!(store.insert(context("ctx_2", 5000)).is_err());
+        assert!(store.get("ctx_2").is_none());
+        assert!(store.insert(context("ctx_1", 9000)).is_err());
+        assert_eq!(store.get("ctx_1").unwrap().expires_at, 5000);
+        assert_eq!(store.len(), 1);
+
+        fs::remove_dir(&tmp_path).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_rejects_corrupt_file() {
+        let path = temp_path("corrupt");
+        fs::write(&path, b"not json").unwrap();
+
+        let err = FileContextStore::open(&path, 1000).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
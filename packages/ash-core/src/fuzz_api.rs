@@ -0,0 +1,79 @@
+//! Byte-oriented entry points for fuzz harnesses.
+//!
+//! Enabled with the `fuzzing` feature. Each function takes arbitrary
+//! bytes, interprets them as UTF-8 (lossily, where the canonicalizer
+//! takes text) and runs the corresponding canonicalizer. Any panic is a
+//! bug; both `Ok` and `Err` are acceptable outcomes.
+//!
+//! ```rust,ignore
+//! // fuzz/fuzz_targets/canonicalize_json.rs
+//! #![no_main]
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+//!     let _ = ash_core::fuzz_api::canonicalize_json(data);
+//! });
+//! ```
+
+use crate::canonicalize;
+use crate::errors::AshError;
+
+/// Run [`canonicalize_json`](crate::canonicalize_json) on arbitrary bytes.
+pub fn canonicalize_json(data: &[u8]) -> Result<String, AshError> {
+    canonicalize::canonicalize_json(&String::from_utf8_lossy(data))
+}
+
+/// Run [`canonicalize_json_jcs`](crate::canonicalize_json_jcs) on arbitrary bytes.
+pub fn canonicalize_json_jcs(data: &[u8]) -> Result<String, AshError> {
+    canonicalize::canonicalize_json_jcs(&String::from_utf8_lossy(data))
+}
+
+/// Run [`canonicalize_urlencoded`](crate::canonicalize_urlencoded) on arbitrary bytes.
+pub fn canonicalize_urlencoded(data: &[u8]) -> Result<String, AshError> {
+    canonicalize::canonicalize_urlencoded(&String::from_utf8_lossy(data))
+}
+
+/// Run [`canonicalize_query`](crate::canonicalize_query) on arbitrary bytes.
+pub fn canonicalize_query(data: &[u8]) -> Result<String, AshError> {
+    canonicalize::canonicalize_query(&String::from_utf8_lossy(data))
+}
+
+/// Run `canonicalize_cbor` on arbitrary bytes (also needs the `cbor` feature).
+#[cfg(feature = "cbor")]
+pub fn canonicalize_cbor(data: &[u8]) -> Result<String, AshError> {
+    canonicalize::canonicalize_cbor(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift64 so failures are reproducible.
+    fn random_bytes(state: &mut u64, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                *state ^= *state << 13;
+                *state ^= *state >> 7;
+                *state ^= *state << 17;
+                *state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_random_bytes_never_panic() {
+        let mut state = 0x9E37_79B9_7F4A_7C15;
+        // Seeds that steer inputs toward interesting syntax
+        let seeds: [&[u8]; 4] = [b"{\"a\":[1,", b"a=%2", b"\"\\u", b"[1e999"];
+
+        for i in 0..2000 {
+            let mut data = seeds[i % seeds.len()].to_vec();
+            data.extend(random_bytes(&mut state, i % 64));
+
+            let _ = canonicalize_json(&data);
+            let _ = canonicalize_json_jcs(&data);
+            let _ = canonicalize_urlencoded(&data);
+            let _ = canonicalize_query(&data);
+            #[cfg(feature = "cbor")]
+            let _ = canonicalize_cbor(&data);
+        }
+    }
+}
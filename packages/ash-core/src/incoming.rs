@@ -0,0 +1,189 @@
+//! Framework-agnostic verification of a complete incoming request.
+//!
+//! [`verify_incoming`] runs the whole server-side pipeline on a request
+//! that the host framework has already read into memory:
+//!
+//! 1. Normalizes the `METHOD /path` binding
+//! 2. Canonicalizes the raw body according to its `Content-Type`
+//! 3. Looks the context up in a [`ContextStore`]
+//! 4. Checks expiry, replay, the endpoint binding and the proof, and
+//!    consumes the context on success
+//!
+//! Every failure is reported as an [`AshOutcome`] rather than an error, so
+//! callers can map outcomes to responses in one `match`.
+
+use crate::canonicalize::{canonicalize_json, canonicalize_urlencoded};
+use crate::errors::{AshError, AshErrorCode};
+use crate::normalize_binding;
+use crate::proof::{hash_body, hash_optional_body, normalize_content_type};
+use crate::store::{verify_and_consume_for_binding, ContextStore};
+use crate::types::VerificationResult;
+
+/// The ASH headers of a request (`X-ASH-Context`, `X-ASH-Timestamp`,
+/// `X-ASH-Proof`), already read from the transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AshHeaders<'a> {
+    /// Context ID
+    pub context_id: &'a str,
+    /// Request timestamp (milliseconds)
+    pub timestamp: &'a str,
+    /// Proof sent by the client
+    pub proof: &'a str,
+}
+
+/// An incoming request, as handed over by the host framework.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AshIncoming<'a> {
+    /// HTTP method (any case)
+    pub method: &'a str,
+    /// Request path; a query string is ignored
+    pub path: &'a str,
+    /// `Host` header, if any.
+    ///
+    /// Not part of the binding: contexts are bound to `METHOD /path` only,
+    /// so the same proof verifies on every host that serves the route (as
+    /// the client SDKs build it). It is carried so callers that pin
+    /// contexts to a host can check it next to verification.
+    pub host: Option<&'a str>,
+    /// `Content-Type` header, if any (parameters such as `charset` are ignored)
+    pub content_type: Option<&'a str>,
+    /// Raw request body
+    pub body: &'a [u8],
+    /// ASH headers
+    pub headers: AshHeaders<'a>,
+}
+
+/// Outcome of [`verify_incoming`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AshOutcome {
+    /// The request verified and its context is now consumed.
+    Valid {
+        /// Canonical body the proof covers (`""` for an empty body)
+        canonical_body: String,
+    },
+    /// The proof does not match the request.
+    IntegrityFailed,
+    /// The context was already consumed.
+    Replay,
+    /// The context has expired.
+    Expired,
+    /// The request arrived on a different endpoint than the context's.
+    EndpointMismatch,
+    /// The request could not be verified: unknown context, bad binding,
+    /// unsupported content type, body that does not canonicalize, or
+    /// malformed headers.
+    Malformed(AshErrorCode),
+}
+
+impl AshOutcome {
+    /// Whether the request verified.
+    pub fn is_valid(&self) -> bool {
+        matches!(self, AshOutcome::Valid { .. })
+    }
+}
+
+/// Verify a complete incoming request and consume its context (server-side).
+///
+/// Bodies are canonicalized by media type: `application/json` with
+/// [`canonicalize_json`] and `application/x-www-form-urlencoded` with
+/// [`canonicalize_urlencoded`]. An empty body needs no content type and
+/// hashes as [`EMPTY_BODY_HASH`](crate::EMPTY_BODY_HASH). Any other media
+/// type is `Malformed(UnsupportedContentType)`.
+///
+/// Only a valid proof consumes the context, so concurrent duplicates of
+/// the same valid request yield exactly one `Valid`; the rest see `Replay`.
+pub fn verify_incoming(store: &dyn ContextStore, req: &AshIncoming<'_>, now_ms: u64) -> AshOutcome {
+    match run_pipeline(store, req, now_ms) {
+        Ok((VerificationResult::Valid, verified)) => AshOutcome::Valid {
+            canonical_body: verified.canonical_body,
+        },
+        Ok((VerificationResult::IntegrityFailed, _)) => AshOutcome::IntegrityFailed,
+        Ok((VerificationResult::Replay, _)) => AshOutcome::Replay,
+        Ok((VerificationResult::Expired, _)) => AshOutcome::Expired,
+        Ok((VerificationResult::EndpointMismatch, _)) => AshOutcome::EndpointMismatch,
+        Err(e) => AshOutcome::Malformed(e.code()),
+    }
+}
+
+/// Request details derived while verifying.
+#[cfg_attr(not(feature = "axum"), allow(dead_code))]
+pub(crate) struct VerifiedIncoming {
+    /// Normalized binding
+    pub(crate) binding: String,
+    /// Hash of the canonical body
+    pub(crate) body_hash: String,
+    /// Canonical body (`""` for an empty body)
+    pub(crate) canonical_body: String,
+}
+
+/// [`verify_incoming`] for framework adapters: any outcome other than
+/// `Valid` is returned as the matching [`AshError`], with its message.
+#[cfg(feature = "axum")]
+pub(crate) fn verify_incoming_checked(
+    store: &dyn ContextStore,
+    req: &AshIncoming<'_>,
+    now_ms: u64,
+) -> Result<VerifiedIncoming, AshError> {
+    let (result, verified) = run_pipeline(store, req, now_ms)?;
+    match result.to_error() {
+        Some(err) => Err(err),
+        None => Ok(verified),
+    }
+}
+
+fn run_pipeline(
+    store: &dyn ContextStore,
+    req: &AshIncoming<'_>,
+    now_ms: u64,
+) -> Result<(VerificationResult, VerifiedIncoming), AshError> {
+    let binding = normalize_binding(req.method, req.path)?;
+    let canonical_body = canonicalize_body(req.content_type, req.body)?;
+    let body_hash = if canonical_body.is_empty() {
+        hash_optional_body(None)
+    } else {
+        hash_body(&canonical_body)
+    };
+
+    let headers = &req.headers;
+    let stored = store
+        .get(headers.context_id)
+        .ok_or_else(AshError::invalid_context)?;
+    let result = verify_and_consume_for_binding(
+        store,
+        &stored,
+        &binding,
+        headers.timestamp,
+        &body_hash,
+        headers.proof,
+        now_ms,
+    )?;
+
+    Ok((
+        result,
+        VerifiedIncoming {
+            binding,
+            body_hash,
+            canonical_body,
+        },
+    ))
+}
+
+/// Canonicalize a raw body according to its declared content type.
+fn canonicalize_body(content_type: Option<&str>, body: &[u8]) -> Result<String, AshError> {
+    if body.is_empty() {
+        return Ok(String::new());
+    }
+
+    let media_type = normalize_content_type(content_type.unwrap_or(""));
+    let body = std::str::from_utf8(body)
+        .map_err(|_| AshError::canonicalization_failed("Request body is not valid UTF-8"))?;
+
+    match media_type.as_str() {
+        "application/json" => canonicalize_json(body),
+        "application/x-www-form-urlencoded" => canonicalize_urlencoded(body),
+        _ => Err(AshError::new(
+            AshErrorCode::UnsupportedContentType,
+            "Unsupported content type",
+        )),
+    }
+}
@@ -34,34 +34,168 @@
 //! ).unwrap();
 //! ```
 //!
+//! ## `no_std` Support
+//!
+//! The default `std` feature can be disabled for embedded verifiers. The
+//! crate then builds as `#![no_std]` with `alloc` and keeps the proof core:
+//!
+//! - [`derive_client_secret`], [`build_proof_v21`], [`verify_proof_v21`]
+//!   and their checked, epoch, base64url and envelope variants
+//! - [`hash_body`], [`hash_optional_body`], [`BodyHasher`]
+//! - Scoped and unified proofs (payloads parsed with `serde_json`)
+//! - [`timing_safe_equal`], [`normalize_binding`] and the error types
+//! - [`generate_nonce_with`] with a host-provided [`RandomSource`]
+//!
+//! Canonicalization (`canonicalize_*`), nonce and context ID generation,
+//! and the context/proof stores require `std`, as do the `axum`, `actix`,
+//! `cache`, `cbor`, `json5`, `protobuf`, `file-store`, `diagnostics` and
+//! `timing-instrumentation` features. A `no_std` verifier receives the
+//! canonical body (or its hash) and the nonce from its host.
+//!
+//! ```toml
+//! ash-core = { version = "2.3", default-features = false }
+//! ```
+//!
+//! The `cdylib` crate type is dropped by rustc on bare-metal targets. On a
+//! hosted target, check the `no_std` build as an rlib with
+//! `cargo rustc -p ash-core --lib --no-default-features --crate-type rlib`.
+//!
 //! ## Security Notes
 //!
 //! ASH verifies **what** is being submitted, not **who** is submitting it.
 //! It should be used alongside authentication systems (JWT, OAuth, etc.).
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(feature = "actix")]
+mod actix;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "std")]
 mod canonicalize;
 mod compare;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
 mod errors;
+#[cfg(feature = "file-store")]
+mod file_store;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz_api;
+#[cfg(feature = "std")]
+mod incoming;
+#[cfg(feature = "axum")]
+mod middleware;
 mod proof;
+#[cfg(feature = "std")]
+mod store;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+#[cfg(feature = "timing-instrumentation")]
+mod timed;
 mod types;
 
-pub use canonicalize::{canonicalize_json, canonicalize_urlencoded};
-pub use compare::timing_safe_equal;
+#[cfg(feature = "std")]
+pub use batch::{
+    build_batch_proof, verify_batch_inclusion, verify_batch_proof, verify_proof_v21_batch,
+    BatchInclusionProof, BatchProof, BatchVerifyResult, V21VerifyItem,
+};
+#[cfg(feature = "cache")]
+pub use cache::CachingCanonicalizer;
+#[cfg(feature = "cbor")]
+pub use canonicalize::canonicalize_cbor;
+#[cfg(feature = "json5")]
+pub use canonicalize::canonicalize_json5;
+#[cfg(feature = "protobuf")]
+pub use canonicalize::canonicalize_protobuf;
+#[cfg(feature = "std")]
+pub use canonicalize::{
+    canonicalize_and_digest_json, canonicalize_headers, canonicalize_json, canonicalize_json_batch,
+    canonicalize_json_jcs, canonicalize_json_streaming, canonicalize_json_typed,
+    canonicalize_json_with_options, canonicalize_ndjson, canonicalize_query,
+    canonicalize_urlencoded, canonicalize_urlencoded_with_options, is_canonical_json,
+    CanonicalizeOptions, InvisibleChars, JsonShape, KeyCase, KeySort, NumberMode, PairSeparators,
+    PercentMode, StringNormalization, UrlEncodedOptions, DEFAULT_MAX_INPUT_BYTES,
+};
+pub use compare::{timing_safe_equal, timing_safe_equal_padded};
 pub use errors::{AshError, AshErrorCode};
+#[cfg(feature = "file-store")]
+pub use file_store::FileContextStore;
+#[cfg(feature = "std")]
+pub use incoming::{verify_incoming, AshHeaders, AshIncoming, AshOutcome};
 pub use proof::{
     build_proof, verify_proof,
     // v2.1 functions
-    generate_nonce, generate_context_id,
-    derive_client_secret, build_proof_v21,
-    verify_proof_v21, hash_body,
+    derive_client_secret, derive_client_secret_v2, build_proof_v21, build_proof_v21_bytes,
+    compute_expected_proof_v21, try_derive_client_secret, try_build_proof_v21,
+    verify_proof_v21, verify_proof_v21_checked, ensure_proof_v21, parse_timestamp_ms,
+    hash_body, BodyHasher,
+    hash_body_bytes,
+    is_well_formed_proof_v21, is_well_formed_body_hash, is_well_formed_scope_hash,
+    is_well_formed_chain_hash,
+    hash_optional_body, EMPTY_BODY_HASH,
+    derive_client_secret_epoch, verify_proof_v21_epoch, verify_proof_v21_multi,
+    verify_proof_v21_any_binding,
+    derive_client_secret_with_device, verify_proof_v21_with_device,
+    derive_client_secret_env, verify_proof_v21_env,
+    build_proof_v21_committed, verify_proof_v21_committed,
+    HashAlg, build_proof_v21_with_alg, verify_proof_v21_with_alg,
+    verify_with_context, build_receipt, verify_proof_v21_with_binding,
+    normalize_content_type, build_proof_v21_with_content_type,
+    verify_proof_v21_with_content_type,
+    build_proof_v21_b64, verify_proof_v21_b64,
+    hex_proof_to_b64, b64_proof_to_hex,
     // v2.2 scoping functions
-    extract_scoped_fields, build_proof_v21_scoped,
-    verify_proof_v21_scoped, hash_scoped_body,
+    extract_scoped_fields, extract_scoped_fields_with_options, ScopeOptions, ScopeSyntax,
+    resolve_scope, ScopeResolution, scope_hash, build_proof_v21_scoped,
+    verify_proof_v21_scoped, verify_proof_v21_scoped_from_hash,
+    verify_proof_v21_scoped_by_hash, hash_scoped_body,
+    hash_scoped_body_with_options, PreSortedScope, verify_proof_v21_scoped_presorted,
     // v2.3 unified functions (scoping + chaining)
     UnifiedProofResult, hash_proof,
-    build_proof_v21_unified, verify_proof_v21_unified,
+    build_proof_v21_unified, verify_proof_v21_unified, verify_proof_v21_unified_from_hash,
+    ensure_proof_v21_unified,
+    verify_proof_v21_unified_presorted,
+    build_proof_v21_moded, verify_proof_v21_moded,
+    ChainVerifyResult, verify_proof_chain,
+    // v2.4 length-prefixed proof message
+    build_proof_v24, verify_proof_v24,
+    // Proof envelope (self-describing version header)
+    ProofVersion, encode_proof_envelope, decode_proof_envelope, verify_auto,
+    // Nonce and context ID helpers
+    RandomSource, generate_nonce_with, derive_context_id,
+};
+#[cfg(feature = "std")]
+pub use proof::{
+    generate_context_id, generate_nonce, generate_nonce_bytes, hash_body_batch, hash_ndjson,
+    try_generate_context_id, try_generate_nonce, try_generate_nonce_bytes, NdjsonHash, OsRandom,
 };
-pub use types::{AshMode, BuildProofInput, VerifyInput};
+#[cfg(feature = "std")]
+pub use store::{
+    verify_and_consume, ContextStore, ContextUse, MemoryContextStore, MemoryProofStore, ProofStore,
+    ProofUse,
+};
+pub use types::{
+    AshMode, BuildProofInput, ProofReceipt, StoredContext, VerificationResult, VerifyInput,
+};
+
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::canonical_diff;
+
+#[cfg(feature = "test-vectors")]
+pub use test_vectors::{build_proof_v21_message, build_proof_v21_unified_message};
+#[cfg(feature = "timing-instrumentation")]
+pub use timed::verify_proof_v21_timed;
+
+#[cfg(feature = "axum")]
+pub use middleware::{AshLayer, AshService, VerifiedAsh};
 
 /// Normalize a binding string to canonical form.
 ///
@@ -128,6 +262,130 @@ pub fn normalize_binding(method: &str, path: &str) -> Result<String, AshError> {
     Ok(format!("{} {}", method, normalized))
 }
 
+/// Placeholder that parameterized path segments normalize to.
+const BINDING_PARAM_PLACEHOLDER: &str = "{}";
+
+/// Normalize a binding pattern with parameterized path segments.
+///
+/// Applies the same rules as [`normalize_binding`], then replaces every
+/// `:param` or `{param}` segment with the canonical placeholder `{}`, so
+/// a proof can be bound to a route rather than a concrete path.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::normalize_binding_pattern;
+///
+/// let pattern = normalize_binding_pattern("get", "/api/users/:id/posts/{post_id}/").unwrap();
+/// assert_eq!(pattern, "GET /api/users/{}/posts/{}");
+/// ```
+pub fn normalize_binding_pattern(method: &str, pattern: &str) -> Result<String, AshError> {
+    let binding = normalize_binding(method, pattern)?;
+    let (method, path) = split_binding(&binding);
+
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(|segment| {
+            let is_param = (segment.len() > 1 && segment.starts_with(':'))
+                || (segment.len() > 2 && segment.starts_with('{') && segment.ends_with('}'));
+            if is_param {
+                BINDING_PARAM_PLACEHOLDER
+            } else {
+                segment
+            }
+        })
+        .collect();
+
+    Ok(format!("{} {}", method, segments.join("/")))
+}
+
+/// Check whether a concrete binding matches a binding pattern.
+///
+/// Both arguments must already be normalized (see [`normalize_binding`]
+/// and [`normalize_binding_pattern`]). Methods must be equal, paths must
+/// have the same number of segments, and each `{}` placeholder matches
+/// exactly one non-empty segment.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::match_binding;
+///
+/// assert!(match_binding("GET /api/users/{}", "GET /api/users/123"));
+/// assert!(!match_binding("GET /api/users/{}", "GET /api/users/123/posts"));
+/// ```
+pub fn match_binding(pattern: &str, concrete: &str) -> bool {
+    let (pattern_method, pattern_path) = split_binding(pattern);
+    let (concrete_method, concrete_path) = split_binding(concrete);
+
+    if pattern_method != concrete_method {
+        return false;
+    }
+
+    let pattern_segments: Vec<&str> = pattern_path.split('/').collect();
+    let concrete_segments: Vec<&str> = concrete_path.split('/').collect();
+
+    pattern_segments.len() == concrete_segments.len()
+        && pattern_segments
+            .iter()
+            .zip(&concrete_segments)
+            .all(|(p, c)| {
+                if *p == BINDING_PARAM_PLACEHOLDER {
+                    !c.is_empty()
+                } else {
+                    p == c
+                }
+            })
+}
+
+/// Parse a binding string back into its method and path.
+///
+/// Inverse of [`normalize_binding`]: splits on the first space, so the
+/// path may itself contain spaces. The method must be non-empty and the
+/// path must start with `/`; the parts are returned as-is, without
+/// re-normalizing.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::parse_binding;
+///
+/// let (method, path) = parse_binding("POST /api/update").unwrap();
+/// assert_eq!(method, "POST");
+/// assert_eq!(path, "/api/update");
+///
+/// assert!(parse_binding("POST").is_err());
+/// ```
+pub fn parse_binding(binding: &str) -> Result<(String, String), AshError> {
+    let (method, path) = binding.split_once(' ').ok_or_else(|| {
+        AshError::new(
+            AshErrorCode::MalformedRequest,
+            "Binding must be \"METHOD /path\"",
+        )
+    })?;
+
+    if method.is_empty() {
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            "Binding method cannot be empty",
+        ));
+    }
+
+    if !path.starts_with('/') {
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            "Binding path must start with /",
+        ));
+    }
+
+    Ok((String::from(method), String::from(path)))
+}
+
+/// Split a normalized binding into method and path.
+fn split_binding(binding: &str) -> (&str, &str) {
+    binding.split_once(' ').unwrap_or((binding, ""))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,4 +444,85 @@ mod tests {
     fn test_normalize_binding_no_leading_slash() {
         assert!(normalize_binding("GET", "api/users").is_err());
     }
+
+    #[test]
+    fn test_normalize_binding_pattern_params() {
+        assert_eq!(
+            normalize_binding_pattern("GET", "/api/users/:id").unwrap(),
+            "GET /api/users/{}"
+        );
+        assert_eq!(
+            normalize_binding_pattern("GET", "/api/users/{id}").unwrap(),
+            "GET /api/users/{}"
+        );
+    }
+
+    #[test]
+    fn test_normalize_binding_pattern_multiple_params() {
+        assert_eq!(
+            normalize_binding_pattern("put", "/orgs/:org/users/{user}/roles/:role").unwrap(),
+            "PUT /orgs/{}/users/{}/roles/{}"
+        );
+    }
+
+    #[test]
+    fn test_normalize_binding_pattern_slashes() {
+        assert_eq!(
+            normalize_binding_pattern("GET", "/api//users/:id/").unwrap(),
+            "GET /api/users/{}"
+        );
+    }
+
+    #[test]
+    fn test_match_binding() {
+        let pattern = normalize_binding_pattern("GET", "/api/users/:id/posts/:post").unwrap();
+
+        assert!(match_binding(&pattern, "GET /api/users/123/posts/9"));
+        assert!(!match_binding(&pattern, "POST /api/users/123/posts/9"));
+        assert!(!match_binding(&pattern, "GET /api/users/123/posts"));
+        assert!(!match_binding(&pattern, "GET /api/users/123/comments/9"));
+    }
+
+    #[test]
+    fn test_match_binding_normalized_concrete() {
+        let pattern = normalize_binding_pattern("GET", "/api/users/{id}").unwrap();
+        let concrete = normalize_binding("get", "/api//users/42/").unwrap();
+
+        assert!(match_binding(&pattern, &concrete));
+        assert!(match_binding("GET /", "GET /"));
+    }
+
+    #[test]
+    fn test_parse_binding() {
+        let (method, path) = parse_binding("POST /api/update").unwrap();
+        assert_eq!(method, "POST");
+        assert_eq!(path, "/api/update");
+    }
+
+    #[test]
+    fn test_parse_binding_round_trip() {
+        let binding = normalize_binding("patch", "/api//users/42/").unwrap();
+        let (method, path) = parse_binding(&binding).unwrap();
+        assert_eq!(normalize_binding(&method, &path).unwrap(), binding);
+    }
+
+    #[test]
+    fn test_parse_binding_path_with_spaces() {
+        let (method, path) = parse_binding("GET /files/a%20b").unwrap();
+        assert_eq!(method, "GET");
+        assert_eq!(path, "/files/a%20b");
+
+        // Only the first space separates method and path
+        let (method, path) = parse_binding("GET /files/a b").unwrap();
+        assert_eq!(method, "GET");
+        assert_eq!(path, "/files/a b");
+    }
+
+    #[test]
+    fn test_parse_binding_invalid() {
+        for binding in ["GET", "", " /api", "GET api", "GET "] {
+            let err = parse_binding(binding).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::MalformedRequest, "{:?}", binding);
+        }
+    }
 }
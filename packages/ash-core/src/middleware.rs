@@ -0,0 +1,262 @@
+//! Tower/axum middleware for server-side ASH v2.1 verification.
+//!
+//! Enabled with the `axum` feature. [`AshLayer`] verifies each request
+//! before it reaches the handler:
+//!
+//! 1. Reads the `X-ASH-Context`, `X-ASH-Timestamp` and `X-ASH-Proof` headers
+//! 2. Buffers the body and hands the request to
+//!    [`verify_incoming`](crate::verify_incoming), which canonicalizes it by
+//!    `Content-Type` (JSON or form data), looks the context up in a
+//!    [`ContextStore`], checks expiry, replay, the endpoint binding and
+//!    finally the proof, then consumes the context in the store
+//!
+//! Each context verifies once: a replay of a request that already passed
+//! is rejected with `ASH_REPLAY_DETECTED`. A failed proof does not consume
+//! the context. On failure the request is rejected with the error's HTTP
+//! status.
+//! On success a [`VerifiedAsh`] is inserted into the request extensions
+//! and can be taken directly as a handler argument.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::body::{Body, Bytes};
+use axum::extract::{FromRequestParts, Request};
+use axum::http::header::{CONTENT_TYPE, HOST};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use tower::{Layer, Service};
+
+use crate::errors::{AshError, AshErrorCode};
+use crate::incoming::{verify_incoming_checked, AshHeaders, AshIncoming};
+use crate::store::ContextStore;
+
+/// Header carrying the context ID.
+pub const HEADER_CONTEXT: &str = "x-ash-context";
+/// Header carrying the request timestamp.
+pub const HEADER_TIMESTAMP: &str = "x-ash-timestamp";
+/// Header carrying the client proof.
+pub const HEADER_PROOF: &str = "x-ash-proof";
+
+/// Default maximum request body size buffered for verification (2 MiB).
+pub const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Details of a successfully verified request.
+///
+/// Inserted into the request extensions by [`AshLayer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedAsh {
+    /// Context ID the request was verified against
+    pub context_id: String,
+    /// Canonical binding (e.g., "POST /api/update")
+    pub binding: String,
+    /// Request timestamp as sent by the client
+    pub timestamp: String,
+    /// SHA-256 hash of the canonical body
+    pub body_hash: String,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for VerifiedAsh {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Missing extension means the layer was not installed on this route
+        parts
+            .extensions
+            .get::<VerifiedAsh>()
+            .cloned()
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl IntoResponse for AshError {
+    fn into_response(self) -> Response {
+        let status =
+            StatusCode::from_u16(self.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (
+            status,
+            [(CONTENT_TYPE, "application/json")],
+            self.to_client_json().to_string(),
+        )
+            .into_response()
+    }
+}
+
+/// Layer that applies ASH verification to wrapped services.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use ash_core::{AshLayer, MemoryContextStore, VerifiedAsh};
+/// use axum::{routing::post, Router};
+///
+/// let store = Arc::new(MemoryContextStore::new());
+/// let app: Router = Router::new()
+///     .route("/api/transfer", post(|ash: VerifiedAsh| async move { ash.context_id }))
+///     .layer(AshLayer::new(store));
+/// ```
+#[derive(Debug)]
+pub struct AshLayer<S> {
+    store: Arc<S>,
+    body_limit: usize,
+}
+
+impl<S> AshLayer<S> {
+    /// Create a layer that looks contexts up in `store`.
+    pub fn new(store: Arc<S>) -> Self {
+        Self {
+            store,
+            body_limit: DEFAULT_BODY_LIMIT,
+        }
+    }
+
+    /// Set the maximum body size buffered for verification.
+    pub fn with_body_limit(mut self, limit: usize) -> Self {
+        self.body_limit = limit;
+        self
+    }
+}
+
+impl<S> Clone for AshLayer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: Arc::clone(&self.store),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+impl<S, I> Layer<I> for AshLayer<S> {
+    type Service = AshService<S, I>;
+
+    fn layer(&self, inner: I) -> Self::Service {
+        AshService {
+            inner,
+            store: Arc::clone(&self.store),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+/// Service produced by [`AshLayer`].
+#[derive(Debug)]
+pub struct AshService<S, I> {
+    inner: I,
+    store: Arc<S>,
+    body_limit: usize,
+}
+
+impl<S, I: Clone> Clone for AshService<S, I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            store: Arc::clone(&self.store),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+impl<S, I> Service<Request> for AshService<S, I>
+where
+    S: ContextStore + 'static,
+    I: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    I::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // Take the service that was driven to readiness, leave a clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let store = Arc::clone(&self.store);
+        let body_limit = self.body_limit;
+
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+
+            let bytes = match axum::body::to_bytes(body, body_limit).await {
+                Ok(bytes) => bytes,
+                Err(_) => {
+                    return Ok(AshError::new(
+                        AshErrorCode::MalformedRequest,
+                        "Request body could not be read",
+                    )
+                    .into_response())
+                }
+            };
+
+            match verify_request(store.as_ref(), &parts, &bytes) {
+                Ok(verified) => {
+                    parts.extensions.insert(verified);
+                    inner
+                        .call(Request::from_parts(parts, Body::from(bytes)))
+                        .await
+                }
+                Err(e) => Ok(e.into_response()),
+            }
+        })
+    }
+}
+
+/// Run the ASH checks against buffered request parts and body.
+fn verify_request(
+    store: &dyn ContextStore,
+    parts: &Parts,
+    body: &Bytes,
+) -> Result<VerifiedAsh, AshError> {
+    let headers = AshHeaders {
+        context_id: required_header(&parts.headers, HEADER_CONTEXT)?,
+        timestamp: required_header(&parts.headers, HEADER_TIMESTAMP)?,
+        proof: required_header(&parts.headers, HEADER_PROOF)?,
+    };
+    let request = AshIncoming {
+        method: parts.method.as_str(),
+        path: parts.uri.path(),
+        host: optional_header(&parts.headers, HOST.as_str()),
+        content_type: optional_header(&parts.headers, CONTENT_TYPE.as_str()),
+        body,
+        headers,
+    };
+
+    // Consumes the context on success, so a replayed request is rejected
+    let verified = verify_incoming_checked(store, &request, now_ms())?;
+
+    Ok(VerifiedAsh {
+        context_id: headers.context_id.to_string(),
+        binding: verified.binding,
+        timestamp: headers.timestamp.to_string(),
+        body_hash: verified.body_hash,
+    })
+}
+
+fn optional_header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn required_header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, AshError> {
+    optional_header(headers, name).ok_or_else(|| {
+        AshError::new(
+            AshErrorCode::MalformedRequest,
+            format!("Missing or invalid {} header", name),
+        )
+    })
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
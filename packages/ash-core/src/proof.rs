@@ -6,12 +6,18 @@
 //! - Canonical payload
 //! - Optional nonce (server-assisted mode)
 
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use sha2::{Digest, Sha256};
 
 use crate::compare::timing_safe_equal;
-use crate::errors::AshError;
-use crate::types::{AshMode, BuildProofInput, VerifyInput};
+use crate::errors::{AshError, AshErrorCode};
+use crate::types::{
+    AshMode, BuildProofInput, ProofReceipt, StoredContext, VerificationResult, VerifyInput,
+};
 
 /// Protocol version identifier.
 const ASH_VERSION: &str = "ASHv1";
@@ -357,6 +363,7 @@ mod tests {
 
 use hmac::{Hmac, Mac};
 use sha2::Sha256 as HmacSha256;
+use subtle::{Choice, ConditionallySelectable};
 
 type HmacSha256Type = Hmac<HmacSha256>;
 
@@ -364,6 +371,89 @@ type HmacSha256Type = Hmac<HmacSha256>;
 #[allow(dead_code)]
 const ASH_VERSION_V21: &str = "ASHv2.1";
 
+/// Source of random bytes for nonce generation.
+///
+/// [`OsRandom`] is the default. Implement this to inject another CSPRNG or,
+/// in tests only, a deterministic source.
+pub trait RandomSource {
+    /// Fill `buf` entirely with random bytes.
+    ///
+    /// # Errors
+    /// Returns `RngUnavailable` if no random bytes can be produced.
+    fn fill(&self, buf: &mut [u8]) -> Result<(), AshError>;
+}
+
+/// Operating system random source (`getrandom`).
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsRandom;
+
+#[cfg(feature = "std")]
+impl RandomSource for OsRandom {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), AshError> {
+        getrandom::getrandom(buf).map_err(|e| {
+            AshError::new(
+                AshErrorCode::RngUnavailable,
+                format!("Failed to generate random bytes: {}", e),
+            )
+        })
+    }
+}
+
+/// Generate a hex-encoded nonce of `bytes` random bytes from `src`.
+///
+/// # Errors
+/// Returns the error from [`RandomSource::fill`].
+pub fn generate_nonce_with(src: &dyn RandomSource, bytes: usize) -> Result<String, AshError> {
+    let mut buf = alloc::vec![0u8; bytes];
+    src.fill(&mut buf)?;
+    Ok(hex::encode(buf))
+}
+
+#[cfg(feature = "std")]
+/// Generate cryptographically secure random bytes, reporting RNG failure.
+///
+/// # Errors
+/// Returns `RngUnavailable` if the OS random source cannot be used
+/// (some sandboxes and WASM hosts).
+pub fn try_generate_nonce_bytes(len: usize) -> Result<Vec<u8>, AshError> {
+    let mut buf = vec![0u8; len];
+    OsRandom.fill(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(feature = "std")]
+/// Generate cryptographically secure random bytes.
+///
+/// Use this when the raw nonce is needed (e.g. to feed another KDF or to
+/// encode it more compactly than hex).
+///
+/// # Arguments
+/// * `len` - Number of bytes
+///
+/// # Returns
+/// Raw random bytes (`len` bytes)
+///
+/// # Panics
+/// Panics if the OS random source is unavailable. Use
+/// [`try_generate_nonce_bytes`] to handle that case.
+pub fn generate_nonce_bytes(len: usize) -> Vec<u8> {
+    try_generate_nonce_bytes(len).expect("Failed to generate random bytes")
+}
+
+#[cfg(feature = "std")]
+/// Generate a cryptographically secure random nonce, reporting RNG failure.
+///
+/// # Returns
+/// Hex-encoded nonce (64 chars for 32 bytes)
+///
+/// # Errors
+/// Returns `RngUnavailable` if the OS random source cannot be used.
+pub fn try_generate_nonce(bytes: usize) -> Result<String, AshError> {
+    generate_nonce_with(&OsRandom, bytes)
+}
+
+#[cfg(feature = "std")]
 /// Generate a cryptographically secure random nonce.
 ///
 /// # Arguments
@@ -371,16 +461,44 @@ const ASH_VERSION_V21: &str = "ASHv2.1";
 ///
 /// # Returns
 /// Hex-encoded nonce (64 chars for 32 bytes)
+///
+/// # Panics
+/// Panics if the OS random source is unavailable. Use
+/// [`try_generate_nonce`] to handle that case.
 pub fn generate_nonce(bytes: usize) -> String {
-    use getrandom::getrandom;
-    let mut buf = vec![0u8; bytes];
-    getrandom(&mut buf).expect("Failed to generate random bytes");
-    hex::encode(buf)
+    try_generate_nonce(bytes).expect("Failed to generate random bytes")
 }
 
+#[cfg(feature = "std")]
+/// Generate a unique context ID with "ash_" prefix, reporting RNG failure.
+pub fn try_generate_context_id() -> Result<String, AshError> {
+    Ok(format!("ash_{}", try_generate_nonce(16)?))
+}
+
+#[cfg(feature = "std")]
 /// Generate a unique context ID with "ash_" prefix.
+///
+/// # Panics
+/// Panics if the OS random source is unavailable. Use
+/// [`try_generate_context_id`] to handle that case.
 pub fn generate_context_id() -> String {
-    format!("ash_{}", generate_nonce(16))
+    try_generate_context_id().expect("Failed to generate random bytes")
+}
+
+/// Derive a stable context ID from a client-supplied request identifier.
+///
+/// For idempotent retries: the same `namespace` and `request_id` always
+/// give the same ID, so a retried request reuses its context. The ID has
+/// the same `ash_` + 32 hex shape as [`generate_context_id`], which remains
+/// the default. It is not secret: anyone who knows both inputs can compute
+/// it, so it must never stand in for the nonce.
+///
+/// Formula: `"ash_" + hex(HMAC-SHA256(namespace, requestId)[..16])`
+pub fn derive_context_id(namespace: &str, request_id: &str) -> String {
+    let mut mac = HmacSha256Type::new_from_slice(namespace.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(request_id.as_bytes());
+    format!("ash_{}", hex::encode(&mac.finalize().into_bytes()[..16]))
 }
 
 /// Derive client secret from server nonce (v2.1).
@@ -398,6 +516,156 @@ pub fn derive_client_secret(nonce: &str, context_id: &str, binding: &str) -> Str
     hex::encode(mac.finalize().into_bytes())
 }
 
+/// Derive client secret from server nonce, rejecting an empty nonce.
+///
+/// [`derive_client_secret`] accepts any nonce, including `""`, which yields
+/// a secret anyone can compute. Use this variant where the nonce comes from
+/// configuration or storage that might be missing.
+///
+/// # Errors
+/// Returns `MalformedRequest` if `nonce` is empty.
+pub fn try_derive_client_secret(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+) -> Result<String, AshError> {
+    if nonce.is_empty() {
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            "nonce must not be empty",
+        ));
+    }
+    Ok(derive_client_secret(nonce, context_id, binding))
+}
+
+/// Domain tag of [`derive_client_secret_v2`] messages.
+const SECRET_V2_TAG: &str = "ASH-secret-v2";
+
+/// Frame `fields` under a domain `tag` so no two different field lists
+/// produce the same bytes.
+///
+/// Each field is written as `<byte length>:<value>|` after `tag|`, so a `|`
+/// inside a field can never shift a field boundary. Every use has its own
+/// tag, so secret derivations and proof messages never share an input.
+fn framed_message(tag: &str, fields: &[&str]) -> String {
+    let mut message = format!("{}|", tag);
+    for field in fields {
+        message.push_str(&format!("{}:{}|", field.len(), field));
+    }
+    message
+}
+
+/// Derive client secret from server nonce with unambiguous framing.
+///
+/// [`derive_client_secret`] joins `contextId|binding`, so a context ID that
+/// contains `|` can shift the field boundary: context `ctx|POST` with
+/// binding `/x` derives the same secret as context `ctx` with binding
+/// `POST|/x`. Servers that accept caller-chosen context IDs could then hand
+/// out a secret valid for another context. This version length-prefixes
+/// each field under its own `ASH-secret-v2` domain tag, so distinct pairs
+/// never collide.
+///
+/// The secrets differ from [`derive_client_secret`], and the nonce-based
+/// verifiers still derive v1 secrets: a server adopting v2 derives the
+/// secret here and compares the expected proof with [`timing_safe_equal`].
+///
+/// Formula: clientSecret = HMAC-SHA256(nonce,
+/// "ASH-secret-v2|" + len(contextId) + ":" + contextId + "|" + len(binding) + ":" + binding + "|")
+pub fn derive_client_secret_v2(nonce: &str, context_id: &str, binding: &str) -> String {
+    let mut mac =
+        HmacSha256Type::new_from_slice(nonce.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(framed_message(SECRET_V2_TAG, &[context_id, binding]).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Domain tag of [`derive_client_secret_epoch`] messages.
+const SECRET_EPOCH_TAG: &str = "ASH-secret-epoch";
+
+/// Derive an epoch-bound client secret from server nonce (v2.1).
+///
+/// Like [`derive_client_secret`], but also binds a caller-supplied epoch
+/// number (e.g. `now_ms / window_ms`) so the secret rotates automatically
+/// each time window without a server round-trip.
+///
+/// Fields are length-prefixed under their own `ASH-secret-epoch` domain
+/// tag, so an epoch secret never equals the v1 secret of a binding that
+/// ends in `|<epoch>`.
+///
+/// Formula: clientSecret = HMAC-SHA256(nonce,
+/// "ASH-secret-epoch|" + len(contextId) + ":" + contextId + "|" + len(binding) + ":" + binding
+/// + "|" + len(epoch) + ":" + epoch + "|")
+pub fn derive_client_secret_epoch(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    epoch: u64,
+) -> String {
+    let mut mac =
+        HmacSha256Type::new_from_slice(nonce.as_bytes()).expect("HMAC can take key of any size");
+    let epoch = epoch.to_string();
+    mac.update(framed_message(SECRET_EPOCH_TAG, &[context_id, binding, &epoch]).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Domain tag of [`derive_client_secret_with_device`] messages.
+const SECRET_DEVICE_TAG: &str = "ASH-secret-device";
+
+/// Derive a device-bound client secret from server nonce (v2.1).
+///
+/// Like [`derive_client_secret`], but also mixes in a device identifier
+/// (e.g. derived from a device-held key) so a proof is only valid for the
+/// device the context was issued to. This binds *which device's context*
+/// a request belongs to; it does not authenticate the user.
+///
+/// Fields are length-prefixed under their own `ASH-secret-device` domain
+/// tag, so these secrets never equal a v1 or [`derive_client_secret_epoch`]
+/// secret, whatever the binding or device ID contains.
+///
+/// Formula: clientSecret = HMAC-SHA256(nonce,
+/// "ASH-secret-device|" + len(contextId) + ":" + contextId + "|" + len(binding) + ":" + binding
+/// + "|" + len(deviceId) + ":" + deviceId + "|")
+pub fn derive_client_secret_with_device(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    device_id: &str,
+) -> String {
+    let mut mac =
+        HmacSha256Type::new_from_slice(nonce.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(framed_message(SECRET_DEVICE_TAG, &[context_id, binding, device_id]).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Domain tag of [`derive_client_secret_env`] messages.
+const SECRET_ENV_TAG: &str = "ASH-secret-env";
+
+/// Derive an environment-bound client secret from server nonce (v2.1).
+///
+/// Like [`derive_client_secret`], but also mixes in a deployment label
+/// (`"staging"`, `"production"`), so a proof built in one environment
+/// never verifies in another even if a nonce or context leaks between
+/// them. Each server passes its own fixed label; clients receive secrets
+/// derived by their server and need no changes.
+///
+/// Fields are length-prefixed (as in [`derive_client_secret_v2`]) under
+/// their own `ASH-secret-env` domain tag, so no label or context ID can
+/// collide with another derivation.
+///
+/// Formula: clientSecret = HMAC-SHA256(nonce,
+/// "ASH-secret-env|" + len(env) + ":" + env + "|" + len(contextId) + ":" + contextId + "|"
+/// + len(binding) + ":" + binding + "|")
+pub fn derive_client_secret_env(
+    nonce: &str,
+    environment: &str,
+    context_id: &str,
+    binding: &str,
+) -> String {
+    let mut mac =
+        HmacSha256Type::new_from_slice(nonce.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(framed_message(SECRET_ENV_TAG, &[environment, context_id, binding]).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
 /// Build v2.1 cryptographic proof (client-side).
 ///
 /// Formula: proof = HMAC-SHA256(clientSecret, timestamp + "|" + binding + "|" + bodyHash)
@@ -407,15 +675,126 @@ pub fn build_proof_v21(
     binding: &str,
     body_hash: &str,
 ) -> String {
-    let message = format!("{}|{}|{}", timestamp, binding, body_hash);
+    hex::encode(proof_v21_bytes(
+        client_secret,
+        timestamp,
+        binding,
+        body_hash,
+    ))
+}
+
+/// Build v2.1 proof, rejecting an empty client secret (client-side).
+///
+/// HMAC accepts an empty key, so [`build_proof_v21`] with `""` (e.g. a
+/// secret that was never derived) silently produces a proof anyone can
+/// forge. This variant fails instead.
+///
+/// # Errors
+/// Returns `MalformedRequest` if `client_secret` is empty.
+pub fn try_build_proof_v21(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+) -> Result<String, AshError> {
+    if client_secret.is_empty() {
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            "client_secret must not be empty",
+        ));
+    }
+    Ok(build_proof_v21(
+        client_secret,
+        timestamp,
+        binding,
+        body_hash,
+    ))
+}
+
+/// Build v2.1 proof over a raw byte body (client-side).
+///
+/// Equivalent to `build_proof_v21(client_secret, timestamp, binding,
+/// &hash_body_bytes(body))`. The body is hashed as-is and **not**
+/// canonicalized: the caller must send byte-identical bodies on both sides.
+/// The server verifies with [`hash_body_bytes`] and [`verify_proof_v21`].
+pub fn build_proof_v21_bytes(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body: &[u8],
+) -> String {
+    build_proof_v21(client_secret, timestamp, binding, &hash_body_bytes(body))
+}
+
+/// Compute the raw v2.1 HMAC bytes shared by the hex and base64url encodings.
+fn proof_v21_bytes(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+) -> Vec<u8> {
+    let message = proof_v21_message(timestamp, binding, body_hash);
     let mut mac = HmacSha256Type::new_from_slice(client_secret.as_bytes())
         .expect("HMAC can take key of any size");
     mac.update(message.as_bytes());
-    hex::encode(mac.finalize().into_bytes())
+    mac.finalize().into_bytes().to_vec()
 }
 
-/// Verify v2.1 proof (server-side).
-pub fn verify_proof_v21(
+/// The v2.1 HMAC message: `timestamp|binding|bodyHash`.
+pub(crate) fn proof_v21_message(timestamp: &str, binding: &str, body_hash: &str) -> String {
+    format!("{}|{}|{}", timestamp, binding, body_hash)
+}
+
+/// MAC used to build v2.1 proofs.
+///
+/// Both sides must use the same algorithm: a proof built with one never
+/// verifies under another. Only the proof MAC changes; the client secret
+/// derivation and body hash stay HMAC-SHA256 / SHA-256.
+///
+/// Non-exhaustive because variants depend on crate features: another crate
+/// enabling `blake3` adds `Blake3Keyed` for every user of the
+/// crate, so matches need a wildcard arm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HashAlg {
+    /// HMAC-SHA256. The protocol default.
+    #[default]
+    HmacSha256,
+    /// Keyed BLAKE3 (`blake3` feature), for CPU-bound gateways. The 32-byte
+    /// key is `blake3::derive_key("ASH v2.1 proof key", clientSecret)`.
+    #[cfg(feature = "blake3")]
+    Blake3Keyed,
+}
+
+/// BLAKE3 key derivation context for [`HashAlg::Blake3Keyed`].
+#[cfg(feature = "blake3")]
+const BLAKE3_PROOF_KEY_CONTEXT: &str = "ASH v2.1 proof key";
+
+/// Build v2.1 proof with an explicit MAC algorithm (client-side).
+///
+/// `HashAlg::HmacSha256` is identical to [`build_proof_v21`]. The message is
+/// `timestamp|binding|bodyHash` for every algorithm.
+pub fn build_proof_v21_with_alg(
+    alg: HashAlg,
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+) -> String {
+    match alg {
+        HashAlg::HmacSha256 => build_proof_v21(client_secret, timestamp, binding, body_hash),
+        #[cfg(feature = "blake3")]
+        HashAlg::Blake3Keyed => {
+            let key = blake3::derive_key(BLAKE3_PROOF_KEY_CONTEXT, client_secret.as_bytes());
+            let message = format!("{}|{}|{}", timestamp, binding, body_hash);
+            hex::encode(blake3::keyed_hash(&key, message.as_bytes()).as_bytes())
+        }
+    }
+}
+
+/// Verify v2.1 proof built with [`build_proof_v21_with_alg`] (server-side).
+pub fn verify_proof_v21_with_alg(
+    alg: HashAlg,
     nonce: &str,
     context_id: &str,
     binding: &str,
@@ -424,117 +803,1967 @@ pub fn verify_proof_v21(
     client_proof: &str,
 ) -> bool {
     let client_secret = derive_client_secret(nonce, context_id, binding);
-    let expected_proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+    let expected_proof =
+        build_proof_v21_with_alg(alg, &client_secret, timestamp, binding, body_hash);
     timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes())
 }
 
-/// Compute SHA-256 hash of canonical body.
-pub fn hash_body(canonical_body: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(canonical_body.as_bytes());
-    hex::encode(hasher.finalize())
+/// Build v2.1 cryptographic proof encoded as base64url (client-side).
+///
+/// Same HMAC as [`build_proof_v21`], encoded as Base64URL without padding
+/// (43 chars instead of 64 hex chars) to shorten request headers.
+pub fn build_proof_v21_b64(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+) -> String {
+    URL_SAFE_NO_PAD.encode(proof_v21_bytes(
+        client_secret,
+        timestamp,
+        binding,
+        body_hash,
+    ))
 }
 
-#[cfg(test)]
-mod tests_v21 {
-    use super::*;
-
-    #[test]
-    fn test_derive_client_secret_deterministic() {
-        let secret1 = derive_client_secret("nonce123", "ctx_abc", "POST /login");
-        let secret2 = derive_client_secret("nonce123", "ctx_abc", "POST /login");
-        assert_eq!(secret1, secret2);
-    }
+/// Compute the proof a valid v2.1 request must carry (server-side).
+///
+/// Derives the client secret from the nonce and builds the hex proof, as
+/// [`verify_proof_v21`] does before comparing. Useful for logging or
+/// idempotency caches.
+///
+/// **Security:** the returned value is a valid proof for this request.
+/// Never send it to clients, and never compare it with `==`; use
+/// [`verify_proof_v21`] or [`timing_safe_equal`].
+pub fn compute_expected_proof_v21(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+) -> String {
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    build_proof_v21(&client_secret, timestamp, binding, body_hash)
+}
 
-    #[test]
-    fn test_derive_client_secret_different_inputs() {
-        let secret1 = derive_client_secret("nonce123", "ctx_abc", "POST /login");
-        let secret2 = derive_client_secret("nonce456", "ctx_abc", "POST /login");
-        assert_ne!(secret1, secret2);
-    }
+/// Verify v2.1 proof (server-side).
+pub fn verify_proof_v21(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    let expected_proof =
+        compute_expected_proof_v21(nonce, context_id, binding, timestamp, body_hash);
+    timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes())
+}
 
-    #[test]
-    fn test_build_proof_v21_deterministic() {
-        let proof1 = build_proof_v21("secret", "1234567890", "POST /login", "bodyhash");
-        let proof2 = build_proof_v21("secret", "1234567890", "POST /login", "bodyhash");
-        assert_eq!(proof1, proof2);
+/// Verify v2.1 proof, failing with `IntegrityFailed` (server-side).
+///
+/// [`verify_proof_v21`] for handlers that propagate errors with `?`.
+pub fn ensure_proof_v21(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> Result<(), AshError> {
+    if verify_proof_v21(
+        nonce,
+        context_id,
+        binding,
+        timestamp,
+        body_hash,
+        client_proof,
+    ) {
+        Ok(())
+    } else {
+        Err(AshError::integrity_failed())
     }
+}
 
-    #[test]
-    fn test_verify_proof_v21() {
-        let nonce = "nonce123";
-        let context_id = "ctx_abc";
-        let binding = "POST /login";
-        let timestamp = "1234567890";
-        let body_hash = "bodyhash123";
+/// Length of a hex-encoded SHA-256 digest or HMAC-SHA256 proof.
+const HEX_DIGEST_LEN: usize = 64;
 
-        let client_secret = derive_client_secret(nonce, context_id, binding);
-        let proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+/// Whether `value` is exactly 64 lowercase hex characters.
+fn is_hex_digest(value: &str) -> bool {
+    value.len() == HEX_DIGEST_LEN
+        && value
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
 
-        assert!(verify_proof_v21(nonce, context_id, binding, timestamp, body_hash, &proof));
-    }
+/// Check whether a v2.1 proof is structurally valid (64 lowercase hex).
+///
+/// A cheap **pre-filter** for gateways to drop obviously bogus requests
+/// before any store lookup or HMAC work. It is not constant-time and says
+/// nothing about whether the proof is correct; always verify afterwards.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::is_well_formed_proof_v21;
+///
+/// assert!(is_well_formed_proof_v21(&"a".repeat(64)));
+/// assert!(!is_well_formed_proof_v21(&"A".repeat(64)));
+/// ```
+pub fn is_well_formed_proof_v21(proof: &str) -> bool {
+    is_hex_digest(proof)
+}
 
-    #[test]
-    fn test_hash_body() {
-        let hash = hash_body(r#"{"name":"John"}"#);
-        assert_eq!(hash.len(), 64); // SHA-256 produces 32 bytes = 64 hex chars
-    }
+/// Check whether a body hash is structurally valid (64 lowercase hex).
+///
+/// Pre-filter only, like [`is_well_formed_proof_v21`].
+pub fn is_well_formed_body_hash(body_hash: &str) -> bool {
+    is_hex_digest(body_hash)
 }
 
-// =========================================================================
-// ASH v2.2 - Context Scoping (Selective Field Protection)
-// =========================================================================
+/// Check whether a v2.2/v2.3 scope hash is structurally valid.
+///
+/// An empty string (no scope) or 64 lowercase hex. Pre-filter only, like
+/// [`is_well_formed_proof_v21`].
+pub fn is_well_formed_scope_hash(scope_hash: &str) -> bool {
+    scope_hash.is_empty() || is_hex_digest(scope_hash)
+}
 
-use serde_json::{Map, Value};
+/// Check whether a v2.3 chain hash is structurally valid.
+///
+/// An empty string (no previous proof) or 64 lowercase hex. Pre-filter
+/// only, like [`is_well_formed_proof_v21`].
+pub fn is_well_formed_chain_hash(chain_hash: &str) -> bool {
+    chain_hash.is_empty() || is_hex_digest(chain_hash)
+}
 
-/// Extract scoped fields from a JSON value.
-pub fn extract_scoped_fields(payload: &Value, scope: &[&str]) -> Result<Value, AshError> {
-    if scope.is_empty() {
-        return Ok(payload.clone());
+/// Check that `value` is a lowercase hex SHA-256 digest or proof.
+///
+/// Only the format is checked, never the value, so this is safe to run
+/// before the constant-time comparison.
+fn validate_hex_digest(name: &str, value: &str) -> Result<(), AshError> {
+    if is_hex_digest(value) {
+        Ok(())
+    } else {
+        Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            format!(
+                "{} must be {} lowercase hex characters",
+                name, HEX_DIGEST_LEN
+            ),
+        ))
     }
+}
 
-    let mut result = Map::new();
-
-    for field_path in scope {
-        let value = get_nested_value(payload, field_path);
-        if let Some(v) = value {
-            set_nested_value(&mut result, field_path, v);
-        }
+/// Parse a request timestamp (milliseconds since the Unix epoch).
+///
+/// Only ASCII digits are accepted: empty, signed (`-1`, `+1`), fractional
+/// and whitespace-padded values are rejected, as are values that overflow
+/// `u64`. Error messages never echo the input.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::parse_timestamp_ms;
+///
+/// assert_eq!(parse_timestamp_ms("1704067200000").unwrap(), 1_704_067_200_000);
+/// assert!(parse_timestamp_ms("-1").is_err());
+/// ```
+///
+/// # Errors
+///
+/// Returns `MalformedRequest` if the timestamp is not a non-negative
+/// integer that fits in `u64`.
+pub fn parse_timestamp_ms(s: &str) -> Result<u64, AshError> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            "Timestamp must be a non-negative integer of milliseconds",
+        ));
     }
 
-    Ok(Value::Object(result))
+    s.parse::<u64>()
+        .map_err(|_| AshError::new(AshErrorCode::MalformedRequest, "Timestamp is out of range"))
 }
 
-fn get_nested_value(payload: &Value, path: &str) -> Option<Value> {
-    let parts: Vec<&str> = path.split('.').collect();
-    let mut current = payload;
-
-    for part in parts {
-        let (key, index) = parse_array_notation(part);
+/// Verify v2.1 proof, rejecting malformed inputs explicitly (server-side).
+///
+/// Like [`verify_proof_v21`], but `timestamp` must be an integer of
+/// milliseconds (see [`parse_timestamp_ms`]) and `body_hash` and
+/// `client_proof` must be 64-character lowercase hex; anything else is
+/// reported as `MalformedRequest` instead of a silent `false`, so it can
+/// be monitored.
+pub fn verify_proof_v21_checked(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> Result<bool, AshError> {
+    parse_timestamp_ms(timestamp)?;
+    validate_hex_digest("body_hash", body_hash)?;
+    validate_hex_digest("client_proof", client_proof)?;
+
+    Ok(verify_proof_v21(
+        nonce,
+        context_id,
+        binding,
+        timestamp,
+        body_hash,
+        client_proof,
+    ))
+}
+
+/// Verify v2.1 proof, reporting a wrong endpoint explicitly (server-side).
+///
+/// `expected_binding` is the endpoint the server is handling and
+/// `claimed_binding` is the one the client says it signed for. Both are
+/// normalized and compared (in constant time) before any HMAC work, so a
+/// proof for the wrong endpoint fails with `EndpointMismatch` instead of
+/// looking like a tampered body.
+///
+/// # Errors
+///
+/// Returns `EndpointMismatch` if the bindings differ after normalization,
+/// `MalformedRequest` if either binding is not `METHOD /path`, plus any
+/// error from [`verify_proof_v21_checked`].
+pub fn verify_proof_v21_with_binding(
+    nonce: &str,
+    context_id: &str,
+    expected_binding: &str,
+    claimed_binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> Result<bool, AshError> {
+    let expected = normalize_binding_string(expected_binding)?;
+    let claimed = normalize_binding_string(claimed_binding)?;
+    if !timing_safe_equal(expected.as_bytes(), claimed.as_bytes()) {
+        return Err(AshError::endpoint_mismatch());
+    }
+
+    verify_proof_v21_checked(
+        nonce,
+        context_id,
+        &expected,
+        timestamp,
+        body_hash,
+        client_proof,
+    )
+}
+
+/// Normalize a full `METHOD /path` binding string.
+fn normalize_binding_string(binding: &str) -> Result<String, AshError> {
+    let (method, path) = crate::split_binding(binding.trim());
+    crate::normalize_binding(method, path)
+}
+
+/// Normalize a `Content-Type` header value for proof binding.
+///
+/// Parameters after `;` are dropped, then the media type is trimmed and
+/// lowercased: `"Application/JSON; charset=utf-8"` becomes
+/// `"application/json"`.
+pub fn normalize_content_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Build v2.1 proof that also binds the request content type (client-side).
+///
+/// Prevents content-type confusion: the same body sent as
+/// `application/json` and as `text/plain` yields different proofs. The
+/// content type is normalized with [`normalize_content_type`].
+///
+/// Formula: proof = HMAC-SHA256(clientSecret,
+///   timestamp + "|" + binding + "|" + contentType + "|" + bodyHash)
+pub fn build_proof_v21_with_content_type(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    content_type: &str,
+    body_hash: &str,
+) -> String {
+    let message = format!(
+        "{}|{}|{}|{}",
+        timestamp,
+        binding,
+        normalize_content_type(content_type),
+        body_hash
+    );
+    let mut mac = HmacSha256Type::new_from_slice(client_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(message.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Verify v2.1 proof built with [`build_proof_v21_with_content_type`]
+/// (server-side).
+///
+/// `content_type` is the `Content-Type` the server received; it is
+/// normalized the same way as on the client.
+pub fn verify_proof_v21_with_content_type(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    content_type: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected_proof = build_proof_v21_with_content_type(
+        &client_secret,
+        timestamp,
+        binding,
+        content_type,
+        body_hash,
+    );
+    timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes())
+}
+
+/// Verify v2.1 proof built with a device-bound client secret (server-side).
+///
+/// The server must know the `device_id` the context was issued to; a proof
+/// built for any other device fails.
+pub fn verify_proof_v21_with_device(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    device_id: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    let client_secret = derive_client_secret_with_device(nonce, context_id, binding, device_id);
+    let expected_proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+    timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes())
+}
+
+/// Label MACed under the client secret to form the key-commitment tag.
+const KEY_COMMITMENT_LABEL: &str = "commit";
+
+/// Length of the key-commitment tag in hex characters (128 bits).
+const KEY_COMMITMENT_HEX_LEN: usize = 32;
+
+/// `HMAC-SHA256(clientSecret, "commit")`, truncated to 128 bits.
+fn key_commitment_tag(client_secret: &str) -> String {
+    let mut mac = HmacSha256Type::new_from_slice(client_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(KEY_COMMITMENT_LABEL.as_bytes());
+    let tag = mac.finalize().into_bytes();
+    hex::encode(&tag[..KEY_COMMITMENT_HEX_LEN / 2])
+}
+
+/// Build key-committing v2.1 proof (client-side).
+///
+/// HMAC is not key-committing: nothing in the algorithm stops a tag from
+/// verifying under two different keys. With a multi-tenant key store, a
+/// server that tries several tenants' secrets could then accept a proof
+/// for the wrong tenant. This variant appends a commitment to the secret,
+/// so a proof binds to exactly one derived client secret.
+///
+/// The result is the 64-character v2.1 proof followed by a 32-character
+/// tag (96 hex characters). The extra cost is one HMAC over a 6-byte
+/// message on each side. Verify with [`verify_proof_v21_committed`];
+/// plain [`verify_proof_v21`] rejects committed proofs.
+///
+/// Formula: proof = HMAC-SHA256(clientSecret, timestamp + "|" + binding + "|" + bodyHash)
+/// + first 16 bytes of HMAC-SHA256(clientSecret, "commit")
+pub fn build_proof_v21_committed(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+) -> String {
+    let mut proof = build_proof_v21(client_secret, timestamp, binding, body_hash);
+    proof.push_str(&key_commitment_tag(client_secret));
+    proof
+}
+
+/// Verify key-committing v2.1 proof (server-side).
+///
+/// Both the proof and the commitment tag must match the secret derived
+/// from `nonce`, `context_id` and `binding`.
+pub fn verify_proof_v21_committed(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    committed_proof_matches(&client_secret, timestamp, binding, body_hash, client_proof)
+}
+
+/// Check both halves of a committed proof under `client_secret`.
+fn committed_proof_matches(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    if client_proof.len() != HEX_DIGEST_LEN + KEY_COMMITMENT_HEX_LEN
+        || !client_proof.is_char_boundary(HEX_DIGEST_LEN)
+    {
+        return false;
+    }
+    let (proof, tag) = client_proof.split_at(HEX_DIGEST_LEN);
+
+    // Check both halves so timing does not reveal which one failed
+    let expected_proof = build_proof_v21(client_secret, timestamp, binding, body_hash);
+    let expected_tag = key_commitment_tag(client_secret);
+    let proof_ok = timing_safe_equal(expected_proof.as_bytes(), proof.as_bytes());
+    let tag_ok = timing_safe_equal(expected_tag.as_bytes(), tag.as_bytes());
+    proof_ok & tag_ok
+}
+
+/// Verify v2.1 proof built with an environment-bound client secret
+/// (server-side).
+///
+/// `environment` is this server's own label; proofs built for any other
+/// environment fail.
+pub fn verify_proof_v21_env(
+    nonce: &str,
+    environment: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    let client_secret = derive_client_secret_env(nonce, environment, context_id, binding);
+    let expected_proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+    timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes())
+}
+
+/// Verify v2.1 proof built with an epoch-bound client secret (server-side).
+///
+/// Accepts proofs whose secret was derived for `current_epoch` or the
+/// epoch immediately before it, to tolerate clock skew at window edges.
+pub fn verify_proof_v21_epoch(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    current_epoch: u64,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    let epochs = [Some(current_epoch), current_epoch.checked_sub(1)];
+
+    // Check every candidate so timing does not reveal which epoch matched
+    let mut valid = false;
+    for epoch in epochs.into_iter().flatten() {
+        let client_secret = derive_client_secret_epoch(nonce, context_id, binding, epoch);
+        let expected_proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+        valid |= timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes());
+    }
+    valid
+}
+
+/// Verify v2.1 proof against several candidate nonces (server-side).
+///
+/// Used during nonce rotation, when in-flight requests may still carry
+/// proofs built from the previous nonce. Every candidate is checked and
+/// the result is selected in constant time, so timing does not reveal
+/// which nonce matched.
+///
+/// # Returns
+/// Index of the first matching nonce, or `None` if none match.
+pub fn verify_proof_v21_multi(
+    nonces: &[&str],
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> Option<usize> {
+    let mut found = Choice::from(0);
+    let mut index = 0u64;
+
+    for (i, nonce) in nonces.iter().enumerate() {
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let expected_proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+        let matched = Choice::from(timing_safe_equal(
+            expected_proof.as_bytes(),
+            client_proof.as_bytes(),
+        ) as u8);
+
+        // Keep the first match without branching on it
+        index = u64::conditional_select(&index, &(i as u64), matched & !found);
+        found |= matched;
+    }
+
+    bool::from(found).then_some(index as usize)
+}
+
+/// Verify v2.1 proof against several allowed bindings (server-side).
+///
+/// For endpoints reachable under more than one route (an alias and the
+/// canonical path), a proof is accepted if it was built for any of
+/// `bindings`. Each binding gets its own client secret, as the client
+/// derives it. Every candidate is checked and the result is selected in
+/// constant time, so timing does not reveal which binding matched.
+///
+/// # Returns
+/// Index of the first matching binding, or `None` if none match.
+pub fn verify_proof_v21_any_binding(
+    nonce: &str,
+    context_id: &str,
+    bindings: &[&str],
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> Option<usize> {
+    let mut found = Choice::from(0);
+    let mut index = 0u64;
+
+    for (i, binding) in bindings.iter().enumerate() {
+        let expected_proof =
+            compute_expected_proof_v21(nonce, context_id, binding, timestamp, body_hash);
+        let matched = Choice::from(timing_safe_equal(
+            expected_proof.as_bytes(),
+            client_proof.as_bytes(),
+        ) as u8);
+
+        // Keep the first match without branching on it
+        index = u64::conditional_select(&index, &(i as u64), matched & !found);
+        found |= matched;
+    }
+
+    bool::from(found).then_some(index as usize)
+}
+
+/// Verify a v2.1 request against its stored context (server-side).
+///
+/// Checks run in order, and the first failure is returned:
+///
+/// 1. `Expired` if the context has expired at `now_ms`
+/// 2. `Replay` if the context was already consumed
+/// 3. `EndpointMismatch` if `binding` differs from the context binding
+/// 4. `IntegrityFailed` if the proof does not match
+///
+/// `binding` must already be normalized (see [`normalize_binding`]).
+/// The context is not consumed; the caller does that on `Valid`.
+///
+/// # Errors
+///
+/// Returns `ModeViolation` if the context has no nonce, and
+/// `MalformedRequest` if `timestamp` is not an integer of milliseconds or
+/// `body_hash` or `client_proof` is not 64-char hex.
+///
+/// [`normalize_binding`]: crate::normalize_binding
+pub fn verify_with_context(
+    context: &StoredContext,
+    now_ms: u64,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> Result<VerificationResult, AshError> {
+    if context.is_expired(now_ms) {
+        return Ok(VerificationResult::Expired);
+    }
+    if context.is_consumed() {
+        return Ok(VerificationResult::Replay);
+    }
+    if binding != context.binding {
+        return Ok(VerificationResult::EndpointMismatch);
+    }
+
+    let nonce = context.nonce.as_deref().ok_or_else(|| {
+        AshError::new(
+            AshErrorCode::ModeViolation,
+            "Context has no nonce for v2.1 verification",
+        )
+    })?;
+
+    let is_valid = verify_proof_v21_checked(
+        nonce,
+        &context.context_id,
+        binding,
+        timestamp,
+        body_hash,
+        client_proof,
+    )?;
+
+    Ok(if is_valid {
+        VerificationResult::Valid
+    } else {
+        VerificationResult::IntegrityFailed
+    })
+}
+
+/// Record a successful verification as a [`ProofReceipt`] (server-side).
+///
+/// Call after verification returns `Valid`, with the same request values.
+/// Pass `""` for `scope_hash` / `chain_hash` when the proof was not scoped
+/// or chained. Nothing is checked here: a receipt only records what was
+/// verified.
+pub fn build_receipt(
+    context: &StoredContext,
+    timestamp: &str,
+    body_hash: &str,
+    scope_hash: &str,
+    chain_hash: &str,
+    verified_at: u64,
+) -> ProofReceipt {
+    ProofReceipt {
+        context_id: context.context_id.clone(),
+        binding: context.binding.clone(),
+        timestamp: timestamp.to_string(),
+        body_hash: body_hash.to_string(),
+        scope_hash: scope_hash.to_string(),
+        chain_hash: chain_hash.to_string(),
+        verified_at,
+    }
+}
+
+/// Incremental SHA-256 body hasher for large payloads.
+///
+/// Produces the same output as [`hash_body`] without holding the whole
+/// body in memory, so uploads can be hashed chunk by chunk as they are read.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::{hash_body, BodyHasher};
+///
+/// let mut hasher = BodyHasher::new();
+/// hasher.update(br#"{"name":"#);
+/// hasher.update(br#""John"}"#);
+/// assert_eq!(hasher.finalize(), hash_body(r#"{"name":"John"}"#));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BodyHasher {
+    hasher: Sha256,
+}
+
+impl BodyHasher {
+    /// Create a new, empty hasher.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next chunk of the canonical body.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// Finish hashing and return the hex-encoded SHA-256 digest.
+    pub fn finalize(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+/// Body hash of a request that has no body: SHA-256 of the empty string.
+///
+/// A missing body is canonically the empty string. This can never collide
+/// with a JSON body, because the smallest canonical JSON documents (`{}`,
+/// `[]`, `null`, `""`) are all non-empty and `canonicalize_json("")` fails.
+pub const EMPTY_BODY_HASH: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Verify base64url-encoded v2.1 proof (server-side).
+///
+/// Expects the encoding produced by [`build_proof_v21_b64`]; a hex proof
+/// is rejected rather than converted.
+pub fn verify_proof_v21_b64(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected_proof = build_proof_v21_b64(&client_secret, timestamp, binding, body_hash);
+    timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes())
+}
+
+/// Convert a hex-encoded proof to base64url (no padding).
+pub fn hex_proof_to_b64(proof: &str) -> Result<String, AshError> {
+    let bytes = hex::decode(proof).map_err(|_| AshError::invalid_proof_format("not valid hex"))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Convert a base64url-encoded (no padding) proof to hex.
+pub fn b64_proof_to_hex(proof: &str) -> Result<String, AshError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(proof)
+        .map_err(|_| AshError::invalid_proof_format("not valid base64url"))?;
+    Ok(hex::encode(bytes))
+}
+
+/// Compute SHA-256 hash of canonical body.
+///
+/// Pass `""` only for a request without a body; see [`EMPTY_BODY_HASH`].
+pub fn hash_body(canonical_body: &str) -> String {
+    hash_body_bytes(canonical_body.as_bytes())
+}
+
+/// Compute SHA-256 hash of a raw byte body.
+///
+/// For bodies that are already canonical bytes or are not UTF-8. No
+/// canonicalization is applied, so the caller is responsible for producing
+/// the same bytes on client and server. For UTF-8 input this equals
+/// [`hash_body`].
+pub fn hash_body_bytes(body: &[u8]) -> String {
+    let mut hasher = BodyHasher::new();
+    hasher.update(body);
+    hasher.finalize()
+}
+
+/// Canonicalize and hash many JSON payloads.
+///
+/// Equivalent to `hash_body(&canonicalize_json(input)?)` for each input,
+/// stopping at the first failure with its index in the error message
+/// (see [`canonicalize_json_batch`](crate::canonicalize_json_batch)).
+#[cfg(feature = "std")]
+pub fn hash_body_batch(inputs: &[&str]) -> Result<Vec<String>, AshError> {
+    let canonical = crate::canonicalize::canonicalize_json_batch(inputs)?;
+    Ok(canonical.iter().map(|body| hash_body(body)).collect())
+}
+
+/// Per-line and aggregate hashes of an NDJSON body, from [`hash_ndjson`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NdjsonHash {
+    /// Body hash of each canonical line, in order (blank lines skipped).
+    pub lines: Vec<String>,
+    /// Body hash of the whole canonical stream, i.e.
+    /// `hash_body(&canonicalize_ndjson(input)?)`. Use this as the proof's
+    /// body hash.
+    pub aggregate: String,
+}
+
+/// Canonicalize an NDJSON body and hash each line and the whole stream.
+///
+/// Per-line hashes let a server log or deduplicate individual events; the
+/// aggregate protects the stream as a whole, including line order.
+///
+/// # Errors
+/// Same as [`canonicalize_ndjson`](crate::canonicalize_ndjson).
+#[cfg(feature = "std")]
+pub fn hash_ndjson(input: &str) -> Result<NdjsonHash, AshError> {
+    let canonical = crate::canonicalize::canonicalize_ndjson(input)?;
+    let lines = if canonical.is_empty() {
+        Vec::new()
+    } else {
+        canonical.split('\n').map(hash_body).collect()
+    };
+
+    Ok(NdjsonHash {
+        lines,
+        aggregate: hash_body(&canonical),
+    })
+}
+
+/// Compute the body hash of a request whose body may be absent.
+///
+/// `None` yields [`EMPTY_BODY_HASH`]; `Some(body)` hashes the canonical body.
+pub fn hash_optional_body(canonical_body: Option<&str>) -> String {
+    match canonical_body {
+        Some(body) => hash_body(body),
+        None => EMPTY_BODY_HASH.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests_v21 {
+    use super::*;
+
+    #[test]
+    fn test_derive_client_secret_deterministic() {
+        let secret1 = derive_client_secret("nonce123", "ctx_abc", "POST /login");
+        let secret2 = derive_client_secret("nonce123", "ctx_abc", "POST /login");
+        assert_eq!(secret1, secret2);
+    }
+
+    #[test]
+    fn test_derive_client_secret_different_inputs() {
+        let secret1 = derive_client_secret("nonce123", "ctx_abc", "POST /login");
+        let secret2 = derive_client_secret("nonce456", "ctx_abc", "POST /login");
+        assert_ne!(secret1, secret2);
+    }
+
+    #[test]
+    fn test_try_variants_reject_empty_key() {
+        let err = try_derive_client_secret("", "ctx_abc", "POST /login").unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+        let err = try_build_proof_v21("", "1234567890", "POST /login", "abc").unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+
+        let secret = try_derive_client_secret("nonce123", "ctx_abc", "POST /login").unwrap();
+        assert_eq!(
+            secret,
+            derive_client_secret("nonce123", "ctx_abc", "POST /login")
+        );
+        assert_eq!(
+            try_build_proof_v21(&secret, "1234567890", "POST /login", "abc").unwrap(),
+            build_proof_v21(&secret, "1234567890", "POST /login", "abc")
+        );
+    }
+
+    #[test]
+    fn test_derive_client_secret_v2_no_field_collision() {
+        // v1 joins with `|`, so moving the boundary gives the same secret
+        assert_eq!(
+            derive_client_secret("nonce123", "ctx|POST", "/x"),
+            derive_client_secret("nonce123", "ctx", "POST|/x")
+        );
+        assert_ne!(
+            derive_client_secret_v2("nonce123", "ctx|POST", "/x"),
+            derive_client_secret_v2("nonce123", "ctx", "POST|/x")
+        );
+        assert_ne!(
+            derive_client_secret_v2("nonce123", "ctx_abc", "POST /login"),
+            derive_client_secret("nonce123", "ctx_abc", "POST /login")
+        );
+    }
+
+    #[test]
+    fn test_derive_client_secret_v2_own_domain() {
+        // Not the v2.4 proof message keyed by the nonce
+        let message = framed_message(SECRET_V2_TAG, &["ctx_abc", "POST /login"]);
+        assert!(message.starts_with("ASH-secret-v2|"));
+        assert_ne!(
+            message,
+            framed_message(ASH_VERSION_V24, &["ctx_abc", "POST /login"])
+        );
+    }
+
+    #[test]
+    fn test_derive_client_secret_epoch_differs_by_epoch() {
+        let secret1 = derive_client_secret_epoch("nonce123", "ctx_abc", "POST /login", 100);
+        let secret2 = derive_client_secret_epoch("nonce123", "ctx_abc", "POST /login", 101);
+        assert_ne!(secret1, secret2);
+        assert_ne!(
+            secret1,
+            derive_client_secret("nonce123", "ctx_abc", "POST /login")
+        );
+    }
+
+    #[test]
+    fn test_derive_client_secret_epoch_no_v1_collision() {
+        // The epoch is framed, not appended to the v1 message
+        assert_ne!(
+            derive_client_secret_epoch("nonce123", "ctx_abc", "POST /login", 100),
+            derive_client_secret("nonce123", "ctx_abc", "POST /login|100")
+        );
+    }
+
+    #[test]
+    fn test_ensure_proof_v21() {
+        let client_secret = derive_client_secret("test_nonce_12345", "ctx_abc123", "POST /api");
+        let body_hash = hash_body("{}");
+        let proof = build_proof_v21(&client_secret, "1234567890", "POST /api", &body_hash);
+
+        assert!(ensure_proof_v21(
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /api",
+            "1234567890",
+            &body_hash,
+            &proof,
+        )
+        .is_ok());
+
+        let err = ensure_proof_v21(
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /api",
+            "1234567891",
+            &body_hash,
+            &proof,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::IntegrityFailed);
+    }
+
+    #[test]
+    fn test_committed_proof_round_trip() {
+        let client_secret = derive_client_secret("test_nonce_12345", "ctx_abc123", "POST /api");
+        let body_hash = hash_body("{}");
+        let proof =
+            build_proof_v21_committed(&client_secret, "1234567890", "POST /api", &body_hash);
+
+        assert_eq!(proof.len(), 96);
+        assert!(proof.starts_with(&build_proof_v21(
+            &client_secret,
+            "1234567890",
+            "POST /api",
+            &body_hash,
+        )));
+        assert!(verify_proof_v21_committed(
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /api",
+            "1234567890",
+            &body_hash,
+            &proof,
+        ));
+        assert!(!verify_proof_v21_committed(
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /api",
+            "1234567891",
+            &body_hash,
+            &proof,
+        ));
+        // Plain v2.1 verification does not accept the longer proof
+        assert!(!verify_proof_v21(
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /api",
+            "1234567890",
+            &body_hash,
+            &proof,
+        ));
+    }
+
+    #[test]
+    fn test_committed_proof_rejects_other_secret() {
+        let body_hash = hash_body("{}");
+        let proof = build_proof_v21_committed("secret_a", "1234567890", "POST /api", &body_hash);
+
+        assert!(committed_proof_matches(
+            "secret_a",
+            "1234567890",
+            "POST /api",
+            &body_hash,
+            &proof,
+        ));
+        assert!(!committed_proof_matches(
+            "secret_b",
+            "1234567890",
+            "POST /api",
+            &body_hash,
+            &proof,
+        ));
+
+        // Contrive an HMAC collision: the MAC half verifies under secret B,
+        // but the commitment still names secret A
+        let colliding = format!(
+            "{}{}",
+            build_proof_v21("secret_b", "1234567890", "POST /api", &body_hash),
+            &proof[64..],
+        );
+        assert!(!committed_proof_matches(
+            "secret_b",
+            "1234567890",
+            "POST /api",
+            &body_hash,
+            &colliding,
+        ));
+    }
+
+    #[test]
+    fn test_committed_proof_rejects_bad_length() {
+        let body_hash = hash_body("{}");
+        let proof = build_proof_v21_committed("secret", "1234567890", "POST /api", &body_hash);
+
+        assert!(!committed_proof_matches(
+            "secret",
+            "1234567890",
+            "POST /api",
+            &body_hash,
+            ""
+        ));
+        assert!(!committed_proof_matches(
+            "secret",
+            "1234567890",
+            "POST /api",
+            &body_hash,
+            &proof[..95],
+        ));
+    }
+
+    #[test]
+    fn test_device_bound_proof_rejects_other_device() {
+        let (nonce, context_id, binding) = ("nonce123", "ctx_abc", "POST /api/transfer");
+        let (timestamp, body_hash) = ("1700000000000", hash_body(r#"{"amount":100}"#));
+
+        let secret = derive_client_secret_with_device(nonce, context_id, binding, "device-a");
+        let proof = build_proof_v21(&secret, timestamp, binding, &body_hash);
+
+        let verify = |device| {
+            verify_proof_v21_with_device(
+                nonce, context_id, binding, device, timestamp, &body_hash, &proof,
+            )
+        };
+        assert!(verify("device-a"));
+        assert!(!verify("device-b"));
+        assert!(!verify(""));
+        // Device-bound proofs are not accepted by plain v2.1 verification
+        assert!(!verify_proof_v21(
+            nonce, context_id, binding, timestamp, &body_hash, &proof
+        ));
+    }
+
+    #[test]
+    fn test_env_secrets_differ_per_environment() {
+        let (nonce, context_id, binding) = ("nonce123", "ctx_abc", "POST /api/transfer");
+        let staging = derive_client_secret_env(nonce, "staging", context_id, binding);
+        let production = derive_client_secret_env(nonce, "production", context_id, binding);
+
+        assert_ne!(staging, production);
+        assert_ne!(staging, derive_client_secret(nonce, context_id, binding));
+        assert_ne!(staging, derive_client_secret_v2(nonce, context_id, binding));
+        assert_eq!(
+            staging,
+            derive_client_secret_env(nonce, "staging", context_id, binding)
+        );
+    }
+
+    #[test]
+    fn test_env_proof_fails_in_other_environment() {
+        let (nonce, context_id, binding) = ("nonce123", "ctx_abc", "POST /api/transfer");
+        let (timestamp, body_hash) = ("1700000000000", hash_body(r#"{"amount":100}"#));
+
+        let secret = derive_client_secret_env(nonce, "staging", context_id, binding);
+        let proof = build_proof_v21(&secret, timestamp, binding, &body_hash);
+
+        let verify = |environment| {
+            verify_proof_v21_env(
+                nonce,
+                environment,
+                context_id,
+                binding,
+                timestamp,
+                &body_hash,
+                &proof,
+            )
+        };
+        assert!(verify("staging"));
+        assert!(!verify("production"));
+        assert!(!verify(""));
+        assert!(!verify_proof_v21(
+            nonce, context_id, binding, timestamp, &body_hash, &proof
+        ));
+    }
+
+    #[test]
+    fn test_device_secret_distinct_from_epoch_secret() {
+        let device = derive_client_secret_with_device("nonce123", "ctx_abc", "POST /login", "100");
+        let epoch = derive_client_secret_epoch("nonce123", "ctx_abc", "POST /login", 100);
+        assert_ne!(device, epoch);
+        assert_ne!(
+            device,
+            derive_client_secret("nonce123", "ctx_abc", "POST /login")
+        );
+    }
+
+    #[test]
+    fn test_device_secret_no_cross_derivation_collision() {
+        let device = derive_client_secret_with_device("nonce123", "ctx_abc", "POST /x", "100");
+        // Neither the v1 message with a suffix nor an epoch on a shifted binding
+        assert_ne!(
+            device,
+            derive_client_secret("nonce123", "ctx_abc", "POST /x|device|100")
+        );
+        assert_ne!(
+            device,
+            derive_client_secret_epoch("nonce123", "ctx_abc", "POST /x|device", 100)
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_v21_epoch_accepts_adjacent_epoch() {
+        let nonce = "nonce123";
+        let context_id = "ctx_abc";
+        let binding = "POST /login";
+        let timestamp = "1234567890";
+        let body_hash = "bodyhash123";
+
+        let client_secret = derive_client_secret_epoch(nonce, context_id, binding, 100);
+        let proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+
+        // Same and next epoch accept; older or future epochs reject
+        assert!(verify_proof_v21_epoch(
+            nonce, context_id, binding, 100, timestamp, body_hash, &proof
+        ));
+        assert!(verify_proof_v21_epoch(
+            nonce, context_id, binding, 101, timestamp, body_hash, &proof
+        ));
+        assert!(!verify_proof_v21_epoch(
+            nonce, context_id, binding, 102, timestamp, body_hash, &proof
+        ));
+        assert!(!verify_proof_v21_epoch(
+            nonce, context_id, binding, 99, timestamp, body_hash, &proof
+        ));
+    }
+
+    #[test]
+    fn test_batch_reports_first_failing_index() {
+        let inputs = [r#"{"b":1,"a":2}"#, "[1,2]", r#"{"broken":"#, "{oops"];
+
+        for err in [
+            crate::canonicalize_json_batch(&inputs).unwrap_err(),
+            hash_body_batch(&inputs).unwrap_err(),
+        ] {
+            assert_eq!(err.code(), AshErrorCode::CanonicalizationFailed);
+            assert!(
+                err.message().starts_with("Batch item 2: "),
+                "{}",
+                err.message()
+            );
+        }
+
+        let hashes = hash_body_batch(&inputs[..2]).unwrap();
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes[0], hash_body(r#"{"a":2,"b":1}"#));
+        assert_eq!(hashes[1], hash_body("[1,2]"));
+        assert!(hash_body_batch(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_verify_proof_v21_multi() {
+        let context_id = "ctx_abc";
+        let binding = "POST /login";
+        let timestamp = "1234567890";
+        let body_hash = "bodyhash123";
+
+        let prove = |nonce| {
+            let client_secret = derive_client_secret(nonce, context_id, binding);
+            build_proof_v21(&client_secret, timestamp, binding, body_hash)
+        };
+        let verify = |nonces: &[&str], proof: &str| {
+            verify_proof_v21_multi(nonces, context_id, binding, timestamp, body_hash, proof)
+        };
+
+        // Match on first (new) and second (old) nonce
+        assert_eq!(verify(&["new", "old"], &prove("new")), Some(0));
+        assert_eq!(verify(&["new", "old"], &prove("old")), Some(1));
+
+        // No match, including no candidates
+        assert_eq!(verify(&["new", "old"], &prove("other")), None);
+        assert_eq!(verify(&[], &prove("new")), None);
+
+        // Duplicate candidates report the first index
+        assert_eq!(verify(&["old", "old"], &prove("old")), Some(0));
+    }
+
+    #[test]
+    fn test_verify_proof_v21_any_binding() {
+        let (nonce, context_id, timestamp, body_hash) =
+            ("nonce", "ctx_abc", "1234567890", "bodyhash123");
+        let bindings = ["POST /api/v2/orders", "POST /api/orders"];
+
+        let prove = |binding| {
+            let client_secret = derive_client_secret(nonce, context_id, binding);
+            build_proof_v21(&client_secret, timestamp, binding, body_hash)
+        };
+        let verify = |bindings: &[&str], proof: &str| {
+            verify_proof_v21_any_binding(nonce, context_id, bindings, timestamp, body_hash, proof)
+        };
+
+        // Match on the canonical route and on the alias
+        assert_eq!(verify(&bindings, &prove("POST /api/v2/orders")), Some(0));
+        assert_eq!(verify(&bindings, &prove("POST /api/orders")), Some(1));
+
+        // No match across all bindings, including no candidates
+        assert_eq!(verify(&bindings, &prove("POST /api/admin")), None);
+        assert_eq!(verify(&[], &prove("POST /api/orders")), None);
+    }
+
+    #[test]
+    fn test_verify_proof_v21_with_binding() {
+        let body_hash = hash_body(r#"{"a":1}"#);
+        let client_secret = derive_client_secret("nonce123", "ctx_abc", "POST /api/a");
+        let proof = build_proof_v21(&client_secret, "1500", "POST /api/a", &body_hash);
+
+        let verify = |expected, claimed| {
+            verify_proof_v21_with_binding(
+                "nonce123", "ctx_abc", expected, claimed, "1500", &body_hash, &proof,
+            )
+        };
+
+        // Equal after normalization
+        assert!(verify("POST /api/a", "post /api//a/").unwrap());
+
+        // Method mismatch
+        let err = verify("PUT /api/a", "POST /api/a").unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::EndpointMismatch);
+
+        // Path mismatch
+        let err = verify("POST /api/b", "POST /api/a").unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::EndpointMismatch);
+
+        // Not a binding at all
+        let err = verify("POST /api/a", "garbage").unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+    }
+
+    fn live_context() -> StoredContext {
+        StoredContext {
+            context_id: "ctx_abc".to_string(),
+            binding: "POST /login".to_string(),
+            mode: AshMode::Balanced,
+            issued_at: 1000,
+            expires_at: 2000,
+            nonce: Some("nonce123".to_string()),
+            consumed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_with_context_results() {
+        let context = live_context();
+        let body_hash = hash_body(r#"{"a":1}"#);
+        let client_secret = derive_client_secret("nonce123", "ctx_abc", "POST /login");
+        let proof = build_proof_v21(&client_secret, "1500", "POST /login", &body_hash);
+
+        let verify = |context: &StoredContext, now_ms, binding, proof: &str| {
+            verify_with_context(context, now_ms, binding, "1500", &body_hash, proof).unwrap()
+        };
+
+        assert_eq!(
+            verify(&context, 1500, "POST /login", &proof),
+            VerificationResult::Valid
+        );
+        assert_eq!(
+            verify(&context, 2000, "POST /login", &proof),
+            VerificationResult::Expired
+        );
+        assert_eq!(
+            verify(&context, 1500, "POST /logout", &proof),
+            VerificationResult::EndpointMismatch
+        );
+        assert_eq!(
+            verify(&context, 1500, "POST /login", &"0".repeat(64)),
+            VerificationResult::IntegrityFailed
+        );
+
+        let consumed = StoredContext {
+            consumed_at: Some(1200),
+            ..live_context()
+        };
+        assert_eq!(
+            verify(&consumed, 1500, "POST /login", &proof),
+            VerificationResult::Replay
+        );
+    }
+
+    #[test]
+    fn test_verify_with_context_requires_nonce() {
+        let context = StoredContext {
+            nonce: None,
+            ..live_context()
+        };
+        let body_hash = hash_body("{}");
+
+        let err = verify_with_context(
+            &context,
+            1500,
+            "POST /login",
+            "1500",
+            &body_hash,
+            &body_hash,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::ModeViolation);
+        assert_eq!(
+            VerificationResult::Expired.to_error().unwrap().code(),
+            AshErrorCode::ContextExpired
+        );
+        assert!(VerificationResult::Valid.to_error().is_none());
+    }
+
+    #[test]
+    fn test_build_proof_v21_deterministic() {
+        let proof1 = build_proof_v21("secret", "1234567890", "POST /login", "bodyhash");
+        let proof2 = build_proof_v21("secret", "1234567890", "POST /login", "bodyhash");
+        assert_eq!(proof1, proof2);
+    }
+
+    #[test]
+    fn test_verify_proof_v21() {
+        let nonce = "nonce123";
+        let context_id = "ctx_abc";
+        let binding = "POST /login";
+        let timestamp = "1234567890";
+        let body_hash = "bodyhash123";
+
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+
+        assert!(verify_proof_v21(nonce, context_id, binding, timestamp, body_hash, &proof));
+    }
+
+    #[test]
+    fn test_proof_v21_b64_round_trip() {
+        let hex_proof = build_proof_v21("secret", "1234567890", "POST /login", "bodyhash");
+        let b64_proof = build_proof_v21_b64("secret", "1234567890", "POST /login", "bodyhash");
+
+        assert_eq!(b64_proof.len(), 43);
+        assert_eq!(hex_proof_to_b64(&hex_proof).unwrap(), b64_proof);
+        assert_eq!(b64_proof_to_hex(&b64_proof).unwrap(), hex_proof);
+        assert!(hex_proof_to_b64("zz").is_err());
+        assert!(b64_proof_to_hex("***").is_err());
+    }
+
+    #[test]
+    fn test_verify_proof_v21_b64_encoding_mismatch() {
+        let nonce = "nonce123";
+        let context_id = "ctx_abc";
+        let binding = "POST /login";
+        let timestamp = "1234567890";
+        let body_hash = "bodyhash123";
+
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let hex_proof = build_proof_v21(&client_secret, timestamp, binding, body_hash);
+        let b64_proof = build_proof_v21_b64(&client_secret, timestamp, binding, body_hash);
+
+        let verify_b64 = |proof: &str| {
+            verify_proof_v21_b64(nonce, context_id, binding, timestamp, body_hash, proof)
+        };
+
+        assert!(verify_b64(&b64_proof));
+        assert!(!verify_b64(&hex_proof));
+        assert!(!verify_proof_v21(
+            nonce, context_id, binding, timestamp, body_hash, &b64_proof
+        ));
+    }
+
+    #[test]
+    fn test_generate_nonce_lengths() {
+        assert_eq!(generate_nonce(32).len(), 64);
+        assert_eq!(generate_nonce_bytes(32).len(), 32);
+    }
+
+    /// Deterministic source for tests: successive bytes of a counter.
+    struct CounterRandom(core::cell::Cell<u8>);
+
+    impl RandomSource for CounterRandom {
+        fn fill(&self, buf: &mut [u8]) -> Result<(), AshError> {
+            for byte in buf {
+                *byte = self.0.get();
+                self.0.set(self.0.get().wrapping_add(1));
+            }
+            Ok(())
+        }
+    }
+
+    struct FailingRandom;
+
+    impl RandomSource for FailingRandom {
+        fn fill(&self, _buf: &mut [u8]) -> Result<(), AshError> {
+            Err(AshError::new(AshErrorCode::RngUnavailable, "no entropy"))
+        }
+    }
+
+    #[test]
+    fn test_generate_nonce_with_deterministic_source() {
+        let nonce = generate_nonce_with(&CounterRandom(core::cell::Cell::new(0)), 4).unwrap();
+        assert_eq!(nonce, "00010203");
+        assert_eq!(
+            generate_nonce_with(&CounterRandom(core::cell::Cell::new(0)), 4).unwrap(),
+            nonce
+        );
+        assert_eq!(generate_nonce_with(&OsRandom, 32).unwrap().len(), 64);
+
+        let err = generate_nonce_with(&FailingRandom, 32).unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::RngUnavailable);
+    }
+
+    #[test]
+    fn test_derive_context_id() {
+        let id = derive_context_id("orders", "req-123");
+        assert_eq!(id, derive_context_id("orders", "req-123"));
+        assert!(id.starts_with("ash_"));
+        assert_eq!(id.len(), generate_context_id().len());
+
+        assert_ne!(id, derive_context_id("orders", "req-124"));
+        assert_ne!(id, derive_context_id("payments", "req-123"));
+    }
+
+    #[test]
+    fn test_try_generate_nonce() {
+        assert_eq!(try_generate_nonce(32).unwrap().len(), 64);
+        assert_eq!(try_generate_nonce_bytes(16).unwrap().len(), 16);
+
+        let context_id = try_generate_context_id().unwrap();
+        assert!(context_id.starts_with("ash_"));
+        assert_eq!(context_id.len(), 4 + 32);
+    }
+
+    #[test]
+    fn test_verify_proof_v21_checked_rejects_malformed_hex() {
+        let nonce = "nonce123";
+        let context_id = "ctx_abc";
+        let binding = "POST /login";
+        let timestamp = "1234567890";
+        let body_hash = hash_body("{}");
+
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let proof = build_proof_v21(&client_secret, timestamp, binding, &body_hash);
+        let check = |body_hash: &str, proof: &str| {
+            verify_proof_v21_checked(nonce, context_id, binding, timestamp, body_hash, proof)
+        };
+
+        assert!(check(&body_hash, &proof).unwrap());
+
+        let uppercase = proof.to_uppercase();
+        let wrong_length = &proof[..63];
+        let non_hex = format!("{}g", &proof[..63]);
+        for bad in [uppercase.as_str(), wrong_length, non_hex.as_str()] {
+            let err = check(&body_hash, bad).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+        }
+
+        let err = check("bodyhash", &proof).unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+
+        let err = verify_proof_v21_checked(nonce, context_id, binding, "12a", &body_hash, &proof)
+            .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+    }
+
+    #[test]
+    fn test_parse_timestamp_ms() {
+        assert_eq!(parse_timestamp_ms("0").unwrap(), 0);
+        assert_eq!(
+            parse_timestamp_ms("1704067200000").unwrap(),
+            1_704_067_200_000
+        );
+        assert_eq!(
+            parse_timestamp_ms("18446744073709551615").unwrap(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_ms_rejects_invalid() {
+        for bad in ["", "-1", "+1", "1.5", " 1", "1e3", "18446744073709551616"] {
+            let err = parse_timestamp_ms(bad).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::MalformedRequest, "{:?}", bad);
+            assert!(bad.is_empty() || !err.message().contains(bad));
+        }
+    }
+
+    #[test]
+    fn test_compute_expected_proof_v21_matches_client() {
+        let body_hash = hash_body(r#"{"a":1}"#);
+        let client_secret = derive_client_secret("nonce123", "ctx_abc", "POST /login");
+        let client_proof = build_proof_v21(&client_secret, "1234567890", "POST /login", &body_hash);
+
+        let expected = compute_expected_proof_v21(
+            "nonce123",
+            "ctx_abc",
+            "POST /login",
+            "1234567890",
+            &body_hash,
+        );
+        assert_eq!(expected, client_proof);
+        assert!(verify_proof_v21(
+            "nonce123",
+            "ctx_abc",
+            "POST /login",
+            "1234567890",
+            &body_hash,
+            &expected
+        ));
+    }
+
+    #[test]
+    fn test_normalize_content_type() {
+        assert_eq!(
+            normalize_content_type("Application/JSON; charset=utf-8"),
+            "application/json"
+        );
+        assert_eq!(normalize_content_type(" text/plain "), "text/plain");
+        assert_eq!(normalize_content_type(""), "");
+    }
+
+    #[test]
+    fn test_proof_with_content_type() {
+        let secret = derive_client_secret("nonce123", "ctx_abc", "POST /api");
+        let body_hash = hash_body(r#"{"a":1}"#);
+        let prove = |content_type| {
+            build_proof_v21_with_content_type(&secret, "1", "POST /api", content_type, &body_hash)
+        };
+
+        let json = prove("application/json");
+        assert_ne!(json, prove("text/plain"));
+        assert_ne!(json, build_proof_v21(&secret, "1", "POST /api", &body_hash));
+        assert_eq!(json, prove("Application/JSON; charset=utf-8"));
+
+        let verify = |content_type, proof: &str| {
+            verify_proof_v21_with_content_type(
+                "nonce123",
+                "ctx_abc",
+                "POST /api",
+                "1",
+                content_type,
+                &body_hash,
+                proof,
+            )
+        };
+        assert!(verify("application/json; charset=UTF-8", &json));
+        assert!(!verify("text/plain", &json));
+    }
+
+    #[test]
+    fn test_build_proof_v21_with_alg_default_is_hmac() {
+        let secret = derive_client_secret("nonce123", "ctx_abc", "POST /api");
+        let body_hash = hash_body("{}");
+
+        assert_eq!(HashAlg::default(), HashAlg::HmacSha256);
+        assert_eq!(
+            build_proof_v21_with_alg(HashAlg::HmacSha256, &secret, "1", "POST /api", &body_hash),
+            build_proof_v21(&secret, "1", "POST /api", &body_hash)
+        );
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_keyed_proof() {
+        let (nonce, context_id, binding) = ("nonce123", "ctx_abc", "POST /api");
+        let secret = derive_client_secret(nonce, context_id, binding);
+        let body_hash = hash_body(r#"{"amount":100}"#);
+        let build = |alg| build_proof_v21_with_alg(alg, &secret, "1", binding, &body_hash);
+
+        let proof = build(HashAlg::Blake3Keyed);
+        assert_eq!(proof, build(HashAlg::Blake3Keyed));
+        assert!(is_well_formed_proof_v21(&proof));
+
+        let verify = |alg, proof: &str| {
+            verify_proof_v21_with_alg(alg, nonce, context_id, binding, "1", &body_hash, proof)
+        };
+        assert!(verify(HashAlg::Blake3Keyed, &proof));
+        // Proofs are not interchangeable across algorithms
+        let hmac_proof = build(HashAlg::HmacSha256);
+        assert_ne!(proof, hmac_proof);
+        assert!(!verify(HashAlg::HmacSha256, &proof));
+        assert!(!verify(HashAlg::Blake3Keyed, &hmac_proof));
+        assert!(!verify_proof_v21(
+            nonce, context_id, binding, "1", &body_hash, &proof
+        ));
+
+        // Any change to the message or secret changes the proof
+        let other_secret = derive_client_secret("nonce456", context_id, binding);
+        assert_ne!(
+            build_proof_v21_with_alg(
+                HashAlg::Blake3Keyed,
+                &other_secret,
+                "1",
+                binding,
+                &body_hash
+            ),
+            proof
+        );
+        assert_ne!(
+            build_proof_v21_with_alg(HashAlg::Blake3Keyed, &secret, "2", binding, &body_hash),
+            proof
+        );
+    }
+
+    #[test]
+    fn test_hash_ndjson() {
+        let hashed = hash_ndjson("{\"b\":1,\"a\":2}\n\n[1.0]\n\"x\"\n").unwrap();
+
+        assert_eq!(
+            hashed.lines,
+            vec![
+                hash_body(r#"{"a":2,"b":1}"#),
+                hash_body("[1]"),
+                hash_body(r#""x""#)
+            ]
+        );
+        assert_eq!(hashed.aggregate, hash_body("{\"a\":2,\"b\":1}\n[1]\n\"x\""));
+
+        // Reordering lines changes the aggregate but not the line set
+        let reordered = hash_ndjson("\"x\"\n[1]\n{\"a\":2,\"b\":1}").unwrap();
+        assert_ne!(reordered.aggregate, hashed.aggregate);
+        let mut lines = reordered.lines.clone();
+        lines.reverse();
+        assert_eq!(lines, hashed.lines);
+
+        let empty = hash_ndjson("\n").unwrap();
+        assert!(empty.lines.is_empty());
+        assert_eq!(empty.aggregate, EMPTY_BODY_HASH);
+
+        assert!(hash_ndjson("{}\n{bad")
+            .unwrap_err()
+            .message()
+            .starts_with("Line 2:"));
+    }
+
+    #[test]
+    fn test_hash_body_bytes_matches_hash_body() {
+        for body in ["", "{}", r#"{"a":1,"b":"caf\u{e9}"}"#, "name=a&x=%20"] {
+            assert_eq!(hash_body_bytes(body.as_bytes()), hash_body(body));
+        }
+        assert_eq!(hash_body_bytes(b""), EMPTY_BODY_HASH);
+    }
+
+    #[test]
+    fn test_build_proof_v21_bytes() {
+        let secret = derive_client_secret("nonce123", "ctx_abc", "POST /upload");
+        let body: &[u8] = &[0x00, 0xff, 0xfe, 0x80];
+
+        let proof = build_proof_v21_bytes(&secret, "1", "POST /upload", body);
+        assert_eq!(
+            proof,
+            build_proof_v21(&secret, "1", "POST /upload", &hash_body_bytes(body))
+        );
+        assert!(verify_proof_v21(
+            "nonce123",
+            "ctx_abc",
+            "POST /upload",
+            "1",
+            &hash_body_bytes(body),
+            &proof,
+        ));
+        assert!(!verify_proof_v21(
+            "nonce123",
+            "ctx_abc",
+            "POST /upload",
+            "1",
+            &hash_body_bytes(&[0x00, 0xff, 0xfe, 0x81]),
+            &proof,
+        ));
+    }
+
+    #[test]
+    fn test_is_well_formed_proof_v21() {
+        let secret = derive_client_secret("nonce123", "ctx_abc", "POST /api");
+        let proof = build_proof_v21(&secret, "1", "POST /api", &hash_body("{}"));
+
+        assert!(is_well_formed_proof_v21(&proof));
+        assert!(is_well_formed_proof_v21(&"0123456789abcdef".repeat(4)));
+
+        let uppercase = proof.to_uppercase();
+        let non_hex = format!("{}g", &proof[..63]);
+        let too_long = format!("{}0", proof);
+        for bad in ["", &proof[..63], &too_long, &uppercase, &non_hex] {
+            assert!(!is_well_formed_proof_v21(bad), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_is_well_formed_hashes() {
+        let digest = hash_body("{}");
+
+        assert!(is_well_formed_body_hash(&digest));
+        assert!(!is_well_formed_body_hash(""));
+        assert!(!is_well_formed_body_hash(&digest.to_uppercase()));
+
+        for check in [is_well_formed_scope_hash, is_well_formed_chain_hash] {
+            assert!(check(""));
+            assert!(check(&digest));
+            assert!(!check(&digest[..32]));
+            assert!(!check(&digest.replace('a', "z")));
+        }
+    }
+
+    fn full_body_proof(payload: &str) -> String {
+        let client_secret = derive_client_secret("nonce123", "ctx_abc", "POST /api");
+        let body_hash = hash_body(&crate::canonicalize_json(payload).unwrap());
+        build_proof_v21(&client_secret, "1234567890", "POST /api", &body_hash)
+    }
+
+    #[test]
+    fn test_logic_flaw_type_confusion() {
+        assert_ne!(
+            crate::canonicalize_json(r#"{"n":"123"}"#).unwrap(),
+            crate::canonicalize_json(r#"{"n":123}"#).unwrap()
+        );
+        assert_ne!(
+            full_body_proof(r#"{"n":"123"}"#),
+            full_body_proof(r#"{"n":123}"#)
+        );
+        assert_ne!(
+            full_body_proof(r#"{"n":"true"}"#),
+            full_body_proof(r#"{"n":true}"#)
+        );
+        assert_ne!(
+            full_body_proof(r#"{"n":"null"}"#),
+            full_body_proof(r#"{"n":null}"#)
+        );
+    }
+
+    #[test]
+    fn test_logic_flaw_array_vs_object() {
+        assert_ne!(
+            full_body_proof(r#"{"a":[1]}"#),
+            full_body_proof(r#"{"a":{"0":1}}"#)
+        );
+        assert_ne!(
+            full_body_proof(r#"{"a":[]}"#),
+            full_body_proof(r#"{"a":{}}"#)
+        );
+    }
+
+    #[test]
+    fn test_hash_body() {
+        let hash = hash_body(r#"{"name":"John"}"#);
+        assert_eq!(hash.len(), 64); // SHA-256 produces 32 bytes = 64 hex chars
+    }
+
+    #[test]
+    fn test_hash_body_empty_vs_json_bodies() {
+        let empty = hash_body("");
+        let empty_object = hash_body(&crate::canonicalize_json("{}").unwrap());
+        let null = hash_body(&crate::canonicalize_json("null").unwrap());
+
+        assert_eq!(empty, EMPTY_BODY_HASH);
+        assert_eq!(hash_optional_body(None), EMPTY_BODY_HASH);
+        assert_ne!(empty, empty_object);
+        assert_ne!(empty, null);
+        assert_ne!(empty_object, null);
+
+        // An empty body cannot come out of JSON canonicalization
+        assert!(crate::canonicalize_json("").is_err());
+    }
+
+    #[test]
+    fn test_bodyless_proof_rejects_empty_object_body() {
+        let nonce = "nonce123";
+        let context_id = "ctx_abc";
+        let binding = "DELETE /api/users/1";
+        let timestamp = "1234567890";
+
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let proof = build_proof_v21(
+            &client_secret,
+            timestamp,
+            binding,
+            &hash_optional_body(None),
+        );
+
+        let empty_object_hash = hash_optional_body(Some("{}"));
+        assert!(!verify_proof_v21(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            &empty_object_hash,
+            &proof
+        ));
+        assert!(verify_proof_v21(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            EMPTY_BODY_HASH,
+            &proof
+        ));
+    }
 
-        match current {
-            Value::Object(map) => {
-                current = map.get(key)?;
-                if let Some(idx) = index {
-                    if let Value::Array(arr) = current {
-                        current = arr.get(idx)?;
-                    } else {
-                        return None;
+    #[test]
+    fn test_body_hasher_streaming_matches_one_shot() {
+        let body: String = "abcdefghijklmnopqrstuvwxyz0123456789"
+            .chars()
+            .cycle()
+            .take(5 * 1024 * 1024)
+            .collect();
+
+        let mut hasher = BodyHasher::new();
+        for chunk in body.as_bytes().chunks(64 * 1024) {
+            hasher.update(chunk);
+        }
+
+        assert_eq!(hasher.finalize(), hash_body(&body));
+    }
+}
+
+// =========================================================================
+// ASH v2.2 - Context Scoping (Selective Field Protection)
+// =========================================================================
+
+use serde_json::{Map, Value};
+
+/// Extract scoped fields from a JSON value.
+///
+/// Each scope entry is a dot-separated path of object keys; an element of
+/// an array is selected only with bracket notation (`items[0].id`). Values
+/// are copied unchanged, so `"123"` and `123` stay distinct. A dotted
+/// numeric segment never indexes an array: `a.0` matches `{"a":{"0":1}}`
+/// but not `{"a":[1]}`, which would otherwise extract to the same object.
+pub fn extract_scoped_fields(payload: &Value, scope: &[&str]) -> Result<Value, AshError> {
+    extract_scoped_fields_with_options(payload, scope, &ScopeOptions::default())
+}
+
+/// Options controlling scoped field extraction.
+///
+/// `ScopeOptions::default()` reproduces [`extract_scoped_fields`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScopeOptions {
+    /// Extract `""` as `null`, so a field sent as an empty string and one
+    /// sent as `null` produce the same scoped body hash.
+    ///
+    /// Defaults to `false`: the two are distinct values. Only enable it for
+    /// lenient endpoints that already treat them alike, on both sides.
+    pub treat_empty_as_null: bool,
+    /// Syntax of the scope paths (dotted by default).
+    pub syntax: ScopeSyntax,
+}
+
+/// Syntax of scope paths.
+///
+/// Both sides must agree: the scope hash covers the path strings, so
+/// `items[0].id` and `/items/0/id` extract the same value but give
+/// different scope hashes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScopeSyntax {
+    /// `user.address.city`, `items[0].id`. Keys containing `.` or `[`
+    /// cannot be addressed.
+    #[default]
+    Dotted,
+    /// RFC 6901 JSON Pointer: `/user/address/city`, `/items/0/id`.
+    ///
+    /// Any key is reachable: `~1` escapes `/` and `~0` escapes `~`, and
+    /// `.` has no special meaning (`/a.b` is the key `"a.b"`). A token
+    /// indexes an array only when the value is an array. Extracted values
+    /// are placed exactly as for the equivalent dotted path.
+    JsonPointer,
+}
+
+/// Extract scoped fields with explicit [`ScopeOptions`].
+pub fn extract_scoped_fields_with_options(
+    payload: &Value,
+    scope: &[&str],
+    options: &ScopeOptions,
+) -> Result<Value, AshError> {
+    let mut result = if scope.is_empty() {
+        payload.clone()
+    } else {
+        let mut result = Map::new();
+        for field_path in scope {
+            match options.syntax {
+                ScopeSyntax::Dotted => {
+                    if let Some(v) = get_nested_value(payload, field_path) {
+                        set_nested_value(&mut result, field_path, v);
+                    }
+                }
+                ScopeSyntax::JsonPointer => {
+                    let tokens = parse_json_pointer(field_path)?;
+                    if let Some((keys, v)) = lookup_json_pointer(payload, &tokens) {
+                        set_nested_keys(&mut result, &keys, v.clone());
                     }
                 }
             }
-            Value::Array(arr) => {
-                let idx: usize = key.parse().ok()?;
-                current = arr.get(idx)?;
-            }
-            _ => return None,
+        }
+        Value::Object(result)
+    };
+
+    if options.treat_empty_as_null {
+        empty_strings_to_null(&mut result);
+    }
+    Ok(result)
+}
+
+fn empty_strings_to_null(value: &mut Value) {
+    match value {
+        Value::String(s) if s.is_empty() => *value = Value::Null,
+        Value::Array(items) => items.iter_mut().for_each(empty_strings_to_null),
+        Value::Object(map) => map.values_mut().for_each(empty_strings_to_null),
+        _ => {}
+    }
+}
+
+/// Result of [`resolve_scope`]: each scope path lands in exactly one list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScopeResolution {
+    /// Paths that resolve to a value.
+    pub present: Vec<String>,
+    /// Paths whose key or array index does not exist.
+    pub missing: Vec<String>,
+    /// Paths that step through the wrong type, such as `items[0]` when
+    /// `items` is an object or `a.b` when `a` is a string.
+    pub type_mismatch: Vec<String>,
+}
+
+/// Report which scope paths are present in a JSON payload.
+///
+/// Lets clients surface validation errors before building a scoped proof:
+/// missing and mismatched paths are silently omitted by
+/// [`extract_scoped_fields`]. Paths are reported in the order given.
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::resolve_scope;
+///
+/// let payload = r#"{"amount":1,"items":{"x":1}}"#;
+/// let resolution = resolve_scope(payload, &["amount", "to", "items[0].x"]).unwrap();
+/// assert_eq!(resolution.present, vec!["amount"]);
+/// assert_eq!(resolution.missing, vec!["to"]);
+/// assert_eq!(resolution.type_mismatch, vec!["items[0].x"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns `CanonicalizationFailed` if `payload` is not valid JSON.
+pub fn resolve_scope(payload: &str, scope: &[&str]) -> Result<ScopeResolution, AshError> {
+    let json_payload: Value = serde_json::from_str(payload)
+        .map_err(|e| AshError::canonicalization_failed(&format!("Invalid JSON: {}", e)))?;
+
+    let mut resolution = ScopeResolution::default();
+    for path in scope {
+        let list = match lookup_nested_value(&json_payload, path) {
+            NestedLookup::Found(_) => &mut resolution.present,
+            NestedLookup::Missing => &mut resolution.missing,
+            NestedLookup::TypeMismatch => &mut resolution.type_mismatch,
+        };
+        list.push(path.to_string());
+    }
+
+    Ok(resolution)
+}
+
+/// Outcome of walking a scope path through a payload.
+enum NestedLookup<'a> {
+    Found(&'a Value),
+    Missing,
+    TypeMismatch,
+}
+
+fn lookup_nested_value<'a>(payload: &'a Value, path: &str) -> NestedLookup<'a> {
+    let mut current = payload;
+
+    for part in path.split('.') {
+        let (key, index) = parse_array_notation(part);
+
+        let Value::Object(map) = current else {
+            return NestedLookup::TypeMismatch;
+        };
+        let Some(next) = map.get(key) else {
+            return NestedLookup::Missing;
+        };
+        current = next;
+
+        if let Some(idx) = index {
+            let Value::Array(arr) = current else {
+                return NestedLookup::TypeMismatch;
+            };
+            let Some(next) = arr.get(idx) else {
+                return NestedLookup::Missing;
+            };
+            current = next;
         }
     }
 
-    Some(current.clone())
+    NestedLookup::Found(current)
+}
+
+fn get_nested_value(payload: &Value, path: &str) -> Option<Value> {
+    match lookup_nested_value(payload, path) {
+        NestedLookup::Found(value) => Some(value.clone()),
+        NestedLookup::Missing | NestedLookup::TypeMismatch => None,
+    }
 }
 
-fn parse_array_notation(part: &str) -> (&str, Option<usize>) {
+pub(crate) fn parse_array_notation(part: &str) -> (&str, Option<usize>) {
     if let Some(bracket_start) = part.find('[') {
         if let Some(bracket_end) = part.find(']') {
             let key = &part[..bracket_start];
@@ -548,26 +2777,152 @@ fn parse_array_notation(part: &str) -> (&str, Option<usize>) {
 }
 
 fn set_nested_value(result: &mut Map<String, Value>, path: &str, value: Value) {
-    let parts: Vec<&str> = path.split('.').collect();
+    let keys: Vec<&str> = path
+        .split('.')
+        .map(|part| parse_array_notation(part).0)
+        .collect();
+    set_nested_keys(result, &keys, value);
+}
+
+/// Insert `value` under the object key path `keys`, creating objects as needed.
+fn set_nested_keys(result: &mut Map<String, Value>, keys: &[&str], value: Value) {
+    match keys {
+        [] => {}
+        [key] => {
+            result.insert(key.to_string(), value);
+        }
+        [first, rest @ ..] => {
+            let nested = result
+                .entry(first.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+
+            if let Value::Object(nested_map) = nested {
+                set_nested_keys(nested_map, rest, value);
+            }
+        }
+    }
+}
 
-    if parts.len() == 1 {
-        let (key, _) = parse_array_notation(parts[0]);
-        result.insert(key.to_string(), value);
-        return;
+/// Split an RFC 6901 JSON Pointer into unescaped reference tokens.
+fn parse_json_pointer(pointer: &str) -> Result<Vec<String>, AshError> {
+    let invalid = || {
+        AshError::new(
+            AshErrorCode::MalformedRequest,
+            format!("Invalid JSON Pointer scope path: {}", pointer),
+        )
+    };
+
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(invalid());
+    };
+    rest.split('/')
+        .map(|token| {
+            let mut out = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    out.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => out.push('~'),
+                    Some('1') => out.push('/'),
+                    _ => return Err(invalid()),
+                }
+            }
+            Ok(out)
+        })
+        .collect()
+}
+
+/// Resolve pointer tokens, returning the object keys passed through and the value.
+fn lookup_json_pointer<'a, 't>(
+    payload: &'a Value,
+    tokens: &'t [String],
+) -> Option<(Vec<&'t str>, &'a Value)> {
+    let mut current = payload;
+    let mut keys = Vec::new();
+
+    for token in tokens {
+        current = match current {
+            Value::Object(map) => {
+                keys.push(token.as_str());
+                map.get(token)?
+            }
+            Value::Array(arr) => {
+                // RFC 6901 array indices: decimal digits, no leading zeros
+                let canonical = token == "0" || !token.starts_with('0');
+                if token.is_empty() || !canonical || !token.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                arr.get(token.parse::<usize>().ok()?)?
+            }
+            _ => return None,
+        };
     }
 
-    let (first_key, _) = parse_array_notation(parts[0]);
-    let remaining_path = parts[1..].join(".");
+    Some((keys, current))
+}
+
+/// Sort and dedupe a scope list so it is treated as a set.
+fn normalize_scope<'a>(scope: &[&'a str]) -> Vec<&'a str> {
+    let mut normalized = scope.to_vec();
+    normalized.sort_unstable();
+    normalized.dedup();
+    normalized
+}
+
+/// Expected scope hash for a scope list (server-side policy checks).
+///
+/// The scope is a set: it is sorted and deduplicated before hashing, so
+/// `["b","a","a"]` and `["a","b"]` hash identically. This is the
+/// `scope_hash` that [`build_proof_v21_scoped`] and
+/// [`build_proof_v21_unified`] return, computed without building a proof.
+/// Callers of the unified functions pass `""` instead for an empty scope.
+///
+/// Formula: scopeHash = SHA256(sorted(dedupe(scope)).join(","))
+pub fn scope_hash(scope: &[&str]) -> String {
+    hash_sorted_scope(&normalize_scope(scope))
+}
+
+/// Hash a scope list that is already sorted and deduplicated.
+fn hash_sorted_scope(sorted: &[&str]) -> String {
+    hash_body(&sorted.join(","))
+}
 
-    let nested = result
-        .entry(first_key.to_string())
-        .or_insert_with(|| Value::Object(Map::new()));
+/// A scope list the caller guarantees is sorted and deduplicated.
+///
+/// Verifiers normally sort and dedupe the scope on every call. Servers that
+/// store their scopes in that canonical order can wrap them once and use
+/// [`verify_proof_v21_scoped_presorted`] or
+/// [`verify_proof_v21_unified_presorted`] to skip that step. The invariant
+/// is only checked by a debug assertion; an unsorted list in a release build
+/// simply fails verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreSortedScope<'a>(&'a [&'a str]);
+
+impl<'a> PreSortedScope<'a> {
+    /// Wrap a scope list that is sorted (byte order) and has no duplicates.
+    ///
+    /// # Panics
+    /// In debug builds, if `scope` is not strictly increasing.
+    pub fn new(scope: &'a [&'a str]) -> Self {
+        debug_assert!(
+            scope.windows(2).all(|w| w[0] < w[1]),
+            "PreSortedScope must be sorted and deduplicated"
+        );
+        Self(scope)
+    }
 
-    if let Value::Object(nested_map) = nested {
-        set_nested_value(nested_map, &remaining_path, value);
+    /// The wrapped scope list.
+    pub fn as_slice(&self) -> &'a [&'a str] {
+        self.0
     }
 }
+
 /// Build v2.2 cryptographic proof with scoped fields.
+///
+/// The scope is a set: field order and duplicates do not affect the proof.
 pub fn build_proof_v21_scoped(
     client_secret: &str,
     timestamp: &str,
@@ -578,26 +2933,36 @@ pub fn build_proof_v21_scoped(
     let json_payload: Value = serde_json::from_str(payload)
         .map_err(|e| AshError::canonicalization_failed(&format!("Invalid JSON: {}", e)))?;
 
-    let scoped_payload = extract_scoped_fields(&json_payload, scope)?;
+    let scoped_payload = extract_scoped_fields(&json_payload, &normalize_scope(scope))?;
 
     let canonical_scoped = serde_json::to_string(&scoped_payload)
         .map_err(|e| AshError::canonicalization_failed(&format!("Failed to serialize: {}", e)))?;
 
     let body_hash = hash_body(&canonical_scoped);
 
-    let scope_str = scope.join(",");
-    let scope_hash = hash_body(&scope_str);
+    let scope_hash = scope_hash(scope);
+    let proof = scoped_proof(client_secret, timestamp, binding, &body_hash, &scope_hash);
+
+    Ok((proof, scope_hash))
+}
 
+/// HMAC of the v2.2 message `timestamp|binding|bodyHash|scopeHash`.
+fn scoped_proof(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+    scope_hash: &str,
+) -> String {
     let message = format!("{}|{}|{}|{}", timestamp, binding, body_hash, scope_hash);
     let mut mac = HmacSha256Type::new_from_slice(client_secret.as_bytes())
         .expect("HMAC can take key of any size");
     mac.update(message.as_bytes());
-    let proof = hex::encode(mac.finalize().into_bytes());
-
-    Ok((proof, scope_hash))
+    hex::encode(mac.finalize().into_bytes())
 }
 
 /// Verify v2.2 proof with scoped fields.
+#[allow(clippy::too_many_arguments)]
 pub fn verify_proof_v21_scoped(
     nonce: &str,
     context_id: &str,
@@ -608,8 +2973,10 @@ pub fn verify_proof_v21_scoped(
     scope_hash: &str,
     client_proof: &str,
 ) -> Result<bool, AshError> {
-    let scope_str = scope.join(",");
-    let expected_scope_hash = hash_body(&scope_str);
+    validate_hex_digest("client_proof", client_proof)?;
+    validate_hex_digest("scope_hash", scope_hash)?;
+
+    let expected_scope_hash = self::scope_hash(scope);
     if !timing_safe_equal(expected_scope_hash.as_bytes(), scope_hash.as_bytes()) {
         return Ok(false);
     }
@@ -624,25 +2991,423 @@ pub fn verify_proof_v21_scoped(
         scope,
     )?;
 
-    Ok(timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes()))
-}
+    Ok(timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes()))
+}
+
+/// Verify v2.2 proof from a precomputed scoped body hash (server-side).
+///
+/// Same result as [`verify_proof_v21_scoped`] without re-parsing and
+/// re-scoping the payload. `body_hash` must be exactly what
+/// [`hash_scoped_body`] returns for the payload and `scope`; a hash from
+/// any other canonicalization simply fails verification.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_proof_v21_scoped_from_hash(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    scope: &[&str],
+    scope_hash: &str,
+    client_proof: &str,
+) -> Result<bool, AshError> {
+    validate_hex_digest("client_proof", client_proof)?;
+    validate_hex_digest("scope_hash", scope_hash)?;
+    validate_hex_digest("body_hash", body_hash)?;
+
+    let expected_scope_hash = self::scope_hash(scope);
+    if !timing_safe_equal(expected_scope_hash.as_bytes(), scope_hash.as_bytes()) {
+        return Ok(false);
+    }
+
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected_proof = scoped_proof(
+        &client_secret,
+        timestamp,
+        binding,
+        body_hash,
+        &expected_scope_hash,
+    );
+
+    Ok(timing_safe_equal(
+        expected_proof.as_bytes(),
+        client_proof.as_bytes(),
+    ))
+}
+
+/// Verify v2.2 proof from a precomputed body hash and scope hash only
+/// (server-side).
+///
+/// For clients that keep their scope private: they send only the scope
+/// hash and scoped body hash, and the server never sees the field names.
+///
+/// # Trust model
+///
+/// Nothing here ties `body_hash` to the request payload or `scope_hash`
+/// to a scope list; the proof only shows that the client holds the secret
+/// and committed to these two hashes. The caller must derive both itself,
+/// from the payload and a scope learned out-of-band (e.g. with
+/// [`hash_scoped_body`] and [`scope_hash`]), or otherwise accept that the
+/// client chose which fields are protected. Passing the client's hashes
+/// through unchecked protects nothing about the body.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_proof_v21_scoped_by_hash(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    scope_hash: &str,
+    client_proof: &str,
+) -> Result<bool, AshError> {
+    validate_hex_digest("client_proof", client_proof)?;
+    validate_hex_digest("scope_hash", scope_hash)?;
+    validate_hex_digest("body_hash", body_hash)?;
+
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected_proof = scoped_proof(&client_secret, timestamp, binding, body_hash, scope_hash);
+
+    Ok(timing_safe_equal(
+        expected_proof.as_bytes(),
+        client_proof.as_bytes(),
+    ))
+}
+
+/// Hash scoped payload for client-side use.
+pub fn hash_scoped_body(payload: &str, scope: &[&str]) -> Result<String, AshError> {
+    hash_scoped_body_with_options(payload, scope, &ScopeOptions::default())
+}
+
+/// Hash scoped payload with explicit [`ScopeOptions`].
+///
+/// Pair with [`verify_proof_v21_scoped_from_hash`] or
+/// [`verify_proof_v21_unified_from_hash`] to verify with non-default options.
+///
+/// Scoped values are copied as sent; [`CanonicalizeOptions`] such as
+/// `numeric_string_fields` are not applied. Pass the output of
+/// [`canonicalize_json_with_options`] as `payload` to hash normalized values.
+///
+/// [`CanonicalizeOptions`]: crate::CanonicalizeOptions
+/// [`canonicalize_json_with_options`]: crate::canonicalize_json_with_options
+pub fn hash_scoped_body_with_options(
+    payload: &str,
+    scope: &[&str],
+    options: &ScopeOptions,
+) -> Result<String, AshError> {
+    hash_sorted_scoped_body(payload, &normalize_scope(scope), options)
+}
+
+/// Scoped body hash for a scope list that is already sorted and deduplicated.
+fn hash_sorted_scoped_body(
+    payload: &str,
+    sorted: &[&str],
+    options: &ScopeOptions,
+) -> Result<String, AshError> {
+    let json_payload: Value = serde_json::from_str(payload)
+        .map_err(|e| AshError::canonicalization_failed(&format!("Invalid JSON: {}", e)))?;
+
+    let scoped_payload = extract_scoped_fields_with_options(&json_payload, sorted, options)?;
+
+    let canonical_scoped = serde_json::to_string(&scoped_payload)
+        .map_err(|e| AshError::canonicalization_failed(&format!("Failed to serialize: {}", e)))?;
+
+    Ok(hash_body(&canonical_scoped))
+}
+
+/// Verify v2.2 proof with a pre-sorted scope (server-side).
+///
+/// Same result as [`verify_proof_v21_scoped`] for the same scope, without
+/// sorting and deduplicating it again.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_proof_v21_scoped_presorted(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    payload: &str,
+    scope: PreSortedScope<'_>,
+    scope_hash: &str,
+    client_proof: &str,
+) -> Result<bool, AshError> {
+    validate_hex_digest("client_proof", client_proof)?;
+    validate_hex_digest("scope_hash", scope_hash)?;
+
+    let expected_scope_hash = hash_sorted_scope(scope.as_slice());
+    if !timing_safe_equal(expected_scope_hash.as_bytes(), scope_hash.as_bytes()) {
+        return Ok(false);
+    }
+
+    let body_hash = hash_sorted_scoped_body(payload, scope.as_slice(), &ScopeOptions::default())?;
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected_proof = scoped_proof(
+        &client_secret,
+        timestamp,
+        binding,
+        &body_hash,
+        &expected_scope_hash,
+    );
+
+    Ok(timing_safe_equal(
+        expected_proof.as_bytes(),
+        client_proof.as_bytes(),
+    ))
+}
+
+#[cfg(test)]
+mod tests_v22_scoping {
+    use super::*;
+
+    #[test]
+    fn test_scoped_hash_of_canonicalized_numeric_strings() {
+        use crate::{canonicalize_json_with_options, CanonicalizeOptions};
+
+        let options = CanonicalizeOptions {
+            numeric_string_fields: vec!["id".to_string()],
+            ..Default::default()
+        };
+        let scoped = |payload: &str| {
+            let canonical = canonicalize_json_with_options(payload, &options).unwrap();
+            hash_scoped_body(&canonical, &["id"]).unwrap()
+        };
+
+        // Raw values are hashed as sent; canonicalizing first normalizes them
+        assert_ne!(
+            hash_scoped_body(r#"{"id":"007"}"#, &["id"]).unwrap(),
+            hash_scoped_body(r#"{"id":"7"}"#, &["id"]).unwrap()
+        );
+        assert_eq!(scoped(r#"{"id":"007"}"#), scoped(r#"{"id":"7"}"#));
+    }
+
+    #[test]
+    fn test_scoped_presorted_agrees_with_unsorted() {
+        let (nonce, context_id, binding, timestamp) = (
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /transfer",
+            "1234567890",
+        );
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let (proof, scope_hash) = build_proof_v21_scoped(
+            &client_secret,
+            timestamp,
+            binding,
+            payload,
+            &["recipient", "amount", "amount"],
+        )
+        .unwrap();
+
+        let sorted = ["amount", "recipient"];
+        let verify = |scope_hash: &str, proof: &str| {
+            verify_proof_v21_scoped_presorted(
+                nonce,
+                context_id,
+                binding,
+                timestamp,
+                payload,
+                PreSortedScope::new(&sorted),
+                scope_hash,
+                proof,
+            )
+            .unwrap()
+        };
+        assert!(verify(&scope_hash, &proof));
+        assert!(!verify(&self::scope_hash(&["amount"]), &proof));
+        assert!(!verify(&scope_hash, &"0".repeat(64)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "sorted and deduplicated")]
+    fn test_presorted_scope_asserts_sorted() {
+        PreSortedScope::new(&["recipient", "amount"]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "sorted and deduplicated")]
+    fn test_presorted_scope_asserts_deduplicated() {
+        PreSortedScope::new(&["amount", "amount"]);
+    }
+
+    #[test]
+    fn test_scoped_from_hash_agrees_with_payload() {
+        let (nonce, context_id, binding, timestamp) = (
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /transfer",
+            "1234567890",
+        );
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+        let scope = ["amount", "recipient"];
+
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let (proof, scope_hash) =
+            build_proof_v21_scoped(&client_secret, timestamp, binding, payload, &scope).unwrap();
+        let body_hash = hash_scoped_body(payload, &scope).unwrap();
+
+        let verify = |body_hash: &str, scope: &[&str]| {
+            verify_proof_v21_scoped_from_hash(
+                nonce,
+                context_id,
+                binding,
+                timestamp,
+                body_hash,
+                scope,
+                &scope_hash,
+                &proof,
+            )
+            .unwrap()
+        };
+        assert!(verify_proof_v21_scoped(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            &scope,
+            &scope_hash,
+            &proof,
+        )
+        .unwrap());
+        assert!(verify(&body_hash, &scope));
+        assert!(!verify(&hash_body(payload), &scope));
+        assert!(!verify(&body_hash, &["amount"]));
+        assert!(verify_proof_v21_scoped_from_hash(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            "nothex",
+            &scope,
+            &scope_hash,
+            &proof,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_edge_empty_string_vs_null() {
+        let empty = r#"{"x":"","y":1}"#;
+        let null = r#"{"x":null,"y":1}"#;
+
+        // Distinct by default
+        let strict = |payload, scope: &[&str]| hash_scoped_body(payload, scope).unwrap();
+        assert_ne!(strict(empty, &["x"]), strict(null, &["x"]));
+        assert_ne!(strict(empty, &[]), strict(null, &[]));
+
+        let lenient = ScopeOptions {
+            treat_empty_as_null: true,
+            ..Default::default()
+        };
+        let hash = |payload, scope: &[&str]| {
+            hash_scoped_body_with_options(payload, scope, &lenient).unwrap()
+        };
+        assert_eq!(hash(empty, &["x"]), hash(null, &["x"]));
+        assert_eq!(hash(empty, &[]), hash(null, &[]));
+        assert_eq!(
+            hash(r#"{"a":{"b":["",{"c":""}]}}"#, &["a"]),
+            hash(r#"{"a":{"b":[null,{"c":null}]}}"#, &["a"])
+        );
+        // Non-empty strings and missing fields are unaffected
+        assert_ne!(hash(r#"{"x":" "}"#, &["x"]), hash(null, &["x"]));
+        assert_ne!(hash(r#"{"y":1}"#, &["x"]), hash(null, &["x"]));
+    }
 
-/// Hash scoped payload for client-side use.
-pub fn hash_scoped_body(payload: &str, scope: &[&str]) -> Result<String, AshError> {
-    let json_payload: Value = serde_json::from_str(payload)
-        .map_err(|e| AshError::canonicalization_failed(&format!("Invalid JSON: {}", e)))?;
+    fn extract_pointers(payload: &str, scope: &[&str]) -> Result<Value, AshError> {
+        let payload: Value = serde_json::from_str(payload).unwrap();
+        let options = ScopeOptions {
+            syntax: ScopeSyntax::JsonPointer,
+            ..Default::default()
+        };
+        extract_scoped_fields_with_options(&payload, scope, &options)
+    }
+
+    #[test]
+    fn test_json_pointer_scope_matches_dotted() {
+        let payload = r#"{"user":{"name":"a","age":3},"items":[{"id":1},{"id":2}],"n":5}"#;
+        let value: Value = serde_json::from_str(payload).unwrap();
+
+        assert_eq!(
+            extract_pointers(payload, &["/user/name", "/items/1/id", "/missing"]).unwrap(),
+            extract_scoped_fields(&value, &["user.name", "items[1].id", "missing"]).unwrap()
+        );
+        // Out of range, non-canonical and non-numeric array tokens select nothing
+        for pointer in ["/items/5/id", "/items/01/id", "/items/-/id", "/n/x"] {
+            assert_eq!(
+                extract_pointers(payload, &[pointer]).unwrap(),
+                serde_json::json!({})
+            );
+        }
+    }
 
-    let scoped_payload = extract_scoped_fields(&json_payload, scope)?;
+    #[test]
+    fn test_json_pointer_scope_escapes() {
+        let payload = r#"{"a/b":1,"m~n":2,"~1":3,"x":4}"#;
+
+        assert_eq!(
+            extract_pointers(payload, &["/a~1b", "/m~0n", "/~01"]).unwrap(),
+            serde_json::json!({"a/b": 1, "m~n": 2, "~1": 3})
+        );
+        for bad in ["x", "", "/a~2b", "/m~"] {
+            let err = extract_pointers(payload, &[bad]).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::MalformedRequest, "{}", bad);
+        }
+    }
 
-    let canonical_scoped = serde_json::to_string(&scoped_payload)
-        .map_err(|e| AshError::canonicalization_failed(&format!("Failed to serialize: {}", e)))?;
+    #[test]
+    fn test_json_pointer_scope_reaches_dotted_keys() {
+        let payload = r#"{"a.b":1,"a":{"b":2}}"#;
+        let value: Value = serde_json::from_str(payload).unwrap();
+
+        // Dotted syntax can only reach the nested field
+        assert_eq!(
+            extract_scoped_fields(&value, &["a.b"]).unwrap(),
+            serde_json::json!({"a": {"b": 2}})
+        );
+        assert_eq!(
+            extract_pointers(payload, &["/a.b"]).unwrap(),
+            serde_json::json!({"a.b": 1})
+        );
+        assert_eq!(
+            extract_pointers(payload, &["/a/b"]).unwrap(),
+            serde_json::json!({"a": {"b": 2}})
+        );
+    }
 
-    Ok(hash_body(&canonical_scoped))
-}
+    #[test]
+    fn test_resolve_scope() {
+        let payload = r#"{"user":{"name":"a","tags":["x"]},"items":{"0":{"x":1}},"n":5}"#;
+        let scope = [
+            "user.name",
+            "user.tags[0]",
+            "user.email",
+            "user.tags[3]",
+            "items[0].x",
+            "n.value",
+        ];
+
+        let resolution = resolve_scope(payload, &scope).unwrap();
+        assert_eq!(resolution.present, vec!["user.name", "user.tags[0]"]);
+        assert_eq!(resolution.missing, vec!["user.email", "user.tags[3]"]);
+        assert_eq!(resolution.type_mismatch, vec!["items[0].x", "n.value"]);
+    }
 
-#[cfg(test)]
-mod tests_v22_scoping {
-    use super::*;
+    #[test]
+    fn test_resolve_scope_matches_extraction() {
+        let payload = r#"{"amount":100,"items":[{"id":1}]}"#;
+        let scope = ["amount", "items[0].id", "missing"];
+
+        let resolution = resolve_scope(payload, &scope).unwrap();
+        let extracted =
+            extract_scoped_fields(&serde_json::from_str(payload).unwrap(), &scope).unwrap();
+        let only_present: Vec<&str> = resolution.present.iter().map(String::as_str).collect();
+        assert_eq!(
+            extracted,
+            extract_scoped_fields(&serde_json::from_str(payload).unwrap(), &only_present).unwrap()
+        );
+        assert!(resolve_scope("{bad", &scope).is_err());
+    }
 
     #[test]
     fn test_build_verify_scoped_proof() {
@@ -676,6 +3441,163 @@ mod tests_v22_scoping {
         assert!(is_valid);
     }
 
+    #[test]
+    fn test_scoped_proof_scope_order_independent() {
+        let nonce = "test_nonce_12345";
+        let context_id = "ctx_abc123";
+        let binding = "POST /transfer";
+        let timestamp = "1234567890";
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let (proof, scope_hash) = build_proof_v21_scoped(
+            &client_secret,
+            timestamp,
+            binding,
+            payload,
+            &["amount", "recipient"],
+        )
+        .unwrap();
+
+        let (reordered_proof, reordered_hash) = build_proof_v21_scoped(
+            &client_secret,
+            timestamp,
+            binding,
+            payload,
+            &["recipient", "amount", "recipient"],
+        )
+        .unwrap();
+        assert_eq!(proof, reordered_proof);
+        assert_eq!(scope_hash, reordered_hash);
+
+        let is_valid = verify_proof_v21_scoped(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            &["recipient", "amount"],
+            &scope_hash,
+            &proof,
+        )
+        .unwrap();
+        assert!(is_valid);
+    }
+
+    #[test]
+    fn test_scoped_by_hash_agrees_with_full_verify() {
+        let (nonce, context_id, binding, timestamp) = (
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /transfer",
+            "1234567890",
+        );
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+        let scope = ["amount", "recipient"];
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let (proof, scope_hash) =
+            build_proof_v21_scoped(&client_secret, timestamp, binding, payload, &scope).unwrap();
+        let body_hash = hash_scoped_body(payload, &scope).unwrap();
+
+        let by_hash = |body_hash: &str, scope_hash: &str, proof: &str| {
+            verify_proof_v21_scoped_by_hash(
+                nonce, context_id, binding, timestamp, body_hash, scope_hash, proof,
+            )
+            .unwrap()
+        };
+        let full = |payload: &str, proof: &str| {
+            verify_proof_v21_scoped(
+                nonce,
+                context_id,
+                binding,
+                timestamp,
+                payload,
+                &scope,
+                &scope_hash,
+                proof,
+            )
+            .unwrap()
+        };
+
+        assert!(full(payload, &proof));
+        assert!(by_hash(&body_hash, &scope_hash, &proof));
+
+        let tampered = r#"{"amount":9999,"recipient":"user1","notes":"hi"}"#;
+        let tampered_hash = hash_scoped_body(tampered, &scope).unwrap();
+        assert!(!full(tampered, &proof));
+        assert!(!by_hash(&tampered_hash, &scope_hash, &proof));
+
+        let other_scope = self::scope_hash(&["amount"]);
+        assert!(!by_hash(&body_hash, &other_scope, &proof));
+
+        let err = verify_proof_v21_scoped_by_hash(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            "not-a-hash",
+            &scope_hash,
+            &proof,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+    }
+
+    #[test]
+    fn test_scope_hash_matches_scoped_proof() {
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+        let scope = ["recipient", "amount"];
+        let (_, returned) =
+            build_proof_v21_scoped("secret", "1234567890", "POST /transfer", payload, &scope)
+                .unwrap();
+
+        assert_eq!(scope_hash(&scope), returned);
+        assert_eq!(scope_hash(&["amount", "recipient", "amount"]), returned);
+        assert_eq!(
+            scope_hash(&["amount", "recipient"]),
+            hash_body("amount,recipient")
+        );
+        assert_ne!(scope_hash(&["amount"]), returned);
+    }
+
+    #[test]
+    fn test_hash_scoped_body_order_stable() {
+        let payload = r#"{"b":{"y":2,"x":1},"a":1,"c":3}"#;
+        assert_eq!(
+            hash_scoped_body(payload, &["b.x", "a", "b.y"]).unwrap(),
+            hash_scoped_body(payload, &["a", "b.y", "b.x", "a"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_scoped_proof_rejects_malformed_hex() {
+        let payload = r#"{"amount":1000}"#;
+        let scope = ["amount"];
+        let valid_hash = hash_body("x");
+
+        let verify = |scope_hash: &str, proof: &str| {
+            verify_proof_v21_scoped(
+                "nonce",
+                "ctx",
+                "POST /transfer",
+                "1",
+                payload,
+                &scope,
+                scope_hash,
+                proof,
+            )
+        };
+
+        for (scope_hash, proof) in [
+            (valid_hash.as_str(), "short"),
+            (valid_hash.as_str(), valid_hash.to_uppercase().as_str()),
+            ("zz", valid_hash.as_str()),
+        ] {
+            let err = verify(scope_hash, proof).unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+        }
+    }
+
     #[test]
     fn test_scoped_proof_ignores_unscoped_changes() {
         let nonce = "test_nonce_12345";
@@ -745,14 +3667,65 @@ mod tests_v22_scoping {
 
         assert!(!is_valid);
     }
+
+    #[test]
+    fn test_scoped_extraction_preserves_type() {
+        let string: Value = serde_json::from_str(r#"{"n":"123","x":1}"#).unwrap();
+        let number: Value = serde_json::from_str(r#"{"n":123,"x":1}"#).unwrap();
+
+        let scoped_string = extract_scoped_fields(&string, &["n"]).unwrap();
+        let scoped_number = extract_scoped_fields(&number, &["n"]).unwrap();
+
+        assert_eq!(scoped_string, serde_json::json!({"n": "123"}));
+        assert_eq!(scoped_number, serde_json::json!({"n": 123}));
+        assert_ne!(
+            hash_scoped_body(r#"{"n":"123"}"#, &["n"]).unwrap(),
+            hash_scoped_body(r#"{"n":123}"#, &["n"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_scoped_extraction_array_vs_object() {
+        let array: Value = serde_json::from_str(r#"{"a":[1]}"#).unwrap();
+        let object: Value = serde_json::from_str(r#"{"a":{"0":1}}"#).unwrap();
+
+        // Dotted segments only match object keys
+        assert_eq!(extract_scoped_fields(&object, &["a.0"]).unwrap(), object);
+        assert_eq!(
+            extract_scoped_fields(&array, &["a.0"]).unwrap(),
+            serde_json::json!({})
+        );
+
+        // Array elements are selected with bracket notation
+        assert_eq!(
+            extract_scoped_fields(&array, &["a[0]"]).unwrap(),
+            serde_json::json!({"a": 1})
+        );
+
+        let client_secret = derive_client_secret("nonce", "ctx", "POST /a");
+        let (array_proof, _) =
+            build_proof_v21_scoped(&client_secret, "1", "POST /a", r#"{"a":[1]}"#, &["a.0"])
+                .unwrap();
+        let (object_proof, _) =
+            build_proof_v21_scoped(&client_secret, "1", "POST /a", r#"{"a":{"0":1}}"#, &["a.0"])
+                .unwrap();
+        assert_ne!(array_proof, object_proof);
+    }
 }
 
 // =========================================================================
 // ASH v2.3 - Unified Proof Functions (Scoping + Chaining)
 // =========================================================================
 
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
 /// Result from unified proof generation.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Serializes as `{"proof": ..., "scopeHash": ..., "chainHash": ...}`,
+/// matching the object returned by the WASM bindings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UnifiedProofResult {
     /// The cryptographic proof.
     pub proof: String,
@@ -762,6 +3735,29 @@ pub struct UnifiedProofResult {
     pub chain_hash: String,
 }
 
+impl UnifiedProofResult {
+    /// Serialize to a JSON object string.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("UnifiedProofResult is always serializable")
+    }
+
+    /// Parse from a JSON object string.
+    pub fn from_json(input: &str) -> Result<Self, AshError> {
+        serde_json::from_str(input).map_err(|e| {
+            AshError::new(
+                AshErrorCode::MalformedRequest,
+                format!("Invalid proof result: {}", e),
+            )
+        })
+    }
+}
+
+impl fmt::Display for UnifiedProofResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_json())
+    }
+}
+
 /// Hash a proof for chaining purposes.
 ///
 /// Used to create chain links between sequential requests.
@@ -779,7 +3775,7 @@ pub fn hash_proof(proof: &str) -> String {
 ///
 /// Formula:
 /// ```text
-/// scopeHash  = scope.len() > 0 ? SHA256(scope.join(",")) : ""
+/// scopeHash  = scope.len() > 0 ? SHA256(sorted(dedupe(scope)).join(",")) : ""
 /// bodyHash   = SHA256(canonicalize(scopedPayload))
 /// chainHash  = previous_proof.is_some() ? SHA256(previous_proof) : ""
 /// proof      = HMAC-SHA256(clientSecret, timestamp|binding|bodyHash|scopeHash|chainHash)
@@ -796,7 +3792,7 @@ pub fn build_proof_v21_unified(
     let json_payload: Value = serde_json::from_str(payload)
         .map_err(|e| AshError::canonicalization_failed(&format!("Invalid JSON: {}", e)))?;
 
-    let scoped_payload = extract_scoped_fields(&json_payload, scope)?;
+    let scoped_payload = extract_scoped_fields(&json_payload, &normalize_scope(scope))?;
 
     let canonical_scoped = serde_json::to_string(&scoped_payload)
         .map_err(|e| AshError::canonicalization_failed(&format!("Failed to serialize: {}", e)))?;
@@ -807,7 +3803,7 @@ pub fn build_proof_v21_unified(
     let scope_hash = if scope.is_empty() {
         String::new()
     } else {
-        hash_body(&scope.join(","))
+        scope_hash(scope)
     };
 
     // Compute chain hash (empty string if no previous proof)
@@ -816,28 +3812,304 @@ pub fn build_proof_v21_unified(
         _ => String::new(),
     };
 
-    // Build proof message: timestamp|binding|bodyHash|scopeHash|chainHash
-    let message = format!(
-        "{}|{}|{}|{}|{}",
-        timestamp, binding, body_hash, scope_hash, chain_hash
+    let proof = unified_proof(
+        client_secret,
+        timestamp,
+        binding,
+        &body_hash,
+        &scope_hash,
+        &chain_hash,
+    );
+
+    Ok(UnifiedProofResult {
+        proof,
+        scope_hash,
+        chain_hash,
+    })
+}
+
+/// HMAC of the v2.3 message `timestamp|binding|bodyHash|scopeHash|chainHash`.
+fn unified_proof(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+    scope_hash: &str,
+    chain_hash: &str,
+) -> String {
+    let message = unified_message(timestamp, binding, body_hash, scope_hash, chain_hash);
+
+    let mut mac = HmacSha256Type::new_from_slice(client_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(message.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// The v2.3 HMAC message: `timestamp|binding|bodyHash|scopeHash|chainHash`.
+pub(crate) fn unified_message(
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+    scope_hash: &str,
+    chain_hash: &str,
+) -> String {
+    format!(
+        "{}|{}|{}|{}|{}",
+        timestamp, binding, body_hash, scope_hash, chain_hash
+    )
+}
+
+/// Verify unified v2.3 proof (server-side).
+///
+/// Validates proof with optional scoping and chaining.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_proof_v21_unified(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    payload: &str,
+    client_proof: &str,
+    scope: &[&str],
+    scope_hash: &str,
+    previous_proof: Option<&str>,
+    chain_hash: &str,
+) -> Result<bool, AshError> {
+    verify_unified_sorted(
+        nonce,
+        context_id,
+        binding,
+        timestamp,
+        UnifiedBody::Payload(payload),
+        client_proof,
+        &normalize_scope(scope),
+        scope_hash,
+        previous_proof,
+        chain_hash,
+    )
+}
+
+/// Verify unified v2.3 proof, failing with `IntegrityFailed` (server-side).
+///
+/// [`verify_proof_v21_unified`] with a single error channel: a proof that
+/// does not match is `Err(IntegrityFailed)`, and malformed inputs keep
+/// their own error code.
+#[allow(clippy::too_many_arguments)]
+pub fn ensure_proof_v21_unified(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    payload: &str,
+    client_proof: &str,
+    scope: &[&str],
+    scope_hash: &str,
+    previous_proof: Option<&str>,
+    chain_hash: &str,
+) -> Result<(), AshError> {
+    let valid = verify_proof_v21_unified(
+        nonce,
+        context_id,
+        binding,
+        timestamp,
+        payload,
+        client_proof,
+        scope,
+        scope_hash,
+        previous_proof,
+        chain_hash,
+    )?;
+    if valid {
+        Ok(())
+    } else {
+        Err(AshError::integrity_failed())
+    }
+}
+
+/// Verify unified v2.3 proof from a precomputed body hash (server-side).
+///
+/// Same result as [`verify_proof_v21_unified`] for callers that already
+/// hashed the payload (e.g. while logging the request), skipping the
+/// re-parse and re-scope. `body_hash` must be exactly what
+/// [`hash_scoped_body`] returns for the payload and `scope` (the scope may
+/// be empty); a hash from any other canonicalization fails verification.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_proof_v21_unified_from_hash(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+    scope: &[&str],
+    scope_hash: &str,
+    previous_proof: Option<&str>,
+    chain_hash: &str,
+) -> Result<bool, AshError> {
+    verify_unified_sorted(
+        nonce,
+        context_id,
+        binding,
+        timestamp,
+        UnifiedBody::Hash(body_hash),
+        client_proof,
+        &normalize_scope(scope),
+        scope_hash,
+        previous_proof,
+        chain_hash,
+    )
+}
+
+/// Verify unified v2.3 proof with a pre-sorted scope (server-side).
+///
+/// Same result as [`verify_proof_v21_unified`] for the same scope, without
+/// sorting and deduplicating it again.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_proof_v21_unified_presorted(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    payload: &str,
+    client_proof: &str,
+    scope: PreSortedScope<'_>,
+    scope_hash: &str,
+    previous_proof: Option<&str>,
+    chain_hash: &str,
+) -> Result<bool, AshError> {
+    verify_unified_sorted(
+        nonce,
+        context_id,
+        binding,
+        timestamp,
+        UnifiedBody::Payload(payload),
+        client_proof,
+        scope.as_slice(),
+        scope_hash,
+        previous_proof,
+        chain_hash,
+    )
+}
+
+/// What a unified verifier is given for the body.
+enum UnifiedBody<'a> {
+    /// Raw JSON payload, scoped and hashed during verification
+    Payload(&'a str),
+    /// Precomputed [`hash_scoped_body`] output
+    Hash(&'a str),
+}
+
+/// Shared body of the unified v2.3 verifiers, for a sorted and
+/// deduplicated scope.
+///
+/// The payload is only parsed once the scope and chain hashes match.
+#[allow(clippy::too_many_arguments)]
+fn verify_unified_sorted(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body: UnifiedBody<'_>,
+    client_proof: &str,
+    sorted: &[&str],
+    scope_hash: &str,
+    previous_proof: Option<&str>,
+    chain_hash: &str,
+) -> Result<bool, AshError> {
+    if !scope_presence_agrees(sorted, scope_hash) {
+        return Ok(false);
+    }
+    validate_unified_inputs(client_proof, sorted, scope_hash, previous_proof, chain_hash)?;
+    if let UnifiedBody::Hash(body_hash) = body {
+        validate_hex_digest("body_hash", body_hash)?;
+    }
+
+    // Empty when scoping is not used; presence was checked above
+    let expected_scope_hash = if sorted.is_empty() {
+        String::new()
+    } else {
+        hash_sorted_scope(sorted)
+    };
+    if !timing_safe_equal(expected_scope_hash.as_bytes(), scope_hash.as_bytes()) {
+        return Ok(false);
+    }
+
+    let expected_chain_hash = match previous_proof {
+        Some(prev) if !prev.is_empty() => hash_proof(prev),
+        _ => String::new(),
+    };
+    if !expected_chain_hash.is_empty()
+        && !timing_safe_equal(expected_chain_hash.as_bytes(), chain_hash.as_bytes())
+    {
+        return Ok(false);
+    }
+
+    let body_hash = match body {
+        UnifiedBody::Payload(payload) => {
+            hash_sorted_scoped_body(payload, sorted, &ScopeOptions::default())?
+        }
+        UnifiedBody::Hash(body_hash) => body_hash.to_string(),
+    };
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected_proof = unified_proof(
+        &client_secret,
+        timestamp,
+        binding,
+        &body_hash,
+        &expected_scope_hash,
+        &expected_chain_hash,
     );
 
-    let mut mac = HmacSha256Type::new_from_slice(client_secret.as_bytes())
-        .expect("HMAC can take key of any size");
-    mac.update(message.as_bytes());
-    let proof = hex::encode(mac.finalize().into_bytes());
+    Ok(timing_safe_equal(
+        expected_proof.as_bytes(),
+        client_proof.as_bytes(),
+    ))
+}
 
-    Ok(UnifiedProofResult {
-        proof,
-        scope_hash,
-        chain_hash,
-    })
+/// Build a v2.3 proof whose construction depends on the security mode.
+///
+/// - `Minimal`: the binding is left out of the HMAC message, which becomes
+///   `timestamp||bodyHash|scopeHash|chainHash`. The client secret is still
+///   derived from the binding.
+/// - `Balanced`: identical to [`build_proof_v21_unified`].
+/// - `Strict`: like `Balanced`, but a non-empty scope is required;
+///   full-payload proofs are rejected.
+///
+/// # Errors
+///
+/// Returns `ModeViolation` if `mode` is `Strict` and `scope` is empty,
+/// plus any error from [`build_proof_v21_unified`].
+pub fn build_proof_v21_moded(
+    mode: AshMode,
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    payload: &str,
+    scope: &[&str],
+    previous_proof: Option<&str>,
+) -> Result<UnifiedProofResult, AshError> {
+    let binding = mode_binding(mode, binding, scope)?;
+    build_proof_v21_unified(
+        client_secret,
+        timestamp,
+        binding,
+        payload,
+        scope,
+        previous_proof,
+    )
 }
 
-/// Verify unified v2.3 proof (server-side).
+/// Verify a v2.3 proof built with [`build_proof_v21_moded`] (server-side).
 ///
-/// Validates proof with optional scoping and chaining.
-pub fn verify_proof_v21_unified(
+/// `mode` should come from the stored context, not from the client.
+///
+/// # Errors
+///
+/// Returns `ModeViolation` if `mode` is `Strict` and `scope` is empty,
+/// plus any error from [`verify_proof_v21_unified`].
+#[allow(clippy::too_many_arguments)]
+pub fn verify_proof_v21_moded(
+    mode: AshMode,
     nonce: &str,
     context_id: &str,
     binding: &str,
@@ -849,43 +4121,280 @@ pub fn verify_proof_v21_unified(
     previous_proof: Option<&str>,
     chain_hash: &str,
 ) -> Result<bool, AshError> {
-    // Validate scope hash if scoping is used
-    if !scope.is_empty() {
-        let expected_scope_hash = hash_body(&scope.join(","));
-        if !timing_safe_equal(expected_scope_hash.as_bytes(), scope_hash.as_bytes()) {
-            return Ok(false);
-        }
+    let message_binding = mode_binding(mode, binding, scope)?;
+    if !scope_presence_agrees(scope, scope_hash) {
+        return Ok(false);
     }
+    validate_unified_inputs(client_proof, scope, scope_hash, previous_proof, chain_hash)?;
 
-    // Validate chain hash if chaining is used
-    if let Some(prev) = previous_proof {
-        if !prev.is_empty() {
-            let expected_chain_hash = hash_proof(prev);
-            if !timing_safe_equal(expected_chain_hash.as_bytes(), chain_hash.as_bytes()) {
-                return Ok(false);
-            }
+    // Validate scope and chain hashes exactly as the unified verifier does
+    if !scope.is_empty()
+        && !timing_safe_equal(self::scope_hash(scope).as_bytes(), scope_hash.as_bytes())
+    {
+        return Ok(false);
+    }
+    if let Some(prev) = previous_proof.filter(|p| !p.is_empty()) {
+        if !timing_safe_equal(hash_proof(prev).as_bytes(), chain_hash.as_bytes()) {
+            return Ok(false);
         }
     }
 
-    // Derive client secret and compute expected proof
+    // Secret is always derived from the real binding
     let client_secret = derive_client_secret(nonce, context_id, binding);
 
     let result = build_proof_v21_unified(
         &client_secret,
         timestamp,
-        binding,
+        message_binding,
         payload,
         scope,
         previous_proof,
     )?;
 
-    Ok(timing_safe_equal(result.proof.as_bytes(), client_proof.as_bytes()))
+    Ok(timing_safe_equal(
+        result.proof.as_bytes(),
+        client_proof.as_bytes(),
+    ))
+}
+
+/// Result of verifying an ordered sequence of chained proofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainVerifyResult {
+    /// Number of leading links that verified.
+    pub verified_links: usize,
+    /// Index of the first link that failed, or `None` if the whole chain verified.
+    pub broken_link: Option<usize>,
+}
+
+impl ChainVerifyResult {
+    /// Whether every link in the chain verified.
+    pub fn is_valid(&self) -> bool {
+        self.broken_link.is_none()
+    }
+}
+
+/// Verify an ordered sequence of unscoped v2.3 proofs (server-side).
+///
+/// Link `i` is verified as a unified proof over `bindings[i]`,
+/// `timestamps[i]` and `payloads[i]`, chained to `proofs[i - 1]`. The first
+/// link must have no chain. Verification stops at the first broken link,
+/// so a reordered, altered or truncated chain reports where it diverges.
+///
+/// # Errors
+///
+/// Returns `MalformedRequest` if the slices differ in length or a proof
+/// is not a 64-char hex digest, plus any canonicalization error from
+/// [`verify_proof_v21_unified`].
+pub fn verify_proof_chain(
+    nonce: &str,
+    context_id: &str,
+    bindings: &[&str],
+    timestamps: &[&str],
+    payloads: &[&str],
+    proofs: &[&str],
+) -> Result<ChainVerifyResult, AshError> {
+    let len = proofs.len();
+    if bindings.len() != len || timestamps.len() != len || payloads.len() != len {
+        return Err(AshError::new(
+            AshErrorCode::MalformedRequest,
+            "Chain bindings, timestamps, payloads and proofs must have the same length",
+        ));
+    }
+
+    for i in 0..len {
+        let previous_proof = i.checked_sub(1).map(|prev| proofs[prev]);
+        let chain_hash = previous_proof.map(hash_proof).unwrap_or_default();
+
+        let is_valid = verify_proof_v21_unified(
+            nonce,
+            context_id,
+            bindings[i],
+            timestamps[i],
+            payloads[i],
+            proofs[i],
+            &[],
+            "",
+            previous_proof,
+            &chain_hash,
+        )?;
+        if !is_valid {
+            return Ok(ChainVerifyResult {
+                verified_links: i,
+                broken_link: Some(i),
+            });
+        }
+    }
+
+    Ok(ChainVerifyResult {
+        verified_links: len,
+        broken_link: None,
+    })
+}
+
+/// Check the hex format of the inputs that scoping and chaining make required.
+fn validate_unified_inputs(
+    client_proof: &str,
+    scope: &[&str],
+    scope_hash: &str,
+    previous_proof: Option<&str>,
+    chain_hash: &str,
+) -> Result<(), AshError> {
+    validate_hex_digest("client_proof", client_proof)?;
+    if !scope.is_empty() {
+        validate_hex_digest("scope_hash", scope_hash)?;
+    }
+    if previous_proof.is_some_and(|p| !p.is_empty()) {
+        validate_hex_digest("chain_hash", chain_hash)?;
+    }
+    Ok(())
+}
+
+/// Scope list and scope hash must be present together.
+///
+/// An empty scope skips the scope-hash check, so a `scope_hash` sent with
+/// no scope (or a scope with no hash) would otherwise be silently ignored.
+fn scope_presence_agrees(scope: &[&str], scope_hash: &str) -> bool {
+    scope.is_empty() == scope_hash.is_empty()
+}
+
+/// Apply mode rules and return the binding to place in the HMAC message.
+fn mode_binding<'a>(mode: AshMode, binding: &'a str, scope: &[&str]) -> Result<&'a str, AshError> {
+    match mode {
+        AshMode::Minimal => Ok(""),
+        AshMode::Balanced => Ok(binding),
+        AshMode::Strict if scope.is_empty() => Err(AshError::new(
+            AshErrorCode::ModeViolation,
+            "Strict mode requires a non-empty scope",
+        )),
+        AshMode::Strict => Ok(binding),
+    }
 }
 
 #[cfg(test)]
 mod tests_v23_unified {
     use super::*;
 
+    #[test]
+    fn test_ensure_proof_v21_unified() {
+        let (nonce, context_id, binding, timestamp) = (
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /transfer",
+            "1234567890",
+        );
+        let payload = r#"{"amount":1000,"recipient":"user1"}"#;
+        let scope = ["amount"];
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let result =
+            build_proof_v21_unified(&client_secret, timestamp, binding, payload, &scope, None)
+                .unwrap();
+
+        assert!(ensure_proof_v21_unified(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            &result.proof,
+            &scope,
+            &result.scope_hash,
+            None,
+            "",
+        )
+        .is_ok());
+
+        let tampered = r#"{"amount":9999,"recipient":"user1"}"#;
+        let err = ensure_proof_v21_unified(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            tampered,
+            &result.proof,
+            &scope,
+            &result.scope_hash,
+            None,
+            "",
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::IntegrityFailed);
+
+        // Malformed inputs keep their own code
+        let err = ensure_proof_v21_unified(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            "not json",
+            &result.proof,
+            &scope,
+            &result.scope_hash,
+            None,
+            "",
+        )
+        .unwrap_err();
+        assert_ne!(err.code(), AshErrorCode::IntegrityFailed);
+    }
+
+    #[test]
+    fn test_unified_from_hash_agrees_with_payload() {
+        let (nonce, context_id, binding, timestamp) = (
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /transfer",
+            "1234567890",
+        );
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+        let previous = "a".repeat(64);
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+
+        let cases: [(&[&str], Option<&str>); 4] = [
+            (&[], None),
+            (&["amount", "recipient"], None),
+            (&[], Some(&previous)),
+            (&["recipient", "amount"], Some(&previous)),
+        ];
+        for (scope, prev) in cases {
+            let result =
+                build_proof_v21_unified(&client_secret, timestamp, binding, payload, scope, prev)
+                    .unwrap();
+            let body_hash = hash_scoped_body(payload, scope).unwrap();
+
+            let verify = |body_hash: &str| {
+                verify_proof_v21_unified_from_hash(
+                    nonce,
+                    context_id,
+                    binding,
+                    timestamp,
+                    body_hash,
+                    &result.proof,
+                    scope,
+                    &result.scope_hash,
+                    prev,
+                    &result.chain_hash,
+                )
+                .unwrap()
+            };
+            let from_payload = verify_proof_v21_unified(
+                nonce,
+                context_id,
+                binding,
+                timestamp,
+                payload,
+                &result.proof,
+                scope,
+                &result.scope_hash,
+                prev,
+                &result.chain_hash,
+            )
+            .unwrap();
+
+            assert!(from_payload);
+            assert_eq!(verify(&body_hash), from_payload);
+            assert!(!verify(&hash_body("{}")));
+        }
+    }
+
     #[test]
     fn test_unified_basic() {
         let nonce = "test_nonce_12345";
@@ -963,6 +4472,154 @@ mod tests_v23_unified {
         assert!(is_valid);
     }
 
+    #[test]
+    fn test_unified_presorted_agrees_with_unsorted() {
+        let (nonce, context_id, binding, timestamp) = (
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /transfer",
+            "1234567890",
+        );
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+        let previous = "a".repeat(64);
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+
+        let cases: [(&[&str], &[&str]); 2] = [
+            (&[], &[]),
+            (&["recipient", "amount"], &["amount", "recipient"]),
+        ];
+        for (scope, sorted) in cases {
+            let result = build_proof_v21_unified(
+                &client_secret,
+                timestamp,
+                binding,
+                payload,
+                scope,
+                Some(&previous),
+            )
+            .unwrap();
+
+            let presorted = verify_proof_v21_unified_presorted(
+                nonce,
+                context_id,
+                binding,
+                timestamp,
+                payload,
+                &result.proof,
+                PreSortedScope::new(sorted),
+                &result.scope_hash,
+                Some(&previous),
+                &result.chain_hash,
+            )
+            .unwrap();
+            let unsorted = verify_proof_v21_unified(
+                nonce,
+                context_id,
+                binding,
+                timestamp,
+                payload,
+                &result.proof,
+                scope,
+                &result.scope_hash,
+                Some(&previous),
+                &result.chain_hash,
+            )
+            .unwrap();
+
+            assert!(presorted);
+            assert_eq!(presorted, unsorted);
+        }
+    }
+
+    #[test]
+    fn test_unified_rejects_scope_hash_without_scope() {
+        let (nonce, context_id, binding, timestamp) = (
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /transfer",
+            "1234567890",
+        );
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let full = build_proof_v21_unified(&client_secret, timestamp, binding, payload, &[], None)
+            .unwrap();
+        let stray_hash = scope_hash(&["amount"]);
+
+        let result = verify_proof_v21_unified(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            &full.proof,
+            &[],
+            &stray_hash,
+            None,
+            "",
+        );
+        assert!(matches!(result, Ok(false)));
+
+        let body_hash = hash_scoped_body(payload, &[]).unwrap();
+        let result = verify_proof_v21_unified_from_hash(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            &body_hash,
+            &full.proof,
+            &[],
+            &stray_hash,
+            None,
+            "",
+        );
+        assert!(matches!(result, Ok(false)));
+    }
+
+    #[test]
+    fn test_unified_rejects_scope_without_scope_hash() {
+        let (nonce, context_id, binding, timestamp) = (
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /transfer",
+            "1234567890",
+        );
+        let payload = r#"{"amount":1000,"recipient":"user1","notes":"hi"}"#;
+        let scope = ["amount", "recipient"];
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let scoped =
+            build_proof_v21_unified(&client_secret, timestamp, binding, payload, &scope, None)
+                .unwrap();
+
+        let result = verify_proof_v21_unified(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            &scoped.proof,
+            &scope,
+            "",
+            None,
+            "",
+        );
+        assert!(matches!(result, Ok(false)));
+
+        let result = verify_proof_v21_moded(
+            AshMode::Balanced,
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            &scoped.proof,
+            &scope,
+            "",
+            None,
+            "",
+        );
+        assert!(matches!(result, Ok(false)));
+    }
+
     #[test]
     fn test_unified_chained_only() {
         let nonce = "test_nonce_12345";
@@ -995,22 +4652,114 @@ mod tests_v23_unified {
             &result.proof,
             &[],
             "",
-            Some(previous_proof),
-            &result.chain_hash,
-        ).unwrap();
-
-        assert!(is_valid);
+            Some(previous_proof),
+            &result.chain_hash,
+        ).unwrap();
+
+        assert!(is_valid);
+    }
+
+    #[test]
+    fn test_unified_full() {
+        let nonce = "test_nonce_12345";
+        let context_id = "ctx_abc123";
+        let binding = "POST /payment";
+        let timestamp = "1234567890";
+        let payload = r#"{"amount":500,"currency":"USD","notes":"tip"}"#;
+        let scope = vec!["amount", "currency"];
+        let previous_proof = "checkout_proof_xyz";
+
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let result = build_proof_v21_unified(
+            &client_secret,
+            timestamp,
+            binding,
+            payload,
+            &scope,
+            Some(previous_proof),
+        ).unwrap();
+
+        assert!(!result.proof.is_empty());
+        assert!(!result.scope_hash.is_empty());
+        assert!(!result.chain_hash.is_empty());
+
+        let is_valid = verify_proof_v21_unified(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            &result.proof,
+            &scope,
+            &result.scope_hash,
+            Some(previous_proof),
+            &result.chain_hash,
+        ).unwrap();
+
+        assert!(is_valid);
+    }
+
+    #[test]
+    fn test_unified_rejects_malformed_hex() {
+        let payload = r#"{"amount":500}"#;
+        let valid_hash = hash_body("x");
+
+        // Malformed chain hash when chaining is used
+        let err = verify_proof_v21_unified(
+            "nonce",
+            "ctx",
+            "POST /payment",
+            "1",
+            payload,
+            &valid_hash,
+            &[],
+            "",
+            Some("previous"),
+            "not-hex",
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+
+        // Malformed scope hash when scoping is used
+        let err = verify_proof_v21_unified(
+            "nonce",
+            "ctx",
+            "POST /payment",
+            "1",
+            payload,
+            &valid_hash,
+            &["amount"],
+            &valid_hash[..10],
+            None,
+            "",
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+
+        // Empty hashes are fine when the feature is unused
+        let is_valid = verify_proof_v21_unified(
+            "nonce",
+            "ctx",
+            "POST /payment",
+            "1",
+            payload,
+            &valid_hash,
+            &[],
+            "",
+            None,
+            "",
+        )
+        .unwrap();
+        assert!(!is_valid);
     }
 
     #[test]
-    fn test_unified_full() {
+    fn test_unified_scope_order_independent() {
         let nonce = "test_nonce_12345";
         let context_id = "ctx_abc123";
         let binding = "POST /payment";
         let timestamp = "1234567890";
         let payload = r#"{"amount":500,"currency":"USD","notes":"tip"}"#;
-        let scope = vec!["amount", "currency"];
-        let previous_proof = "checkout_proof_xyz";
 
         let client_secret = derive_client_secret(nonce, context_id, binding);
         let result = build_proof_v21_unified(
@@ -1018,13 +4767,10 @@ mod tests_v23_unified {
             timestamp,
             binding,
             payload,
-            &scope,
-            Some(previous_proof),
-        ).unwrap();
-
-        assert!(!result.proof.is_empty());
-        assert!(!result.scope_hash.is_empty());
-        assert!(!result.chain_hash.is_empty());
+            &["currency", "amount"],
+            None,
+        )
+        .unwrap();
 
         let is_valid = verify_proof_v21_unified(
             nonce,
@@ -1033,11 +4779,12 @@ mod tests_v23_unified {
             timestamp,
             payload,
             &result.proof,
-            &scope,
+            &["amount", "currency"],
             &result.scope_hash,
-            Some(previous_proof),
-            &result.chain_hash,
-        ).unwrap();
+            None,
+            "",
+        )
+        .unwrap();
 
         assert!(is_valid);
     }
@@ -1115,6 +4862,229 @@ mod tests_v23_unified {
         assert!(!is_valid);
     }
 
+    #[test]
+    fn test_moded_strict_rejects_empty_scope() {
+        let client_secret = derive_client_secret("nonce", "ctx", "POST /transfer");
+        let err = build_proof_v21_moded(
+            AshMode::Strict,
+            &client_secret,
+            "1234567890",
+            "POST /transfer",
+            r#"{"amount":1}"#,
+            &[],
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::ModeViolation);
+
+        let err = verify_proof_v21_moded(
+            AshMode::Strict,
+            "nonce",
+            "ctx",
+            "POST /transfer",
+            "1234567890",
+            r#"{"amount":1}"#,
+            "proof",
+            &[],
+            "",
+            None,
+            "",
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::ModeViolation);
+    }
+
+    #[test]
+    fn test_moded_balanced_matches_unified() {
+        let client_secret = derive_client_secret("nonce", "ctx", "POST /transfer");
+        let payload = r#"{"amount":1}"#;
+
+        let moded = build_proof_v21_moded(
+            AshMode::Balanced,
+            &client_secret,
+            "1234567890",
+            "POST /transfer",
+            payload,
+            &[],
+            None,
+        )
+        .unwrap();
+        let unified = build_proof_v21_unified(
+            &client_secret,
+            "1234567890",
+            "POST /transfer",
+            payload,
+            &[],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(moded, unified);
+    }
+
+    #[test]
+    fn test_moded_modes_round_trip_and_differ() {
+        let nonce = "test_nonce_12345";
+        let context_id = "ctx_abc123";
+        let binding = "POST /transfer";
+        let timestamp = "1234567890";
+        let payload = r#"{"amount":1000,"recipient":"user1"}"#;
+        let scope = ["amount"];
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+
+        let mut proofs = Vec::new();
+        for mode in [AshMode::Minimal, AshMode::Balanced, AshMode::Strict] {
+            let result = build_proof_v21_moded(
+                mode,
+                &client_secret,
+                timestamp,
+                binding,
+                payload,
+                &scope,
+                None,
+            )
+            .unwrap();
+
+            let is_valid = verify_proof_v21_moded(
+                mode,
+                nonce,
+                context_id,
+                binding,
+                timestamp,
+                payload,
+                &result.proof,
+                &scope,
+                &result.scope_hash,
+                None,
+                "",
+            )
+            .unwrap();
+            assert!(is_valid);
+            proofs.push(result.proof);
+        }
+
+        // Minimal drops the binding from the message; Balanced and Strict agree
+        assert_ne!(proofs[0], proofs[1]);
+        assert_eq!(proofs[1], proofs[2]);
+    }
+
+    const CHAIN_NONCE: &str = "test_nonce_12345";
+    const CHAIN_CONTEXT: &str = "ctx_abc123";
+    const CHAIN_BINDINGS: [&str; 3] = ["POST /cart", "POST /checkout", "POST /pay"];
+    const CHAIN_TIMESTAMPS: [&str; 3] = ["1000", "2000", "3000"];
+    const CHAIN_PAYLOADS: [&str; 3] = [r#"{"item":1}"#, r#"{"total":10}"#, r#"{"card":"x"}"#];
+
+    fn build_chain() -> Vec<String> {
+        let mut proofs: Vec<String> = Vec::new();
+        for i in 0..3 {
+            let secret = derive_client_secret(CHAIN_NONCE, CHAIN_CONTEXT, CHAIN_BINDINGS[i]);
+            let result = build_proof_v21_unified(
+                &secret,
+                CHAIN_TIMESTAMPS[i],
+                CHAIN_BINDINGS[i],
+                CHAIN_PAYLOADS[i],
+                &[],
+                proofs.last().map(String::as_str),
+            )
+            .unwrap();
+            proofs.push(result.proof);
+        }
+        proofs
+    }
+
+    #[test]
+    fn test_verify_proof_chain_valid() {
+        let proofs = build_chain();
+        let proofs: Vec<&str> = proofs.iter().map(String::as_str).collect();
+
+        let result = verify_proof_chain(
+            CHAIN_NONCE,
+            CHAIN_CONTEXT,
+            &CHAIN_BINDINGS,
+            &CHAIN_TIMESTAMPS,
+            &CHAIN_PAYLOADS,
+            &proofs,
+        )
+        .unwrap();
+
+        assert!(result.is_valid());
+        assert_eq!(result.verified_links, 3);
+    }
+
+    #[test]
+    fn test_verify_proof_chain_swapped_middle_link() {
+        let proofs = build_chain();
+        let other = build_proof_v21_unified(
+            &derive_client_secret(CHAIN_NONCE, CHAIN_CONTEXT, CHAIN_BINDINGS[1]),
+            CHAIN_TIMESTAMPS[1],
+            CHAIN_BINDINGS[1],
+            r#"{"total":99}"#,
+            &[],
+            Some(&proofs[0]),
+        )
+        .unwrap();
+        let proofs = [proofs[0].as_str(), other.proof.as_str(), proofs[2].as_str()];
+
+        let result = verify_proof_chain(
+            CHAIN_NONCE,
+            CHAIN_CONTEXT,
+            &CHAIN_BINDINGS,
+            &CHAIN_TIMESTAMPS,
+            &CHAIN_PAYLOADS,
+            &proofs,
+        )
+        .unwrap();
+
+        assert!(!result.is_valid());
+        assert_eq!(result.broken_link, Some(1));
+        assert_eq!(result.verified_links, 1);
+    }
+
+    #[test]
+    fn test_verify_proof_chain_truncated() {
+        let proofs = build_chain();
+        let tail: Vec<&str> = proofs[1..].iter().map(String::as_str).collect();
+
+        // Dropping the first link leaves the new head chained to a missing proof
+        let result = verify_proof_chain(
+            CHAIN_NONCE,
+            CHAIN_CONTEXT,
+            &CHAIN_BINDINGS[1..],
+            &CHAIN_TIMESTAMPS[1..],
+            &CHAIN_PAYLOADS[1..],
+            &tail,
+        )
+        .unwrap();
+        assert_eq!(result.broken_link, Some(0));
+
+        // Mismatched input lengths are malformed
+        let err = verify_proof_chain(
+            CHAIN_NONCE,
+            CHAIN_CONTEXT,
+            &CHAIN_BINDINGS,
+            &CHAIN_TIMESTAMPS,
+            &CHAIN_PAYLOADS,
+            &tail,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), AshErrorCode::MalformedRequest);
+    }
+
+    #[test]
+    fn test_unified_result_serde_round_trip() {
+        let result = UnifiedProofResult {
+            proof: "abc".to_string(),
+            scope_hash: "def".to_string(),
+            chain_hash: String::new(),
+        };
+
+        let json = result.to_json();
+        assert_eq!(json, r#"{"proof":"abc","scopeHash":"def","chainHash":""}"#);
+        assert_eq!(result.to_string(), json);
+        assert_eq!(UnifiedProofResult::from_json(&json).unwrap(), result);
+        assert!(UnifiedProofResult::from_json(r#"{"proof":"abc"}"#).is_err());
+    }
+
     #[test]
     fn test_hash_proof() {
         let proof = "test_proof_123";
@@ -1125,3 +5095,408 @@ mod tests_v23_unified {
         assert_eq!(hash1.len(), 64); // SHA-256 = 64 hex chars
     }
 }
+
+// =========================================================================
+// ASH v2.4 - Length-Prefixed Proof Message
+// =========================================================================
+
+/// ASH v2.4 domain tag, the first field of every v2.4 proof message.
+const ASH_VERSION_V24: &str = "ASHv2.4";
+
+/// Build v2.4 proof (client-side).
+///
+/// Same inputs as [`build_proof_v21`], but the HMAC message is
+/// length-prefixed instead of `|`-joined, so a timestamp or binding
+/// containing `|` cannot collide with a different split of the same bytes.
+/// v2.1 proofs keep their original message and still verify with
+/// [`verify_proof_v21`]; send v2.4 proofs as `ASH2.4` envelopes.
+///
+/// Formula: proof = HMAC-SHA256(clientSecret,
+/// "ASHv2.4|" + len(timestamp) + ":" + timestamp + "|" + len(binding) + ":" + binding + "|"
+/// + len(bodyHash) + ":" + bodyHash + "|")
+pub fn build_proof_v24(
+    client_secret: &str,
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+) -> String {
+    let message = framed_message(ASH_VERSION_V24, &[timestamp, binding, body_hash]);
+    let mut mac = HmacSha256Type::new_from_slice(client_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(message.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Verify v2.4 proof (server-side).
+pub fn verify_proof_v24(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> bool {
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    let expected_proof = build_proof_v24(&client_secret, timestamp, binding, body_hash);
+    timing_safe_equal(expected_proof.as_bytes(), client_proof.as_bytes())
+}
+
+#[cfg(test)]
+mod tests_v24_framing {
+    use super::*;
+
+    #[test]
+    fn test_v24_round_trip() {
+        let client_secret = derive_client_secret("test_nonce_12345", "ctx_abc123", "POST /api");
+        let body_hash = hash_body("{}");
+        let proof = build_proof_v24(&client_secret, "1234567890", "POST /api", &body_hash);
+
+        assert_eq!(proof.len(), 64);
+        assert!(verify_proof_v24(
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /api",
+            "1234567890",
+            &body_hash,
+            &proof,
+        ));
+        assert!(!verify_proof_v24(
+            "test_nonce_12345",
+            "ctx_abc123",
+            "POST /api",
+            "1234567891",
+            &body_hash,
+            &proof,
+        ));
+    }
+
+    #[test]
+    fn test_v24_field_splits_do_not_collide() {
+        let secret = "secret";
+        let body_hash = hash_body("{}");
+
+        // The same bytes split differently collide under the v2.1 message
+        assert_eq!(
+            build_proof_v21(secret, "1700000000|POST", "/api", &body_hash),
+            build_proof_v21(secret, "1700000000", "POST|/api", &body_hash),
+        );
+        assert_ne!(
+            build_proof_v24(secret, "1700000000|POST", "/api", &body_hash),
+            build_proof_v24(secret, "1700000000", "POST|/api", &body_hash),
+        );
+    }
+
+    #[test]
+    fn test_v24_differs_from_v21() {
+        let body_hash = hash_body("{}");
+        let v21 = build_proof_v21("secret", "1234567890", "POST /api", &body_hash);
+        let v24 = build_proof_v24("secret", "1234567890", "POST /api", &body_hash);
+
+        assert_ne!(v21, v24);
+    }
+}
+
+// =========================================================================
+// Proof Envelope (Self-Describing Version Header)
+// =========================================================================
+
+/// Protocol version carried in a proof envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProofVersion {
+    /// v2.1 basic proof (`build_proof_v21`).
+    V21,
+    /// v2.2 scoped proof (`build_proof_v21_scoped`).
+    V22,
+    /// v2.3 unified proof (`build_proof_v21_unified`).
+    V23,
+    /// v2.4 length-prefixed proof (`build_proof_v24`).
+    V24,
+}
+
+impl ProofVersion {
+    /// Get the envelope tag for this version.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProofVersion::V21 => "ASH2.1",
+            ProofVersion::V22 => "ASH2.2",
+            ProofVersion::V23 => "ASH2.3",
+            ProofVersion::V24 => "ASH2.4",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "ASH2.1" => Some(ProofVersion::V21),
+            "ASH2.2" => Some(ProofVersion::V22),
+            "ASH2.3" => Some(ProofVersion::V23),
+            "ASH2.4" => Some(ProofVersion::V24),
+            _ => None,
+        }
+    }
+}
+
+/// Wrap a raw proof in a self-describing envelope.
+///
+/// Format: `<tag>:<proof>`, e.g. `ASH2.3:3f9a...`
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::{encode_proof_envelope, decode_proof_envelope, ProofVersion};
+///
+/// let envelope = encode_proof_envelope(ProofVersion::V23, "abc123");
+/// assert_eq!(envelope, "ASH2.3:abc123");
+///
+/// let (version, proof) = decode_proof_envelope(&envelope).unwrap();
+/// assert_eq!(version, ProofVersion::V23);
+/// assert_eq!(proof, "abc123");
+/// ```
+pub fn encode_proof_envelope(version: ProofVersion, proof: &str) -> String {
+    format!("{}:{}", version.as_str(), proof)
+}
+
+/// Split a proof envelope into its version and raw proof.
+///
+/// # Errors
+///
+/// Returns `InvalidProofFormat` if the envelope has no header, an unknown
+/// version tag, or an empty proof.
+pub fn decode_proof_envelope(envelope: &str) -> Result<(ProofVersion, &str), AshError> {
+    let (tag, proof) = envelope
+        .split_once(':')
+        .ok_or_else(|| AshError::invalid_proof_format("missing version header"))?;
+
+    let version = ProofVersion::from_tag(tag)
+        .ok_or_else(|| AshError::invalid_proof_format("unsupported version"))?;
+
+    if proof.is_empty() {
+        return Err(AshError::invalid_proof_format("empty proof"));
+    }
+
+    Ok((version, proof))
+}
+
+/// Verify an enveloped proof, dispatching on its version header (server-side).
+///
+/// - `ASH2.1`: `payload` must already be canonical; it is hashed and
+///   checked with `verify_proof_v21`. Scope and chain arguments are ignored.
+/// - `ASH2.2`: checked with `verify_proof_v21_scoped`. Chain arguments are ignored.
+/// - `ASH2.3`: checked with `verify_proof_v21_unified`.
+/// - `ASH2.4`: like `ASH2.1`, checked with `verify_proof_v24`.
+///
+/// # Errors
+///
+/// Returns `InvalidProofFormat` if the envelope cannot be decoded, or any
+/// error raised by the underlying verifier.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_auto(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    payload: &str,
+    envelope: &str,
+    scope: &[&str],
+    scope_hash: &str,
+    previous_proof: Option<&str>,
+    chain_hash: &str,
+) -> Result<bool, AshError> {
+    let (version, client_proof) = decode_proof_envelope(envelope)?;
+
+    match version {
+        ProofVersion::V21 => verify_proof_v21_checked(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            &hash_body(payload),
+            client_proof,
+        ),
+        ProofVersion::V22 => verify_proof_v21_scoped(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            scope,
+            scope_hash,
+            client_proof,
+        ),
+        ProofVersion::V23 => verify_proof_v21_unified(
+            nonce,
+            context_id,
+            binding,
+            timestamp,
+            payload,
+            client_proof,
+            scope,
+            scope_hash,
+            previous_proof,
+            chain_hash,
+        ),
+        ProofVersion::V24 => {
+            parse_timestamp_ms(timestamp)?;
+            validate_hex_digest("client_proof", client_proof)?;
+            Ok(verify_proof_v24(
+                nonce,
+                context_id,
+                binding,
+                timestamp,
+                &hash_body(payload),
+                client_proof,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_envelope {
+    use super::*;
+
+    const NONCE: &str = "test_nonce_12345";
+    const CONTEXT_ID: &str = "ctx_abc123";
+    const BINDING: &str = "POST /transfer";
+    const TIMESTAMP: &str = "1234567890";
+    const PAYLOAD: &str = r#"{"amount":1000,"recipient":"user1"}"#;
+
+    #[test]
+    fn test_envelope_round_trip() {
+        for version in [
+            ProofVersion::V21,
+            ProofVersion::V22,
+            ProofVersion::V23,
+            ProofVersion::V24,
+        ] {
+            let envelope = encode_proof_envelope(version, "deadbeef");
+            let (decoded, proof) = decode_proof_envelope(&envelope).unwrap();
+            assert_eq!(decoded, version);
+            assert_eq!(proof, "deadbeef");
+        }
+    }
+
+    #[test]
+    fn test_envelope_rejects_malformed() {
+        assert!(decode_proof_envelope("deadbeef").is_err());
+        assert!(decode_proof_envelope("ASH9.9:deadbeef").is_err());
+        assert!(decode_proof_envelope("ASH2.3:").is_err());
+    }
+
+    #[test]
+    fn test_verify_auto_dispatches_by_version() {
+        let client_secret = derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+
+        let v21 = build_proof_v21(&client_secret, TIMESTAMP, BINDING, &hash_body(PAYLOAD));
+        let envelope = encode_proof_envelope(ProofVersion::V21, &v21);
+        assert!(verify_auto(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            TIMESTAMP,
+            PAYLOAD,
+            &envelope,
+            &[],
+            "",
+            None,
+            "",
+        )
+        .unwrap());
+
+        let scope = ["amount"];
+        let (v22, scope_hash) =
+            build_proof_v21_scoped(&client_secret, TIMESTAMP, BINDING, PAYLOAD, &scope).unwrap();
+        let envelope = encode_proof_envelope(ProofVersion::V22, &v22);
+        assert!(verify_auto(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            TIMESTAMP,
+            PAYLOAD,
+            &envelope,
+            &scope,
+            &scope_hash,
+            None,
+            "",
+        )
+        .unwrap());
+
+        let v23 = build_proof_v21_unified(
+            &client_secret,
+            TIMESTAMP,
+            BINDING,
+            PAYLOAD,
+            &scope,
+            Some("prev"),
+        )
+        .unwrap();
+        let envelope = encode_proof_envelope(ProofVersion::V23, &v23.proof);
+        assert!(verify_auto(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            TIMESTAMP,
+            PAYLOAD,
+            &envelope,
+            &scope,
+            &v23.scope_hash,
+            Some("prev"),
+            &v23.chain_hash,
+        )
+        .unwrap());
+
+        let v24 = build_proof_v24(&client_secret, TIMESTAMP, BINDING, &hash_body(PAYLOAD));
+        let envelope = encode_proof_envelope(ProofVersion::V24, &v24);
+        assert!(verify_auto(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            TIMESTAMP,
+            PAYLOAD,
+            &envelope,
+            &[],
+            "",
+            None,
+            "",
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_verify_auto_rejects_wrong_version() {
+        let client_secret = derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+        let v23 = build_proof_v21_unified(&client_secret, TIMESTAMP, BINDING, PAYLOAD, &[], None)
+            .unwrap();
+
+        // A v2.3 proof labelled as v2.1 must not verify
+        let envelope = encode_proof_envelope(ProofVersion::V21, &v23.proof);
+        assert!(!verify_auto(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            TIMESTAMP,
+            PAYLOAD,
+            &envelope,
+            &[],
+            "",
+            None,
+            "",
+        )
+        .unwrap());
+
+        // A v2.1 proof labelled as v2.4 must not verify
+        let v21 = build_proof_v21(&client_secret, TIMESTAMP, BINDING, &hash_body(PAYLOAD));
+        let envelope = encode_proof_envelope(ProofVersion::V24, &v21);
+        assert!(!verify_auto(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            TIMESTAMP,
+            PAYLOAD,
+            &envelope,
+            &[],
+            "",
+            None,
+            "",
+        )
+        .unwrap());
+    }
+}
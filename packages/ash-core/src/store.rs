@@ -0,0 +1,517 @@
+//! Server-side context and proof storage.
+//!
+//! Servers issue contexts (with their secret nonce) and later look them up
+//! when a request arrives. The [`ContextStore`] trait abstracts that lookup,
+//! and the single-use consumption of a context, so verification helpers can
+//! work with any backend.
+//!
+//! [`ProofStore`] complements context consumption by remembering individual
+//! proofs, so a context reused across a burst of requests still accepts
+//! each exact proof only once.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, RwLock};
+
+use crate::errors::AshError;
+use crate::proof::verify_with_context;
+use crate::types::{StoredContext, VerificationResult};
+
+/// Outcome of consuming a context in a [`ContextStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextUse {
+    /// Context was live and unconsumed; it is now marked consumed.
+    FirstUse,
+    /// Context was consumed earlier (replay).
+    AlreadyConsumed,
+    /// Context has expired; it is left unconsumed.
+    Expired,
+    /// No context with this ID was issued.
+    NotFound,
+}
+
+/// Lookup of issued contexts by context ID.
+///
+/// Implementations must be safe to share across request handlers.
+pub trait ContextStore: Send + Sync {
+    /// Get a stored context by ID, or `None` if it was never issued.
+    fn get(&self, context_id: &str) -> Option<StoredContext>;
+
+    /// Mark a context consumed at `now_ms`.
+    ///
+    /// Implementations must check and mark atomically, so two concurrent
+    /// consumptions of the same context never both observe `FirstUse`.
+    fn consume(&self, context_id: &str, now_ms: u64) -> ContextUse;
+}
+
+/// Verify a v2.1 request and consume its context in one step (server-side).
+///
+/// Runs [`verify_with_context`] against the context's own binding and, only
+/// if the proof is valid, consumes the context through
+/// [`ContextStore::consume`]. Because consumption is the store's atomic
+/// check-and-mark, concurrent duplicates of the same valid request resolve
+/// to exactly one `Valid`; the others see `Replay`.
+///
+/// `stored` is the context as previously read from `store`; invalid proofs
+/// never consume it.
+///
+/// # Errors
+///
+/// Same as [`verify_with_context`], plus `InvalidContext` if the context
+/// disappeared from the store before it could be consumed.
+pub fn verify_and_consume(
+    store: &dyn ContextStore,
+    stored: &StoredContext,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+    now_ms: u64,
+) -> Result<VerificationResult, AshError> {
+    verify_and_consume_for_binding(
+        store,
+        stored,
+        &stored.binding,
+        timestamp,
+        body_hash,
+        client_proof,
+        now_ms,
+    )
+}
+
+/// [`verify_and_consume`] for the binding the request actually arrived on,
+/// so a mismatch with the context binding reports `EndpointMismatch`.
+pub(crate) fn verify_and_consume_for_binding(
+    store: &dyn ContextStore,
+    stored: &StoredContext,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+    now_ms: u64,
+) -> Result<VerificationResult, AshError> {
+    let result = verify_with_context(stored, now_ms, binding, timestamp, body_hash, client_proof)?;
+    if !result.is_valid() {
+        return Ok(result);
+    }
+
+    match store.consume(&stored.context_id, now_ms) {
+        ContextUse::FirstUse => Ok(VerificationResult::Valid),
+        ContextUse::AlreadyConsumed => Ok(VerificationResult::Replay),
+        ContextUse::Expired => Ok(VerificationResult::Expired),
+        ContextUse::NotFound => Err(AshError::invalid_context()),
+    }
+}
+
+/// In-memory context store.
+///
+/// Suitable for tests and single-process servers. Contexts are lost on restart.
+///
+/// Expired contexts are pruned on [`insert`] (using the new context's
+/// `issued_at` as the clock) and on [`consume`]; a background task can also
+/// call [`sweep`]. With [`with_max_contexts`], inserting into a full store
+/// evicts the context that expires soonest.
+///
+/// Pruning scans the whole map under the write lock, so `insert` and
+/// `consume` cost O(n) in the number of contexts held. Keep the store
+/// bounded with [`with_max_contexts`], or use a backend with native expiry
+/// for large volumes.
+///
+/// [`insert`]: MemoryContextStore::insert
+/// [`consume`]: ContextStore::consume
+/// [`sweep`]: MemoryContextStore::sweep
+/// [`with_max_contexts`]: MemoryContextStore::with_max_contexts
+#[derive(Debug, Default)]
+pub struct MemoryContextStore {
+    contexts: RwLock<HashMap<String, StoredContext>>,
+    /// Maximum number of contexts held (`None` = unbounded)
+    max_contexts: Option<NonZeroUsize>,
+}
+
+impl MemoryContextStore {
+    /// Create an empty, unbounded store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty store holding at most `max_contexts` contexts.
+    ///
+    /// When full, inserting a new context first prunes expired ones and then
+    /// evicts the live context with the earliest `expires_at`.
+    pub fn with_max_contexts(max_contexts: NonZeroUsize) -> Self {
+        Self {
+            contexts: RwLock::default(),
+            max_contexts: Some(max_contexts),
+        }
+    }
+
+    /// Insert or replace a context.
+    pub fn insert(&self, context: StoredContext) {
+        let mut contexts = self.contexts.write().expect("context store lock poisoned");
+        prune_expired(&mut contexts, context.issued_at, None);
+
+        if let Some(max) = self.max_contexts {
+            while contexts.len() >= max.get() && !contexts.contains_key(&context.context_id) {
+                let Some(oldest) = contexts
+                    .values()
+                    .min_by_key(|c| c.expires_at)
+                    .map(|c| c.context_id.clone())
+                else {
+                    break;
+                };
+                contexts.remove(&oldest);
+            }
+        }
+
+        contexts.insert(context.context_id.clone(), context);
+    }
+
+    /// Drop all expired contexts, returning how many were removed.
+    pub fn sweep(&self, now_ms: u64) -> usize {
+        let mut contexts = self.contexts.write().expect("context store lock poisoned");
+        prune_expired(&mut contexts, now_ms, None)
+    }
+
+    /// Number of contexts currently held (including expired, unswept ones).
+    pub fn len(&self) -> usize {
+        self.contexts
+            .read()
+            .expect("context store lock poisoned")
+            .len()
+    }
+
+    /// Whether no contexts are held.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Remove contexts expired at `now_ms`, except `keep`; returns the count removed.
+fn prune_expired(
+    contexts: &mut HashMap<String, StoredContext>,
+    now_ms: u64,
+    keep: Option<&str>,
+) -> usize {
+    let before = contexts.len();
+    contexts.retain(|id, context| !context.is_expired(now_ms) || Some(id.as_str()) == keep);
+    before - contexts.len()
+}
+
+impl ContextStore for MemoryContextStore {
+    fn get(&self, context_id: &str) -> Option<StoredContext> {
+        self.contexts
+            .read()
+            .expect("context store lock poisoned")
+            .get(context_id)
+            .cloned()
+    }
+
+    fn consume(&self, context_id: &str, now_ms: u64) -> ContextUse {
+        let mut contexts = self.contexts.write().expect("context store lock poisoned");
+        // The requested context survives so its outcome is still reported.
+        // This scans every context (O(n)) while holding the write lock.
+        prune_expired(&mut contexts, now_ms, Some(context_id));
+
+        let Some(context) = contexts.get_mut(context_id) else {
+            return ContextUse::NotFound;
+        };
+        if context.is_consumed() {
+            return ContextUse::AlreadyConsumed;
+        }
+        if context.is_expired(now_ms) {
+            return ContextUse::Expired;
+        }
+
+        context.consumed_at = Some(now_ms);
+        ContextUse::FirstUse
+    }
+}
+
+/// Outcome of recording a proof in a [`ProofStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofUse {
+    /// Proof had not been seen within its TTL; it is now recorded.
+    FirstUse,
+    /// Proof was already recorded and has not expired (replay).
+    AlreadySeen,
+}
+
+/// Single-use tracking of individual proofs.
+///
+/// Implementations must check and record atomically, so two concurrent
+/// submissions of the same proof never both observe `FirstUse`.
+pub trait ProofStore: Send + Sync {
+    /// Record `proof` as seen until `now_ms + ttl_ms`.
+    ///
+    /// Returns `AlreadySeen` if the exact proof was recorded earlier and
+    /// its TTL has not yet elapsed.
+    fn mark_proof_seen(&self, proof: &str, ttl_ms: u64, now_ms: u64) -> ProofUse;
+}
+
+/// In-memory proof store.
+///
+/// Expired entries are replaced on reuse; call [`purge_expired`] periodically
+/// to bound memory.
+///
+/// [`purge_expired`]: MemoryProofStore::purge_expired
+#[derive(Debug, Default)]
+pub struct MemoryProofStore {
+    /// Proof -> expiry time (milliseconds since epoch)
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl MemoryProofStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop all entries whose TTL has elapsed.
+    pub fn purge_expired(&self, now_ms: u64) {
+        self.seen
+            .lock()
+            .expect("proof store lock poisoned")
+            .retain(|_, expires_at| now_ms < *expires_at);
+    }
+
+    /// Number of proofs currently recorded (including expired, unpurged ones).
+    pub fn len(&self) -> usize {
+        self.seen.lock().expect("proof store lock poisoned").len()
+    }
+
+    /// Whether no proofs are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ProofStore for MemoryProofStore {
+    fn mark_proof_seen(&self, proof: &str, ttl_ms: u64, now_ms: u64) -> ProofUse {
+        let mut seen = self.seen.lock().expect("proof store lock poisoned");
+
+        if let Some(expires_at) = seen.get(proof) {
+            if now_ms < *expires_at {
+                return ProofUse::AlreadySeen;
+            }
+        }
+
+        seen.insert(proof.to_string(), now_ms.saturating_add(ttl_ms));
+        ProofUse::FirstUse
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AshMode;
+
+    #[test]
+    fn test_memory_store_get() {
+        let store = MemoryContextStore::new();
+        store.insert(StoredContext {
+            context_id: "ctx_1".to_string(),
+            binding: "POST /api".to_string(),
+            mode: AshMode::Balanced,
+            issued_at: 1000,
+            expires_at: 2000,
+            nonce: Some("nonce".to_string()),
+            consumed_at: None,
+        });
+
+        assert_eq!(store.get("ctx_1").unwrap().binding, "POST /api");
+        assert!(store.get("ctx_2").is_none());
+    }
+
+    #[test]
+    fn test_memory_store_consume() {
+        let store = MemoryContextStore::new();
+        store.insert(StoredContext {
+            context_id: "ctx_1".to_string(),
+            binding: "POST /api".to_string(),
+            mode: AshMode::Balanced,
+            issued_at: 1000,
+            expires_at: 2000,
+            nonce: None,
+            consumed_at: None,
+        });
+
+        assert_eq!(store.consume("ctx_2", 1500), ContextUse::NotFound);
+        assert_eq!(store.consume("ctx_1", 2000), ContextUse::Expired);
+        assert!(!store.get("ctx_1").unwrap().is_consumed());
+
+        assert_eq!(store.consume("ctx_1", 1500), ContextUse::FirstUse);
+        assert_eq!(store.get("ctx_1").unwrap().consumed_at, Some(1500));
+        assert_eq!(store.consume("ctx_1", 1600), ContextUse::AlreadyConsumed);
+    }
+
+    fn context(id: &str, issued_at: u64, expires_at: u64) -> StoredContext {
+        StoredContext {
+            context_id: id.to_string(),
+            binding: "POST /api".to_string(),
+            mode: AshMode::Balanced,
+            issued_at,
+            expires_at,
+            nonce: None,
+            consumed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_memory_store_prunes_on_consume() {
+        let store = MemoryContextStore::new();
+        store.insert(context("ctx_old", 1000, 2000));
+        store.insert(context("ctx_new", 1000, 5000));
+
+        assert_eq!(store.consume("ctx_new", 3000), ContextUse::FirstUse);
+        assert!(store.get("ctx_old").is_none());
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_memory_store_sweep() {
+        let store = MemoryContextStore::new();
+        store.insert(context("ctx_1", 1000, 2000));
+        store.insert(context("ctx_2", 1000, 3000));
+        store.insert(context("ctx_3", 1000, 4000));
+
+        assert_eq!(store.sweep(3000), 2);
+        assert_eq!(store.sweep(3000), 0);
+        assert!(store.get("ctx_3").is_some());
+    }
+
+    #[test]
+    fn test_memory_store_capacity_evicts_soonest_expiring() {
+        let store = MemoryContextStore::with_max_contexts(NonZeroUsize::new(2).unwrap());
+        store.insert(context("ctx_a", 1000, 9000));
+        store.insert(context("ctx_b", 1000, 5000));
+        store.insert(context("ctx_c", 1000, 7000));
+
+        assert_eq!(store.len(), 2);
+        assert!(store.get("ctx_b").is_none());
+        assert!(store.get("ctx_a").is_some());
+        assert!(store.get("ctx_c").is_some());
+
+        // Replacing an existing context does not evict
+        store.insert(context("ctx_a", 1000, 9500));
+        assert_eq!(store.len(), 2);
+        assert!(store.get("ctx_c").is_some());
+    }
+
+    fn signed_context(store: &MemoryContextStore) -> (StoredContext, String, String) {
+        use crate::proof::{build_proof_v21, derive_client_secret, hash_body};
+
+        let context = StoredContext {
+            context_id: "ctx_1".to_string(),
+            binding: "POST /api/transfer".to_string(),
+            mode: AshMode::Balanced,
+            issued_at: 1000,
+            expires_at: 2000,
+            nonce: Some("nonce123".to_string()),
+            consumed_at: None,
+        };
+        store.insert(context.clone());
+
+        let body_hash = hash_body(r#"{"amount":100}"#);
+        let secret = derive_client_secret("nonce123", "ctx_1", "POST /api/transfer");
+        let proof = build_proof_v21(&secret, "1500", "POST /api/transfer", &body_hash);
+        (context, body_hash, proof)
+    }
+
+    #[test]
+    fn test_verify_and_consume() {
+        let store = MemoryContextStore::new();
+        let (context, body_hash, proof) = signed_context(&store);
+
+        let tampered = "0".repeat(64);
+        assert_eq!(
+            verify_and_consume(&store, &context, "1500", &body_hash, &tampered, 1500).unwrap(),
+            VerificationResult::IntegrityFailed
+        );
+        assert!(!store.get("ctx_1").unwrap().is_consumed());
+
+        assert_eq!(
+            verify_and_consume(&store, &context, "1500", &body_hash, &proof, 1500).unwrap(),
+            VerificationResult::Valid
+        );
+        assert_eq!(store.get("ctx_1").unwrap().consumed_at, Some(1500));
+
+        // A stale snapshot still cannot be replayed
+        assert_eq!(
+            verify_and_consume(&store, &context, "1500", &body_hash, &proof, 1600).unwrap(),
+            VerificationResult::Replay
+        );
+    }
+
+    #[test]
+    fn test_verify_and_consume_concurrent_single_success() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let store = Arc::new(MemoryContextStore::new());
+        let (context, body_hash, proof) = signed_context(&store);
+
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                let (context, body_hash, proof) =
+                    (context.clone(), body_hash.clone(), proof.clone());
+                thread::spawn(move || {
+                    verify_and_consume(store.as_ref(), &context, "1500", &body_hash, &proof, 1500)
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        let valid = results.iter().filter(|r| r.is_valid()).count();
+
+        assert_eq!(valid, 1);
+        assert!(results
+            .iter()
+            .all(|r| r.is_valid() || *r == VerificationResult::Replay));
+    }
+
+    #[test]
+    fn test_memory_proof_store_rejects_resubmission() {
+        let store = MemoryProofStore::new();
+
+        assert_eq!(store.mark_proof_seen("abc", 1000, 0), ProofUse::FirstUse);
+        assert_eq!(
+            store.mark_proof_seen("abc", 1000, 500),
+            ProofUse::AlreadySeen
+        );
+        assert_eq!(store.mark_proof_seen("def", 1000, 500), ProofUse::FirstUse);
+    }
+
+    #[test]
+    fn test_memory_proof_store_expires() {
+        let store = MemoryProofStore::new();
+
+        assert_eq!(store.mark_proof_seen("abc", 1000, 0), ProofUse::FirstUse);
+        assert_eq!(store.mark_proof_seen("abc", 1000, 1000), ProofUse::FirstUse);
+
+        store.purge_expired(5000);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_memory_proof_store_concurrent_single_use() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let store = Arc::new(MemoryProofStore::new());
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                thread::spawn(move || store.mark_proof_seen("proof", 60_000, 0))
+            })
+            .collect();
+
+        let first_uses = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|outcome| *outcome == ProofUse::FirstUse)
+            .count();
+
+        assert_eq!(first_uses, 1);
+    }
+}
@@ -0,0 +1,103 @@
+//! Pre-MAC proof messages for authoring cross-SDK test vectors.
+//!
+//! Enabled with the `test-vectors` feature. These return the exact string
+//! fed to HMAC-SHA256 so vectors can be diffed against other SDKs. Nothing
+//! here is needed to build or verify proofs; keep the feature out of
+//! production builds.
+
+use alloc::string::String;
+
+use crate::proof::{proof_v21_message, unified_message};
+
+/// The v2.1 HMAC message for `build_proof_v21`.
+///
+/// Format: `timestamp|binding|bodyHash`
+///
+/// # Example
+///
+/// ```rust
+/// use ash_core::build_proof_v21_message;
+///
+/// let message = build_proof_v21_message("1700000000", "POST /api", "abc");
+/// assert_eq!(message, "1700000000|POST /api|abc");
+/// ```
+pub fn build_proof_v21_message(timestamp: &str, binding: &str, body_hash: &str) -> String {
+    proof_v21_message(timestamp, binding, body_hash)
+}
+
+/// The v2.3 HMAC message for `build_proof_v21_unified`.
+///
+/// Format: `timestamp|binding|bodyHash|scopeHash|chainHash`, where
+/// `scope_hash` and `chain_hash` are empty strings when scoping or chaining
+/// is not used.
+pub fn build_proof_v21_unified_message(
+    timestamp: &str,
+    binding: &str,
+    body_hash: &str,
+    scope_hash: &str,
+    chain_hash: &str,
+) -> String {
+    unified_message(timestamp, binding, body_hash, scope_hash, chain_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::{build_proof_v21, build_proof_v21_unified, hash_body, hash_scoped_body};
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    fn hmac_hex(key: &str, message: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+        mac.update(message.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[test]
+    fn test_v21_message_matches_formula() {
+        let body_hash = hash_body("{}");
+        let message = build_proof_v21_message("1700000000", "POST /api", &body_hash);
+
+        assert_eq!(message, format!("1700000000|POST /api|{}", body_hash));
+        assert_eq!(
+            hmac_hex("secret", &message),
+            build_proof_v21("secret", "1700000000", "POST /api", &body_hash)
+        );
+    }
+
+    #[test]
+    fn test_unified_message_matches_formula() {
+        let payload = r#"{"amount":100,"note":"x"}"#;
+        let scope = ["amount"];
+        let result = build_proof_v21_unified(
+            "secret",
+            "1700000000",
+            "POST /api",
+            payload,
+            &scope,
+            Some("p"),
+        )
+        .unwrap();
+        let body_hash = hash_scoped_body(payload, &scope).unwrap();
+
+        let message = build_proof_v21_unified_message(
+            "1700000000",
+            "POST /api",
+            &body_hash,
+            &result.scope_hash,
+            &result.chain_hash,
+        );
+        assert_eq!(
+            message,
+            format!(
+                "1700000000|POST /api|{}|{}|{}",
+                body_hash, result.scope_hash, result.chain_hash
+            )
+        );
+        assert_eq!(hmac_hex("secret", &message), result.proof);
+
+        // No scope or chain: both trailing fields are empty
+        let message = build_proof_v21_unified_message("1", "GET /", "h", "", "");
+        assert_eq!(message, "1|GET /|h||");
+    }
+}
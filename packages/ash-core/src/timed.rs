@@ -0,0 +1,62 @@
+//! Timed verification for load testing and capacity planning.
+//!
+//! Enabled with the `timing-instrumentation` feature. **Not for production
+//! request paths**: the measured duration depends on secret-dependent
+//! work, and logging or returning it to clients could leak what the
+//! constant-time comparison is designed to hide. Use it in benchmarks and
+//! load-test harnesses only.
+
+use std::time::{Duration, Instant};
+
+use crate::proof::verify_proof_v21;
+
+/// [`verify_proof_v21`] plus the time spent in it.
+///
+/// The duration covers the client secret derivation, the expected proof
+/// and the comparison: pure CPU work, with no I/O. Both values come from
+/// the same call, so the result is always identical to the plain verifier.
+pub fn verify_proof_v21_timed(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+    client_proof: &str,
+) -> (bool, Duration) {
+    let start = Instant::now();
+    let valid = verify_proof_v21(
+        nonce,
+        context_id,
+        binding,
+        timestamp,
+        body_hash,
+        client_proof,
+    );
+    (valid, start.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::{build_proof_v21, derive_client_secret, hash_body};
+
+    #[test]
+    fn test_timed_matches_plain_verify() {
+        let (nonce, context_id, binding, timestamp) =
+            ("test_nonce_12345", "ctx_abc123", "POST /api", "1234567890");
+        let body_hash = hash_body("{}");
+        let client_secret = derive_client_secret(nonce, context_id, binding);
+        let proof = build_proof_v21(&client_secret, timestamp, binding, &body_hash);
+
+        for candidate in [proof.as_str(), "0".repeat(64).as_str()] {
+            let (valid, elapsed) = verify_proof_v21_timed(
+                nonce, context_id, binding, timestamp, &body_hash, candidate,
+            );
+            assert_eq!(
+                valid,
+                verify_proof_v21(nonce, context_id, binding, timestamp, &body_hash, candidate)
+            );
+            assert!(elapsed > Duration::ZERO);
+        }
+    }
+}
@@ -1,8 +1,11 @@
 //! Core types for ASH protocol.
 
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
 use serde::{Deserialize, Serialize};
-use std::fmt;
-use std::str::FromStr;
 
 use crate::errors::{AshError, AshErrorCode};
 
@@ -36,21 +39,34 @@ impl fmt::Display for AshMode {
     }
 }
 
+/// Accepted mode names (after trimming and lowercasing) besides the
+/// canonical `minimal`, `balanced` and `strict`.
+const MODE_ALIASES: &[(&str, AshMode)] = &[("default", AshMode::Balanced)];
+
+/// Parses a mode name, ignoring case and surrounding whitespace (as left
+/// by header parsing). `"default"` is accepted as an alias for `balanced`.
 impl FromStr for AshMode {
     type Err = AshError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
+        let name = s.trim().to_lowercase();
+        match name.as_str() {
             "minimal" => Ok(AshMode::Minimal),
             "balanced" => Ok(AshMode::Balanced),
             "strict" => Ok(AshMode::Strict),
-            _ => Err(AshError::new(
-                AshErrorCode::ModeViolation,
-                format!(
-                    "Invalid mode: {}. Expected: minimal, balanced, or strict",
-                    s
-                ),
-            )),
+            _ => MODE_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == name)
+                .map(|(_, mode)| *mode)
+                .ok_or_else(|| {
+                    AshError::new(
+                        AshErrorCode::ModeViolation,
+                        format!(
+                            "Invalid mode: {}. Expected: minimal, balanced, or strict",
+                            s
+                        ),
+                    )
+                }),
         }
     }
 }
@@ -125,7 +141,6 @@ pub struct ContextPublicInfo {
 }
 
 /// Stored context (server-side).
-#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StoredContext {
@@ -147,7 +162,6 @@ pub struct StoredContext {
     pub consumed_at: Option<u64>,
 }
 
-#[allow(dead_code)]
 impl StoredContext {
     /// Check if context has been consumed.
     pub fn is_consumed(&self) -> bool {
@@ -160,10 +174,137 @@ impl StoredContext {
     }
 }
 
+/// Outcome of verifying a request against a stored context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationResult {
+    /// Context is live, binding matches and the proof is valid
+    Valid,
+    /// Context has expired
+    Expired,
+    /// Context was already consumed
+    Replay,
+    /// Request binding differs from the context binding
+    EndpointMismatch,
+    /// Proof does not match
+    IntegrityFailed,
+}
+
+impl VerificationResult {
+    /// Whether the request verified.
+    pub fn is_valid(&self) -> bool {
+        *self == VerificationResult::Valid
+    }
+
+    /// Convert a failed result into the matching error (`None` if valid).
+    pub fn to_error(&self) -> Option<AshError> {
+        match self {
+            VerificationResult::Valid => None,
+            VerificationResult::Expired => Some(AshError::context_expired()),
+            VerificationResult::Replay => Some(AshError::replay_detected()),
+            VerificationResult::EndpointMismatch => Some(AshError::endpoint_mismatch()),
+            VerificationResult::IntegrityFailed => Some(AshError::integrity_failed()),
+        }
+    }
+}
+
+/// Immutable record of a verified request.
+///
+/// Built with [`build_receipt`](crate::build_receipt) after verification
+/// succeeds, and stored for audit. [`receipt_digest`](Self::receipt_digest)
+/// commits to every field, so receipts can be compared or deduplicated by
+/// digest alone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofReceipt {
+    /// Context the request was verified against
+    pub context_id: String,
+    /// Canonical binding
+    pub binding: String,
+    /// Request timestamp, as sent by the client
+    pub timestamp: String,
+    /// Body hash covered by the proof
+    pub body_hash: String,
+    /// Scope hash (empty if the proof was not scoped)
+    pub scope_hash: String,
+    /// Chain hash (empty if the proof was not chained)
+    pub chain_hash: String,
+    /// Verification time (milliseconds since epoch)
+    pub verified_at: u64,
+}
+
+impl ProofReceipt {
+    /// SHA-256 over a canonical encoding of all fields (hex).
+    ///
+    /// Each field is length-prefixed (`len:value|`) after a version tag, so
+    /// no two distinct receipts share an encoding even if a field contains
+    /// the separator.
+    pub fn receipt_digest(&self) -> String {
+        let fields: [&str; 6] = [
+            &self.context_id,
+            &self.binding,
+            &self.timestamp,
+            &self.body_hash,
+            &self.scope_hash,
+            &self.chain_hash,
+        ];
+
+        let mut encoded = String::from("ash-receipt-v1|");
+        for field in fields {
+            encoded.push_str(&format!("{}:{}|", field.len(), field));
+        }
+        encoded.push_str(&format!("{}", self.verified_at));
+
+        crate::proof::hash_body(&encoded)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn receipt() -> ProofReceipt {
+        ProofReceipt {
+            context_id: "ctx_1".to_string(),
+            binding: "POST /api".to_string(),
+            timestamp: "1700000000000".to_string(),
+            body_hash: "a".repeat(64),
+            scope_hash: String::new(),
+            chain_hash: String::new(),
+            verified_at: 1700000000123,
+        }
+    }
+
+    #[test]
+    fn test_receipt_digest_identical_fields() {
+        assert_eq!(receipt().receipt_digest(), receipt().receipt_digest());
+        assert_eq!(receipt().receipt_digest().len(), 64);
+    }
+
+    #[test]
+    fn test_receipt_digest_covers_every_field() {
+        let base = receipt().receipt_digest();
+        let changes: [fn(&mut ProofReceipt); 7] = [
+            |r| r.context_id.push('x'),
+            |r| r.binding = "POST /other".to_string(),
+            |r| r.timestamp.push('1'),
+            |r| r.body_hash = "b".repeat(64),
+            |r| r.scope_hash = "c".repeat(64),
+            |r| r.chain_hash = "d".repeat(64),
+            |r| r.verified_at += 1,
+        ];
+        for change in changes {
+            let mut changed = receipt();
+            change(&mut changed);
+            assert_ne!(changed.receipt_digest(), base, "{:?}", changed);
+        }
+
+        // Moving bytes between adjacent fields is not a collision
+        let mut shifted = receipt();
+        shifted.context_id = "ctx_1|9:POST".to_string();
+        shifted.binding = " /api".to_string();
+        assert_ne!(shifted.receipt_digest(), base);
+    }
+
     #[test]
     fn test_ash_mode_default() {
         assert_eq!(AshMode::default(), AshMode::Balanced);
@@ -177,6 +318,28 @@ mod tests {
         assert_eq!("BALANCED".parse::<AshMode>().unwrap(), AshMode::Balanced);
     }
 
+    #[test]
+    fn test_ash_mode_from_str_trims_whitespace() {
+        assert_eq!(" balanced ".parse::<AshMode>().unwrap(), AshMode::Balanced);
+        assert_eq!("Balanced\n".parse::<AshMode>().unwrap(), AshMode::Balanced);
+        assert_eq!("\tStRiCt\r\n".parse::<AshMode>().unwrap(), AshMode::Strict);
+        assert_eq!("  MINIMAL".parse::<AshMode>().unwrap(), AshMode::Minimal);
+    }
+
+    #[test]
+    fn test_ash_mode_from_str_default_alias() {
+        assert_eq!("default".parse::<AshMode>().unwrap(), AshMode::Balanced);
+        assert_eq!(" Default ".parse::<AshMode>().unwrap(), AshMode::Balanced);
+    }
+
+    #[test]
+    fn test_ash_mode_from_str_unknown() {
+        for bad in ["paranoid", "", "   ", "bal anced", "defaults"] {
+            let err = bad.parse::<AshMode>().unwrap_err();
+            assert_eq!(err.code(), AshErrorCode::ModeViolation, "{:?}", bad);
+        }
+    }
+
     #[test]
     fn test_ash_mode_display() {
         assert_eq!(AshMode::Minimal.to_string(), "minimal");
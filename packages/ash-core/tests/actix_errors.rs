@@ -0,0 +1,40 @@
+//! Integration tests for the actix-web error mapping.
+
+#![cfg(feature = "actix")]
+
+use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+use ash_core::{AshError, AshErrorCode};
+
+#[tokio::test]
+async fn test_replay_detected_maps_to_409() {
+    let error = AshError::replay_detected();
+    assert_eq!(error.status_code(), StatusCode::CONFLICT);
+
+    let response = error.error_response();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+
+    let bytes = actix_web::body::to_bytes(response.into_body())
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({"error": "ASH_REPLAY_DETECTED", "status": 409})
+    );
+}
+
+#[tokio::test]
+async fn test_error_response_omits_message() {
+    let error = AshError::new(AshErrorCode::CanonicalizationFailed, "Invalid JSON: line 1");
+    assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+
+    let bytes = actix_web::body::to_bytes(error.error_response().into_body())
+        .await
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&bytes).contains("line 1"));
+}
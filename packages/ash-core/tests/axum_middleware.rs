@@ -0,0 +1,165 @@
+//! Integration tests for the axum verification middleware.
+
+#![cfg(feature = "axum")]
+
+use std::sync::Arc;
+
+use ash_core::{
+    build_proof_v21, canonicalize_json, canonicalize_urlencoded, derive_client_secret, hash_body,
+    AshError, AshLayer, AshMode, MemoryContextStore, StoredContext, VerifiedAsh,
+};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::post;
+use axum::Router;
+use tower::ServiceExt;
+
+const NONCE: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+const CONTEXT_ID: &str = "ash_test_context_axum";
+const BINDING: &str = "POST /api/transfer";
+const TIMESTAMP: &str = "1704067200000";
+
+fn app() -> Router {
+    let store = Arc::new(MemoryContextStore::new());
+    store.insert(StoredContext {
+        context_id: CONTEXT_ID.to_string(),
+        binding: BINDING.to_string(),
+        mode: AshMode::Balanced,
+        issued_at: 0,
+        expires_at: u64::MAX,
+        nonce: Some(NONCE.to_string()),
+        consumed_at: None,
+    });
+
+    Router::new()
+        .route(
+            "/api/transfer",
+            post(|ash: VerifiedAsh| async move { ash.context_id }),
+        )
+        .layer(AshLayer::new(store))
+}
+
+fn signed_request(body: &str, signed_body: &str) -> Request<Body> {
+    let client_secret = derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+    let body_hash = hash_body(&canonicalize_json(signed_body).unwrap());
+    let proof = build_proof_v21(&client_secret, TIMESTAMP, BINDING, &body_hash);
+
+    Request::post("/api/transfer")
+        .header("content-type", "application/json")
+        .header("x-ash-context", CONTEXT_ID)
+        .header("x-ash-timestamp", TIMESTAMP)
+        .header("x-ash-proof", proof)
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_valid_request_reaches_handler() {
+    let body = r#"{"recipient":"user123","amount":100}"#;
+    let response = app().oneshot(signed_request(body, body)).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&bytes[..], CONTEXT_ID.as_bytes());
+}
+
+#[tokio::test]
+async fn test_tampered_body_is_rejected() {
+    let signed = r#"{"recipient":"user123","amount":100}"#;
+    let tampered = r#"{"recipient":"user123","amount":9999}"#;
+    let response = app()
+        .oneshot(signed_request(tampered, signed))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "ASH_INTEGRITY_FAILED");
+}
+
+#[tokio::test]
+async fn test_replayed_request_is_rejected() {
+    let app = app();
+    let body = r#"{"recipient":"user123","amount":100}"#;
+
+    let first = app
+        .clone()
+        .oneshot(signed_request(body, body))
+        .await
+        .unwrap();
+    assert_eq!(first.status(), StatusCode::OK);
+
+    let replay = app.oneshot(signed_request(body, body)).await.unwrap();
+    assert_eq!(replay.status(), StatusCode::CONFLICT);
+    let bytes = axum::body::to_bytes(replay.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "ASH_REPLAY_DETECTED");
+}
+
+#[tokio::test]
+async fn test_form_body_reaches_handler() {
+    let body = "recipient=user123&amount=100";
+    let client_secret = derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+    let body_hash = hash_body(&canonicalize_urlencoded(body).unwrap());
+    let proof = build_proof_v21(&client_secret, TIMESTAMP, BINDING, &body_hash);
+
+    let request = Request::post("/api/transfer")
+        .header("content-type", "application/x-www-form-urlencoded")
+        .header("x-ash-context", CONTEXT_ID)
+        .header("x-ash-timestamp", TIMESTAMP)
+        .header("x-ash-proof", proof)
+        .body(Body::from(body))
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_body_without_content_type_is_rejected() {
+    let body = r#"{"recipient":"user123","amount":100}"#;
+    let mut request = signed_request(body, body);
+    request.headers_mut().remove("content-type");
+    let response = app().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "ASH_UNSUPPORTED_CONTENT_TYPE");
+}
+
+#[tokio::test]
+async fn test_missing_headers_are_rejected() {
+    let request = Request::post("/api/transfer")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_ash_error_into_response() {
+    let response = AshError::replay_detected().into_response();
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({"error": "ASH_REPLAY_DETECTED", "status": 409})
+    );
+}
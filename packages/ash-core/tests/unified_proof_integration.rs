@@ -253,7 +253,7 @@ fn test_unified_wrong_scope_hash_fails() {
         payload,
         &result.proof,
         &scope,
-        "wrongscopehash1234567890abcdef1234567890abcdef1234567890abcdef12",
+        "0000000000000000000000000000000000000000000000000000000000000000",
         None,
         "",
     )
@@ -295,13 +295,60 @@ fn test_unified_wrong_chain_hash_fails() {
         &[],
         "",
         Some(previous_proof),
-        "wrongchainhash1234567890abcdef1234567890abcdef1234567890abcdef12",
+        "0000000000000000000000000000000000000000000000000000000000000000",
     )
     .expect("verify should return result");
 
     assert!(!valid, "wrong chain hash should fail");
 }
 
+/// Test the `scoped_unsorted_duplicated` vector: scope is hashed as a set
+#[test]
+fn test_unified_scoped_unsorted_vector() {
+    let nonce = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    let context_id = "ash_test_context_007";
+    let binding = "POST /api/transfer";
+    let timestamp = "1704067200000";
+    let payload = r#"{"amount":100,"note":"test","recipient":"user123"}"#;
+    let scope = ["recipient", "amount", "recipient"];
+
+    let client_secret = derive_client_secret(nonce, context_id, binding);
+    assert_eq!(
+        client_secret,
+        "40566bb4c5e6df01db8835828a27108b0a7e8148bba9d5b31024390568957ee5"
+    );
+
+    let result =
+        build_proof_v21_unified(&client_secret, timestamp, binding, payload, &scope, None)
+            .expect("build should succeed");
+
+    assert_eq!(
+        result.scope_hash,
+        "33b49dfc2f9995122702b8a09b7583c4d352c2430c153983e344b4d1a8e2279a"
+    );
+    assert_eq!(
+        result.proof,
+        "4e6bfee79cf9c48a9e8fbb61348e7c4ac60f91f902edd16673c0e94f82b1f055"
+    );
+
+    // A server listing the scope in sorted order accepts the same proof
+    let valid = verify_proof_v21_unified(
+        nonce,
+        context_id,
+        binding,
+        timestamp,
+        payload,
+        &result.proof,
+        &["amount", "recipient"],
+        &result.scope_hash,
+        None,
+        "",
+    )
+    .expect("verify should succeed");
+
+    assert!(valid, "proof should verify");
+}
+
 /// Test hash_proof function
 #[test]
 fn test_hash_proof() {
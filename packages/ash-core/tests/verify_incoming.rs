@@ -0,0 +1,148 @@
+//! Integration tests for `verify_incoming`.
+
+use ash_core::{
+    build_proof_v21, canonicalize_json, derive_client_secret, hash_body, verify_incoming,
+    AshErrorCode, AshHeaders, AshIncoming, AshMode, AshOutcome, MemoryContextStore, StoredContext,
+};
+
+const NONCE: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+const CONTEXT_ID: &str = "ash_test_context_incoming";
+const BINDING: &str = "POST /api/transfer";
+const TIMESTAMP: &str = "1704067200000";
+const NOW_MS: u64 = 1_704_067_200_000;
+
+fn store() -> MemoryContextStore {
+    let store = MemoryContextStore::new();
+    store.insert(StoredContext {
+        context_id: CONTEXT_ID.to_string(),
+        binding: BINDING.to_string(),
+        mode: AshMode::Balanced,
+        issued_at: 0,
+        expires_at: u64::MAX,
+        nonce: Some(NONCE.to_string()),
+        consumed_at: None,
+    });
+    store
+}
+
+fn sign(signed_body: &str) -> String {
+    let client_secret = derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+    let body_hash = hash_body(&canonicalize_json(signed_body).unwrap());
+    build_proof_v21(&client_secret, TIMESTAMP, BINDING, &body_hash)
+}
+
+fn incoming<'a>(body: &'a str, content_type: &'a str, proof: &'a str) -> AshIncoming<'a> {
+    AshIncoming {
+        method: "post",
+        path: "/api/transfer?ref=1",
+        host: Some("api.example.com"),
+        content_type: Some(content_type),
+        body: body.as_bytes(),
+        headers: AshHeaders {
+            context_id: CONTEXT_ID,
+            timestamp: TIMESTAMP,
+            proof,
+        },
+    }
+}
+
+#[test]
+fn test_valid_request() {
+    let store = store();
+    let body = r#"{"recipient":"user123","amount":100}"#;
+    let proof = sign(body);
+
+    let outcome = verify_incoming(
+        &store,
+        &incoming(body, "application/json; charset=utf-8", &proof),
+        NOW_MS,
+    );
+    assert_eq!(
+        outcome,
+        AshOutcome::Valid {
+            canonical_body: r#"{"amount":100,"recipient":"user123"}"#.to_string()
+        }
+    );
+    assert!(outcome.is_valid());
+}
+
+#[test]
+fn test_tampered_body() {
+    let store = store();
+    let proof = sign(r#"{"recipient":"user123","amount":100}"#);
+    let tampered = r#"{"recipient":"user123","amount":9999}"#;
+
+    let outcome = verify_incoming(
+        &store,
+        &incoming(tampered, "application/json", &proof),
+        NOW_MS,
+    );
+    assert_eq!(outcome, AshOutcome::IntegrityFailed);
+
+    // A failed proof does not burn the context
+    let body = r#"{"recipient":"user123","amount":100}"#;
+    let outcome = verify_incoming(&store, &incoming(body, "application/json", &proof), NOW_MS);
+    assert!(outcome.is_valid());
+}
+
+#[test]
+fn test_replayed_context() {
+    let store = store();
+    let body = r#"{"recipient":"user123","amount":100}"#;
+    let proof = sign(body);
+    let request = incoming(body, "application/json", &proof);
+
+    assert!(verify_incoming(&store, &request, NOW_MS).is_valid());
+    assert_eq!(
+        verify_incoming(&store, &request, NOW_MS),
+        AshOutcome::Replay
+    );
+}
+
+#[test]
+fn test_unsupported_content_type() {
+    let store = store();
+    let body = "<transfer amount=\"100\"/>";
+    let proof = sign("{}");
+
+    let outcome = verify_incoming(&store, &incoming(body, "application/xml", &proof), NOW_MS);
+    assert_eq!(
+        outcome,
+        AshOutcome::Malformed(AshErrorCode::UnsupportedContentType)
+    );
+}
+
+#[test]
+fn test_endpoint_mismatch() {
+    let store = store();
+    let body = r#"{"amount":100}"#;
+    let proof = sign(body);
+    let mut request = incoming(body, "application/json", &proof);
+    request.path = "/api/withdraw";
+
+    assert_eq!(
+        verify_incoming(&store, &request, NOW_MS),
+        AshOutcome::EndpointMismatch
+    );
+}
+
+#[test]
+fn test_host_is_not_bound() {
+    let store = store();
+    let body = r#"{"amount":100}"#;
+    let proof = sign(body);
+    let mut request = incoming(body, "application/json", &proof);
+    request.host = Some("other.example.com");
+
+    assert!(verify_incoming(&store, &request, NOW_MS).is_valid());
+}
+
+#[test]
+fn test_unknown_context() {
+    let store = MemoryContextStore::new();
+    let body = r#"{"amount":100}"#;
+    let proof = sign(body);
+
+    let outcome = verify_incoming(&store, &incoming(body, "application/json", &proof), NOW_MS);
+    assert_eq!(outcome, AshOutcome::Malformed(AshErrorCode::InvalidContext));
+}
@@ -266,7 +266,7 @@ public static partial class ProofV22
             return payload;
 
         var result = new Dictionary<string, object?>();
-        foreach (var fieldPath in scope)
+        foreach (var fieldPath in NormalizeScope(scope))
         {
             var value = GetNestedValue(payload, fieldPath);
             if (value != null)
@@ -277,6 +277,26 @@ public static partial class ProofV22
         return result;
     }
 
+    /// <summary>
+    /// Sort (by UTF-8 bytes) and deduplicate a scope list.
+    /// The scope is a set: field order and duplicates do not affect the proof.
+    /// </summary>
+    private static string[] NormalizeScope(string[] scope)
+    {
+        var normalized = scope.Distinct().ToArray();
+        Array.Sort(normalized, (a, b) =>
+            Encoding.UTF8.GetBytes(a).AsSpan().SequenceCompareTo(Encoding.UTF8.GetBytes(b)));
+        return normalized;
+    }
+
+    /// <summary>
+    /// Hash a scope list: SHA256 of the normalized fields joined by ",".
+    /// </summary>
+    public static string HashScope(string[] scope)
+    {
+        return ProofV21.HashBody(string.Join(",", NormalizeScope(scope)));
+    }
+
     private static object? GetNestedValue(Dictionary<string, object?> obj, string path)
     {
         var keys = path.Split('.');
@@ -329,8 +349,7 @@ public static partial class ProofV22
         var canonicalScoped = System.Text.Json.JsonSerializer.Serialize(scopedPayload);
         var bodyHash = ProofV21.HashBody(canonicalScoped);
 
-        var scopeStr = string.Join(",", scope);
-        var scopeHash = ProofV21.HashBody(scopeStr);
+        var scopeHash = HashScope(scope);
 
         var message = $"{timestamp}|{binding}|{bodyHash}|{scopeHash}";
         var key = Encoding.UTF8.GetBytes(clientSecret);
@@ -355,8 +374,7 @@ public static partial class ProofV22
         string scopeHash,
         string clientProof)
     {
-        var scopeStr = string.Join(",", scope);
-        var expectedScopeHash = ProofV21.HashBody(scopeStr);
+        var expectedScopeHash = HashScope(scope);
         if (!Compare.TimingSafe(expectedScopeHash, scopeHash))
             return false;
 
@@ -410,7 +428,7 @@ public static partial class ProofV23
     /// <remarks>
     /// Formula:
     /// <code>
-    /// scopeHash  = scope.Length > 0 ? SHA256(scope.join(",")) : ""
+    /// scopeHash  = scope.Length > 0 ? SHA256(sorted(unique(scope)).join(",")) : ""
     /// bodyHash   = SHA256(canonicalize(scopedPayload))
     /// chainHash  = previousProof != null ? SHA256(previousProof) : ""
     /// proof      = HMAC-SHA256(clientSecret, timestamp|binding|bodyHash|scopeHash|chainHash)
@@ -440,7 +458,7 @@ public static partial class ProofV23
 
         // Compute scope hash (empty string if no scope)
         var scopeHash = scope.Length > 0
-            ? ProofV21.HashBody(string.Join(",", scope))
+            ? ProofV22.HashScope(scope)
             : "";
 
         // Compute chain hash (empty string if no previous proof)
@@ -490,7 +508,7 @@ public static partial class ProofV23
         // Validate scope hash if scoping is used
         if (scope.Length > 0)
         {
-            var expectedScopeHash = ProofV21.HashBody(string.Join(",", scope));
+            var expectedScopeHash = ProofV22.HashScope(scope);
             if (!Compare.TimingSafe(expectedScopeHash, scopeHash))
                 return false;
         }
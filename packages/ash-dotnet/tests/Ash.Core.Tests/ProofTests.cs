@@ -137,4 +137,31 @@ public class ProofTests
 
         Assert.Equal("Hello", System.Text.Encoding.UTF8.GetString(decoded));
     }
+
+    [Fact]
+    public void BuildProofUnified_TreatsScopeAsSet()
+    {
+        // scoped_unsorted_duplicated vector
+        var clientSecret = ProofV21.DeriveClientSecret(
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            "ash_test_context_007",
+            "POST /api/transfer");
+        var payload = new Dictionary<string, object?>
+        {
+            ["amount"] = 100,
+            ["recipient"] = "user123",
+            ["note"] = "test",
+        };
+
+        var result = ProofV23.BuildProofUnified(
+            clientSecret,
+            "1704067200000",
+            "POST /api/transfer",
+            payload,
+            new[] { "recipient", "amount", "recipient" });
+
+        Assert.Equal(ProofV22.HashScope(new[] { "amount", "recipient" }), result.ScopeHash);
+        Assert.Equal("33b49dfc2f9995122702b8a09b7583c4d352c2430c153983e344b4d1a8e2279a", result.ScopeHash);
+        Assert.Equal("4e6bfee79cf9c48a9e8fbb61348e7c4ac60f91f902edd16673c0e94f82b1f055", result.Proof);
+    }
 }
@@ -834,6 +834,25 @@ func setNestedValue(obj map[string]interface{}, path string, value interface{})
 	current[keys[len(keys)-1]] = value
 }
 
+// normalizeScope returns the scope sorted (byte order) and deduplicated.
+// The scope is a set: field order and duplicates do not affect the proof.
+func normalizeScope(scope []string) []string {
+	sorted := append([]string(nil), scope...)
+	sort.Strings(sorted)
+	normalized := sorted[:0]
+	for i, field := range sorted {
+		if i == 0 || field != sorted[i-1] {
+			normalized = append(normalized, field)
+		}
+	}
+	return normalized
+}
+
+// HashScope hashes a scope list: SHA256 of the normalized fields joined by ",".
+func HashScope(scope []string) string {
+	return HashBody(strings.Join(normalizeScope(scope), ","))
+}
+
 // BuildProofV21Scoped builds a v2.2 proof with scoped fields.
 func BuildProofV21Scoped(clientSecret, timestamp, binding string, payload map[string]interface{}, scope []string) ScopedProofResult {
 	scopedPayload := ExtractScopedFields(payload, scope)
@@ -842,8 +861,7 @@ func BuildProofV21Scoped(clientSecret, timestamp, binding string, payload map[st
 	canonicalBytes, _ := json.Marshal(scopedPayload)
 	bodyHash := HashBody(string(canonicalBytes))
 
-	scopeStr := strings.Join(scope, ",")
-	scopeHash := HashBody(scopeStr)
+	scopeHash := HashScope(scope)
 
 	message := timestamp + "|" + binding + "|" + bodyHash + "|" + scopeHash
 	h := hmac.New(sha256.New, []byte(clientSecret))
@@ -856,8 +874,7 @@ func BuildProofV21Scoped(clientSecret, timestamp, binding string, payload map[st
 // VerifyProofV21Scoped verifies a v2.2 proof with scoped fields.
 func VerifyProofV21Scoped(nonce, contextID, binding, timestamp string, payload map[string]interface{}, scope []string, scopeHash, clientProof string) bool {
 	// Verify scope hash
-	scopeStr := strings.Join(scope, ",")
-	expectedScopeHash := HashBody(scopeStr)
+	expectedScopeHash := HashScope(scope)
 	if !TimingSafeCompare(expectedScopeHash, scopeHash) {
 		return false
 	}
@@ -895,7 +912,7 @@ func HashProof(proof string) string {
 // BuildProofUnified builds a unified v2.3 proof with optional scoping and chaining.
 //
 // Formula:
-//   scopeHash  = len(scope) > 0 ? SHA256(scope.join(",")) : ""
+//   scopeHash  = len(scope) > 0 ? SHA256(sorted(unique(scope)).join(",")) : ""
 //   bodyHash   = SHA256(canonicalize(scopedPayload))
 //   chainHash  = previousProof != "" ? SHA256(previousProof) : ""
 //   proof      = HMAC-SHA256(clientSecret, timestamp|binding|bodyHash|scopeHash|chainHash)
@@ -908,8 +925,7 @@ func BuildProofUnified(clientSecret, timestamp, binding string, payload map[stri
 	// Compute scope hash (empty string if no scope)
 	scopeHash := ""
 	if len(scope) > 0 {
-		scopeStr := strings.Join(scope, ",")
-		scopeHash = HashBody(scopeStr)
+		scopeHash = HashScope(scope)
 	}
 
 	// Compute chain hash (empty string if no previous proof)
@@ -935,8 +951,7 @@ func BuildProofUnified(clientSecret, timestamp, binding string, payload map[stri
 func VerifyProofUnified(nonce, contextID, binding, timestamp string, payload map[string]interface{}, clientProof string, scope []string, scopeHash, previousProof, chainHash string) bool {
 	// Validate scope hash if scoping is used
 	if len(scope) > 0 {
-		scopeStr := strings.Join(scope, ",")
-		expectedScopeHash := HashBody(scopeStr)
+		expectedScopeHash := HashScope(scope)
 		if !TimingSafeCompare(expectedScopeHash, scopeHash) {
 			return false
 		}
@@ -785,6 +785,29 @@ func TestCanonicalizeURLEncodedFromMap(t *testing.T) {
 	}
 }
 
+func TestBuildProofUnifiedScopeIsASet(t *testing.T) {
+	// scoped_unsorted_duplicated vector
+	clientSecret := DeriveClientSecret(
+		"0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+		"ash_test_context_007",
+		"POST /api/transfer",
+	)
+	payload := map[string]interface{}{"amount": float64(100), "recipient": "user123", "note": "test"}
+
+	result := BuildProofUnified(clientSecret, "1704067200000", "POST /api/transfer", payload,
+		[]string{"recipient", "amount", "recipient"}, "")
+
+	if result.ScopeHash != HashScope([]string{"amount", "recipient"}) {
+		t.Error("Scope hash should not depend on order or duplicates")
+	}
+	if result.ScopeHash != "33b49dfc2f9995122702b8a09b7583c4d352c2430c153983e344b4d1a8e2279a" {
+		t.Errorf("Unexpected scope hash %s", result.ScopeHash)
+	}
+	if result.Proof != "4e6bfee79cf9c48a9e8fbb61348e7c4ac60f91f902edd16673c0e94f82b1f055" {
+		t.Errorf("Unexpected proof %s", result.Proof)
+	}
+}
+
 // Benchmark tests
 func BenchmarkBuildProof(b *testing.B) {
 	input := BuildProofInput{
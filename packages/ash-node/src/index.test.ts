@@ -9,6 +9,9 @@ import {
   ashTimingSafeEqual,
   ashVersion,
   ashLibraryVersion,
+  ashBuildProofUnified,
+  ashDeriveClientSecret,
+  ashScopeHash,
 } from './index';
 
 describe('ASH Node.js SDK', () => {
@@ -168,4 +171,28 @@ describe('ASH Node.js SDK', () => {
       expect(ashTimingSafeEqual('hello', 'hi')).toBe(false);
     });
   });
+
+  describe('ashBuildProofUnified', () => {
+    it('treats scope as a set (scoped_unsorted_duplicated vector)', () => {
+      const clientSecret = ashDeriveClientSecret(
+        '0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef',
+        'ash_test_context_007',
+        'POST /api/transfer'
+      );
+      const result = ashBuildProofUnified(
+        clientSecret,
+        '1704067200000',
+        'POST /api/transfer',
+        { amount: 100, recipient: 'user123', note: 'test' },
+        ['recipient', 'amount', 'recipient']
+      );
+      expect(result.scopeHash).toBe(ashScopeHash(['amount', 'recipient']));
+      expect(result.scopeHash).toBe(
+        '33b49dfc2f9995122702b8a09b7583c4d352c2430c153983e344b4d1a8e2279a'
+      );
+      expect(result.proof).toBe(
+        '4e6bfee79cf9c48a9e8fbb61348e7c4ac60f91f902edd16673c0e94f82b1f055'
+      );
+    });
+  });
 });
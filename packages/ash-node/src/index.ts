@@ -206,6 +206,27 @@ export interface AshScopedProofResult {
   scopeHash: string;
 }
 
+/**
+ * Sort (by UTF-8 bytes) and deduplicate a scope list.
+ *
+ * The scope is a set: field order and duplicates do not affect the proof.
+ */
+function normalizeScope(scope: string[]): string[] {
+  return Array.from(new Set(scope)).sort((a, b) =>
+    Buffer.compare(Buffer.from(a, 'utf8'), Buffer.from(b, 'utf8'))
+  );
+}
+
+/**
+ * Hash a scope list: SHA256 of the sorted, deduplicated fields joined by ",".
+ *
+ * @param scope Field paths
+ * @returns Scope hash (64 hex chars)
+ */
+export function ashScopeHash(scope: string[]): string {
+  return ashHashBody(normalizeScope(scope).join(','));
+}
+
 /**
  * Extract scoped fields from a payload object.
  *
@@ -223,7 +244,7 @@ export function ashExtractScopedFields(
 
   const result: Record<string, unknown> = {};
 
-  for (const fieldPath of scope) {
+  for (const fieldPath of normalizeScope(scope)) {
     const value = getNestedValue(payload, fieldPath);
     if (value !== undefined) {
       setNestedValue(result, fieldPath, value);
@@ -287,8 +308,7 @@ export function ashBuildProofV21Scoped(
   const canonicalScoped = JSON.stringify(scopedPayload);
   const bodyHash = ashHashBody(canonicalScoped);
 
-  const scopeStr = scope.join(',');
-  const scopeHash = ashHashBody(scopeStr);
+  const scopeHash = ashScopeHash(scope);
 
   const message = timestamp + '|' + binding + '|' + bodyHash + '|' + scopeHash;
   const proof = crypto.createHmac('sha256', clientSecret)
@@ -312,8 +332,7 @@ export function ashVerifyProofV21Scoped(
   clientProof: string
 ): boolean {
   // Verify scope hash
-  const scopeStr = scope.join(',');
-  const expectedScopeHash = ashHashBody(scopeStr);
+  const expectedScopeHash = ashScopeHash(scope);
 
   try {
     if (!crypto.timingSafeEqual(
@@ -378,7 +397,7 @@ export function ashHashProof(proof: string): string {
  * Build unified v2.3 cryptographic proof with optional scoping and chaining.
  *
  * Formula:
- *   scopeHash  = scope.length > 0 ? SHA256(scope.join(",")) : ""
+ *   scopeHash  = scope.length > 0 ? SHA256(sorted(unique(scope)).join(",")) : ""
  *   bodyHash   = SHA256(canonicalize(scopedPayload))
  *   chainHash  = previousProof ? SHA256(previousProof) : ""
  *   proof      = HMAC-SHA256(clientSecret, timestamp|binding|bodyHash|scopeHash|chainHash)
@@ -405,7 +424,7 @@ export function ashBuildProofUnified(
   const bodyHash = ashHashBody(canonicalScoped);
 
   // Compute scope hash (empty string if no scope)
-  const scopeHash = scope.length > 0 ? ashHashBody(scope.join(',')) : '';
+  const scopeHash = scope.length > 0 ? ashScopeHash(scope) : '';
 
   // Compute chain hash (empty string if no previous proof)
   const chainHash = (previousProof && previousProof !== '')
@@ -450,7 +469,7 @@ export function ashVerifyProofUnified(
 ): boolean {
   // Validate scope hash if scoping is used
   if (scope.length > 0) {
-    const expectedScopeHash = ashHashBody(scope.join(','));
+    const expectedScopeHash = ashScopeHash(scope);
     try {
       if (!crypto.timingSafeEqual(
         Buffer.from(expectedScopeHash, 'hex'),
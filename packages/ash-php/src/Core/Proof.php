@@ -227,7 +227,7 @@ final class Proof
         }
 
         $result = [];
-        foreach ($scope as $fieldPath) {
+        foreach (self::normalizeScope($scope) as $fieldPath) {
             $value = self::getNestedValue($payload, $fieldPath);
             if ($value !== null) {
                 self::setNestedValue($result, $fieldPath, $value);
@@ -236,6 +236,31 @@ final class Proof
         return $result;
     }
 
+    /**
+     * Sort (byte order) and deduplicate a scope list.
+     * The scope is a set: field order and duplicates do not affect the proof.
+     *
+     * @param array<string> $scope Field paths
+     * @return array<string> Normalized field paths
+     */
+    private static function normalizeScope(array $scope): array
+    {
+        $normalized = array_values(array_unique($scope, SORT_STRING));
+        sort($normalized, SORT_STRING);
+        return $normalized;
+    }
+
+    /**
+     * Hash a scope list: SHA256 of the normalized fields joined by ",".
+     *
+     * @param array<string> $scope Field paths
+     * @return string Scope hash (64 hex chars)
+     */
+    public static function hashScope(array $scope): string
+    {
+        return self::hashBody(implode(',', self::normalizeScope($scope)));
+    }
+
     private static function getNestedValue(array $array, string $path): mixed
     {
         $keys = explode('.', $path);
@@ -282,8 +307,7 @@ final class Proof
         $canonicalScoped = json_encode($scopedPayload, JSON_UNESCAPED_SLASHES | JSON_UNESCAPED_UNICODE);
         $bodyHash = self::hashBody($canonicalScoped);
 
-        $scopeStr = implode(',', $scope);
-        $scopeHash = self::hashBody($scopeStr);
+        $scopeHash = self::hashScope($scope);
 
         $message = $timestamp . '|' . $binding . '|' . $bodyHash . '|' . $scopeHash;
         $proof = hash_hmac('sha256', $message, $clientSecret);
@@ -304,8 +328,7 @@ final class Proof
         string $scopeHash,
         string $clientProof
     ): bool {
-        $scopeStr = implode(',', $scope);
-        $expectedScopeHash = self::hashBody($scopeStr);
+        $expectedScopeHash = self::hashScope($scope);
         if (!Compare::timingSafe($expectedScopeHash, $scopeHash)) {
             return false;
         }
@@ -364,7 +387,7 @@ final class Proof
         $canonicalScoped = json_encode($scopedPayload, JSON_UNESCAPED_SLASHES | JSON_UNESCAPED_UNICODE);
         $bodyHash = self::hashBody($canonicalScoped);
 
-        $scopeHash = empty($scope) ? '' : self::hashBody(implode(',', $scope));
+        $scopeHash = empty($scope) ? '' : self::hashScope($scope);
         $chainHash = ($previousProof !== null && $previousProof !== '')
             ? self::hashProof($previousProof)
             : '';
@@ -407,7 +430,7 @@ final class Proof
         string $chainHash = ''
     ): bool {
         if (!empty($scope)) {
-            $expectedScopeHash = self::hashBody(implode(',', $scope));
+            $expectedScopeHash = self::hashScope($scope);
             if (!Compare::timingSafe($expectedScopeHash, $scopeHash)) {
                 return false;
             }
@@ -136,4 +136,28 @@ final class ProofTest extends TestCase
 
         $this->assertSame('hello', $decoded);
     }
+
+    #[Test]
+    public function unifiedProofTreatsScopeAsSet(): void
+    {
+        // scoped_unsorted_duplicated vector
+        $clientSecret = Proof::deriveClientSecret(
+            '0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef',
+            'ash_test_context_007',
+            'POST /api/transfer'
+        );
+        $payload = ['amount' => 100, 'recipient' => 'user123', 'note' => 'test'];
+
+        $result = Proof::buildUnified(
+            $clientSecret,
+            '1704067200000',
+            'POST /api/transfer',
+            $payload,
+            ['recipient', 'amount', 'recipient']
+        );
+
+        $this->assertSame(Proof::hashScope(['amount', 'recipient']), $result['scopeHash']);
+        $this->assertSame('33b49dfc2f9995122702b8a09b7583c4d352c2430c153983e344b4d1a8e2279a', $result['scopeHash']);
+        $this->assertSame('4e6bfee79cf9c48a9e8fbb61348e7c4ac60f91f902edd16673c0e94f82b1f055', $result['proof']);
+    }
 }
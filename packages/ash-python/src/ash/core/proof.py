@@ -233,6 +233,16 @@ def extract_scoped_fields(payload: dict, scope: list[str]) -> dict:
     return result
 
 
+def _normalize_scope(scope: list[str]) -> list[str]:
+    """Sort (by UTF-8 bytes) and deduplicate a scope list; scope is a set."""
+    return sorted(set(scope), key=lambda field: field.encode("utf-8"))
+
+
+def _hash_scope(scope: list[str]) -> str:
+    """Scope hash: SHA-256 of the normalized fields joined by ","."""
+    return hash_body(",".join(_normalize_scope(scope)))
+
+
 def _get_nested_value(obj: dict, path: str):
     """Get a nested value using dot notation."""
     keys = path.split(".")
@@ -285,8 +295,7 @@ def build_proof_v21_scoped(
     canonical_scoped = json.dumps(scoped_payload, separators=(",", ":"), sort_keys=True)
     body_hash = hash_body(canonical_scoped)
 
-    scope_str = ",".join(scope)
-    scope_hash = hash_body(scope_str)
+    scope_hash = _hash_scope(scope)
 
     message = f"{timestamp}|{binding}|{body_hash}|{scope_hash}"
     proof = hmac.new(
@@ -315,8 +324,7 @@ def verify_proof_v21_scoped(
         True if proof is valid
     """
     # Verify scope hash
-    scope_str = ",".join(scope)
-    expected_scope_hash = hash_body(scope_str)
+    expected_scope_hash = _hash_scope(scope)
     if not hmac.compare_digest(expected_scope_hash, scope_hash):
         return False
 
@@ -376,7 +384,7 @@ def build_proof_unified(
     Build unified v2.3 cryptographic proof with optional scoping and chaining.
 
     Formula:
-        scopeHash  = len(scope) > 0 ? SHA256(scope.join(",")) : ""
+        scopeHash  = len(scope) > 0 ? SHA256(sorted(unique(scope)).join(",")) : ""
         bodyHash   = SHA256(canonicalize(scopedPayload))
         chainHash  = previous_proof ? SHA256(previous_proof) : ""
         proof      = HMAC-SHA256(clientSecret, timestamp|binding|bodyHash|scopeHash|chainHash)
@@ -403,7 +411,7 @@ def build_proof_unified(
     body_hash = hash_body(canonical_scoped)
 
     # Compute scope hash (empty string if no scope)
-    scope_hash = hash_body(",".join(scope)) if scope else ""
+    scope_hash = _hash_scope(scope) if scope else ""
 
     # Compute chain hash (empty string if no previous proof)
     chain_hash = hash_proof(previous_proof) if previous_proof else ""
@@ -454,7 +462,7 @@ def verify_proof_unified(
 
     # Validate scope hash if scoping is used
     if scope:
-        expected_scope_hash = hash_body(",".join(scope))
+        expected_scope_hash = _hash_scope(scope)
         if not hmac.compare_digest(expected_scope_hash, scope_hash):
             return False
 
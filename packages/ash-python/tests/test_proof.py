@@ -1,7 +1,14 @@
 """Tests for ASH proof generation."""
 
 import pytest
-from ash.core.proof import build_proof, base64url_encode, base64url_decode
+from ash.core.proof import (
+    build_proof,
+    build_proof_unified,
+    base64url_encode,
+    base64url_decode,
+    derive_client_secret,
+    verify_proof_unified,
+)
 from ash.core.types import BuildProofInput
 
 
@@ -114,3 +121,38 @@ class TestBase64Url:
         encoded = "aGVsbG8="
         decoded = base64url_decode(encoded)
         assert decoded == b"hello"
+
+
+class TestUnifiedScope:
+    """Tests for scope handling in unified proofs."""
+
+    NONCE = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+    CONTEXT_ID = "ash_test_context_007"
+    BINDING = "POST /api/transfer"
+    TIMESTAMP = "1704067200000"
+    PAYLOAD = {"amount": 100, "recipient": "user123", "note": "test"}
+
+    def test_scope_is_a_set(self):
+        """Unsorted, duplicated scope matches the scoped_unsorted_duplicated vector."""
+        client_secret = derive_client_secret(self.NONCE, self.CONTEXT_ID, self.BINDING)
+        proof, scope_hash, chain_hash = build_proof_unified(
+            client_secret,
+            self.TIMESTAMP,
+            self.BINDING,
+            self.PAYLOAD,
+            ["recipient", "amount", "recipient"],
+        )
+
+        assert scope_hash == "33b49dfc2f9995122702b8a09b7583c4d352c2430c153983e344b4d1a8e2279a"
+        assert proof == "4e6bfee79cf9c48a9e8fbb61348e7c4ac60f91f902edd16673c0e94f82b1f055"
+        assert chain_hash == ""
+        assert verify_proof_unified(
+            self.NONCE,
+            self.CONTEXT_ID,
+            self.BINDING,
+            self.TIMESTAMP,
+            self.PAYLOAD,
+            proof,
+            ["amount", "recipient"],
+            scope_hash,
+        )
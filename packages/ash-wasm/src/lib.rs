@@ -70,6 +70,22 @@ pub fn ash_canonicalize_urlencoded(input: &str) -> Result<String, JsValue> {
     ash_core::canonicalize_urlencoded(input).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
+/// Canonicalize a URL query string to deterministic form.
+///
+/// # Canonicalization Rules
+/// - RFC 3986 semantics: `+` is a literal plus, only `%20` is a space
+/// - Leading `?` ignored
+/// - Key-value pairs sorted by key
+/// - Unicode NFC normalized
+///
+/// @param input - Query string to canonicalize
+/// @returns Canonical query string
+/// @throws Error if input cannot be canonicalized
+#[wasm_bindgen(js_name = "ashCanonicalizeQuery")]
+pub fn ash_canonicalize_query(input: &str) -> Result<String, JsValue> {
+    ash_core::canonicalize_query(input).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 /// Build a cryptographic proof for request integrity.
 ///
 /// The proof binds the payload to a specific context and endpoint,
@@ -232,6 +248,29 @@ mod tests {
         assert_eq!(result, "POST /api/test");
     }
 
+    #[test]
+    fn test_canonicalize_and_hash() {
+        let input = r#"{"z":1,"a":{"c":3,"b":2}}"#;
+        let expected = ash_core::hash_body(&ash_core::canonicalize_json(input).unwrap());
+        assert_eq!(ash_canonicalize_and_hash(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_build_proof_from_nonce_matches_two_step() {
+        let body_hash = ash_hash_body(r#"{"a":1}"#);
+        let client_secret = ash_derive_client_secret("nonce123", "ctx_abc", "POST /login");
+        let two_step = ash_build_proof_v21(&client_secret, "1234567890", "POST /login", &body_hash);
+
+        let one_step = ash_build_proof_from_nonce(
+            "nonce123",
+            "ctx_abc",
+            "POST /login",
+            "1234567890",
+            &body_hash,
+        );
+        assert_eq!(one_step, two_step);
+    }
+
     #[test]
     fn test_version() {
         assert_eq!(ash_version(), "ASHv2.1");
@@ -245,15 +284,27 @@ mod tests {
 /// Generate a cryptographically secure random nonce.
 /// @param bytes - Number of bytes (default 32)
 /// @returns Hex-encoded nonce
+/// @throws Error if the secure random source is unavailable
 #[wasm_bindgen(js_name = "ashGenerateNonce")]
-pub fn ash_generate_nonce(bytes: Option<usize>) -> String {
-    ash_core::generate_nonce(bytes.unwrap_or(32))
+pub fn ash_generate_nonce(bytes: Option<usize>) -> Result<String, JsValue> {
+    ash_core::try_generate_nonce(bytes.unwrap_or(32)).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Generate cryptographically secure random bytes.
+/// @param bytes - Number of bytes (default 32)
+/// @returns Raw random bytes as a Uint8Array
+/// @throws Error if the secure random source is unavailable
+#[wasm_bindgen(js_name = "ashGenerateNonceBytes")]
+pub fn ash_generate_nonce_bytes(bytes: Option<usize>) -> Result<Vec<u8>, JsValue> {
+    ash_core::try_generate_nonce_bytes(bytes.unwrap_or(32))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
 /// Generate a unique context ID with "ash_" prefix.
+/// @throws Error if the secure random source is unavailable
 #[wasm_bindgen(js_name = "ashGenerateContextId")]
-pub fn ash_generate_context_id() -> String {
-    ash_core::generate_context_id()
+pub fn ash_generate_context_id() -> Result<String, JsValue> {
+    ash_core::try_generate_context_id().map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
 /// Derive client secret from server nonce (v2.1).
@@ -282,6 +333,28 @@ pub fn ash_build_proof_v21(
     ash_core::build_proof_v21(client_secret, timestamp, binding, body_hash)
 }
 
+/// Derive the client secret and build a v2.1 proof in one call.
+///
+/// Equivalent to `ashBuildProofV21(ashDeriveClientSecret(...), ...)`, but
+/// the derived secret stays inside WASM memory and never reaches JS.
+/// @param nonce - Server-side secret nonce
+/// @param contextId - Context identifier
+/// @param binding - Request binding
+/// @param timestamp - Request timestamp (milliseconds as string)
+/// @param bodyHash - SHA-256 hash of canonical body
+/// @returns Proof (64 hex chars)
+#[wasm_bindgen(js_name = "ashBuildProofFromNonce")]
+pub fn ash_build_proof_from_nonce(
+    nonce: &str,
+    context_id: &str,
+    binding: &str,
+    timestamp: &str,
+    body_hash: &str,
+) -> String {
+    let client_secret = ash_core::derive_client_secret(nonce, context_id, binding);
+    ash_core::build_proof_v21(&client_secret, timestamp, binding, body_hash)
+}
+
 /// Verify v2.1 proof.
 /// @param nonce - Server-side secret nonce
 /// @param contextId - Context identifier
@@ -310,6 +383,20 @@ pub fn ash_hash_body(canonical_body: &str) -> String {
     ash_core::hash_body(canonical_body)
 }
 
+/// Canonicalize JSON and hash it in a single call.
+///
+/// Equivalent to `ashHashBody(ashCanonicalizeJson(input))` but crosses the
+/// WASM boundary once, avoiding an extra copy of large payloads.
+/// @param input - JSON string to canonicalize
+/// @returns SHA-256 hash of the canonical JSON (64 hex chars)
+/// @throws Error if input is not valid JSON
+#[wasm_bindgen(js_name = "ashCanonicalizeAndHash")]
+pub fn ash_canonicalize_and_hash(input: &str) -> Result<String, JsValue> {
+    let canonical =
+        ash_core::canonicalize_json(input).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(ash_core::hash_body(&canonical))
+}
+
 // =========================================================================
 // ASH v2.2 - Context Scoping WASM Bindings
 // =========================================================================
@@ -362,6 +449,7 @@ pub fn ash_build_proof_scoped(
 /// @param clientProof - Proof received from client
 /// @returns true if proof is valid
 #[wasm_bindgen(js_name = "ashVerifyProofScoped")]
+#[allow(clippy::too_many_arguments)]
 pub fn ash_verify_proof_scoped(
     nonce: &str,
     context_id: &str,
@@ -452,13 +540,7 @@ pub fn ash_build_proof_unified(
         prev_proof,
     ).map_err(|e| JsValue::from_str(&e.to_string()))?;
 
-    let json_result = serde_json::json!({
-        "proof": result.proof,
-        "scopeHash": result.scope_hash,
-        "chainHash": result.chain_hash
-    });
-
-    Ok(JsValue::from_str(&json_result.to_string()))
+    Ok(JsValue::from_str(&result.to_json()))
 }
 
 /// Verify unified v2.3 proof with optional scoping and chaining.
@@ -474,6 +556,7 @@ pub fn ash_build_proof_unified(
 /// @param chainHash - Chain hash from client (empty if no chaining)
 /// @returns true if proof is valid
 #[wasm_bindgen(js_name = "ashVerifyProofUnified")]
+#[allow(clippy::too_many_arguments)]
 pub fn ash_verify_proof_unified(
     nonce: &str,
     context_id: &str,
@@ -507,3 +590,398 @@ pub fn ash_verify_proof_unified(
         chain_hash,
     ).map_err(|e| JsValue::from_str(&e.to_string()))
 }
+
+// =========================================================================
+// Signed Request Bundle WASM Bindings
+// =========================================================================
+
+/// Options object accepted by `ashCreateSignedRequest`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct SignedRequestOptions {
+    scope: Vec<String>,
+    previous_proof: Option<String>,
+}
+
+/// Header-ready bundle returned by `ashCreateSignedRequest`.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignedRequest {
+    timestamp: String,
+    body_hash: String,
+    proof: String,
+    binding: String,
+    scope_hash: String,
+    chain_hash: String,
+}
+
+/// Canonicalize, hash and prove a request in a single call.
+///
+/// Without scope or previous proof this is a plain v2.1 proof
+/// (`ashBuildProofV21` over the canonical body hash). Otherwise it is a
+/// unified v2.3 proof and `scopeHash`/`chainHash` must be sent as well.
+/// @param clientSecret - Derived client secret
+/// @param binding - Request binding (e.g., "POST /api/transfer")
+/// @param payload - JSON payload
+/// @param options - Optional JSON object string: { scope?: string[], previousProof?: string }
+/// @returns Object with { timestamp, bodyHash, proof, binding, scopeHash, chainHash }
+/// @throws Error if the payload or options are not valid JSON
+#[wasm_bindgen(js_name = "ashCreateSignedRequest")]
+pub fn ash_create_signed_request(
+    client_secret: &str,
+    binding: &str,
+    payload: &str,
+    options: Option<String>,
+) -> Result<JsValue, JsValue> {
+    let timestamp = (js_sys::Date::now() as u64).to_string();
+
+    let bundle = create_signed_request(
+        client_secret,
+        binding,
+        payload,
+        options.as_deref(),
+        &timestamp,
+    )
+    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    Ok(JsValue::from_str(&bundle))
+}
+
+/// Build the signed request bundle JSON for a given timestamp.
+fn create_signed_request(
+    client_secret: &str,
+    binding: &str,
+    payload: &str,
+    options: Option<&str>,
+    timestamp: &str,
+) -> Result<String, ash_core::AshError> {
+    let options: SignedRequestOptions = match options.filter(|o| !o.is_empty()) {
+        Some(json) => serde_json::from_str(json).map_err(|e| {
+            ash_core::AshError::new(
+                ash_core::AshErrorCode::MalformedRequest,
+                format!("Invalid signed request options: {}", e),
+            )
+        })?,
+        None => SignedRequestOptions::default(),
+    };
+
+    let scope: Vec<&str> = options.scope.iter().map(String::as_str).collect();
+    let previous_proof = options.previous_proof.as_deref().filter(|p| !p.is_empty());
+
+    let bundle = if scope.is_empty() && previous_proof.is_none() {
+        let body_hash = ash_core::hash_body(&ash_core::canonicalize_json(payload)?);
+        SignedRequest {
+            timestamp: timestamp.to_string(),
+            proof: ash_core::build_proof_v21(client_secret, timestamp, binding, &body_hash),
+            body_hash,
+            binding: binding.to_string(),
+            scope_hash: String::new(),
+            chain_hash: String::new(),
+        }
+    } else {
+        let result = ash_core::build_proof_v21_unified(
+            client_secret,
+            timestamp,
+            binding,
+            payload,
+            &scope,
+            previous_proof,
+        )?;
+        SignedRequest {
+            timestamp: timestamp.to_string(),
+            body_hash: ash_core::hash_scoped_body(payload, &scope)?,
+            proof: result.proof,
+            binding: binding.to_string(),
+            scope_hash: result.scope_hash,
+            chain_hash: result.chain_hash,
+        }
+    };
+
+    Ok(serde_json::to_string(&bundle).expect("SignedRequest is always serializable"))
+}
+
+#[cfg(test)]
+mod tests_signed_request {
+    use super::*;
+    use serde_json::Value;
+
+    const NONCE: &str = "test_nonce_12345";
+    const CONTEXT_ID: &str = "ctx_abc123";
+    const BINDING: &str = "POST /api/transfer";
+    const PAYLOAD: &str = r#"{"recipient":"user1","amount":1000}"#;
+
+    fn bundle(options: Option<&str>) -> Value {
+        let secret = ash_core::derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+        let json =
+            create_signed_request(&secret, BINDING, PAYLOAD, options, "1700000000000").unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_signed_request_verifies_v21() {
+        let bundle = bundle(None);
+
+        assert_eq!(bundle["timestamp"], "1700000000000");
+        assert_eq!(bundle["binding"], BINDING);
+        assert_eq!(
+            bundle["bodyHash"],
+            ash_canonicalize_and_hash(PAYLOAD).unwrap()
+        );
+
+        let is_valid = ash_core::verify_proof_v21_checked(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            bundle["timestamp"].as_str().unwrap(),
+            bundle["bodyHash"].as_str().unwrap(),
+            bundle["proof"].as_str().unwrap(),
+        )
+        .unwrap();
+        assert!(is_valid);
+    }
+
+    #[test]
+    fn test_signed_request_verifies_scoped_and_chained() {
+        let previous = "a".repeat(64);
+        let options = format!(r#"{{"scope":["amount"],"previousProof":"{}"}}"#, previous);
+        let bundle = bundle(Some(&options));
+
+        let is_valid = ash_core::verify_proof_v21_unified(
+            NONCE,
+            CONTEXT_ID,
+            BINDING,
+            bundle["timestamp"].as_str().unwrap(),
+            PAYLOAD,
+            bundle["proof"].as_str().unwrap(),
+            &["amount"],
+            bundle["scopeHash"].as_str().unwrap(),
+            Some(&previous),
+            bundle["chainHash"].as_str().unwrap(),
+        )
+        .unwrap();
+        assert!(is_valid);
+        assert_eq!(
+            bundle["bodyHash"],
+            ash_core::hash_scoped_body(PAYLOAD, &["amount"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_signed_request_rejects_bad_options() {
+        let secret = ash_core::derive_client_secret(NONCE, CONTEXT_ID, BINDING);
+        let err = create_signed_request(&secret, BINDING, PAYLOAD, Some(r#"{"scop":[]}"#), "1")
+            .unwrap_err();
+        assert_eq!(err.code(), ash_core::AshErrorCode::MalformedRequest);
+    }
+}
+
+// =========================================================================
+// Context Store (server-side replay protection)
+// =========================================================================
+
+/// In-memory context store for JavaScript servers.
+///
+/// Wraps the core `MemoryContextStore`. State is per instance: it is not
+/// shared across workers, processes or separately loaded WASM modules, so
+/// every instance that verifies requests must see the same store.
+#[wasm_bindgen(js_name = "WasmInMemoryContextStore")]
+#[derive(Debug, Default)]
+pub struct WasmInMemoryContextStore {
+    inner: ash_core::MemoryContextStore,
+}
+
+#[wasm_bindgen(js_class = "WasmInMemoryContextStore")]
+impl WasmInMemoryContextStore {
+    /// Create an empty store.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert or replace an issued context.
+    /// @param context - JSON object string:
+    ///   { contextId, binding, mode, issuedAt, expiresAt, nonce?, consumedAt? }
+    /// @throws Error if the context is not valid JSON
+    pub fn insert(&self, context: &str) -> Result<(), JsValue> {
+        insert_context(&self.inner, context).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Atomically mark a context consumed.
+    /// @param contextId - Context ID from the request
+    /// @param nowMs - Current time (milliseconds since epoch)
+    /// @returns "first_use", "already_consumed", "expired" or "not_found"
+    pub fn consume(&self, context_id: &str, now_ms: f64) -> String {
+        use ash_core::{ContextStore, ContextUse};
+
+        let status = match self.inner.consume(context_id, now_ms as u64) {
+            ContextUse::FirstUse => "first_use",
+            ContextUse::AlreadyConsumed => "already_consumed",
+            ContextUse::Expired => "expired",
+            ContextUse::NotFound => "not_found",
+        };
+        status.to_string()
+    }
+}
+
+/// Parse a stored context from JSON and insert it into `store`.
+fn insert_context(
+    store: &ash_core::MemoryContextStore,
+    context: &str,
+) -> Result<(), ash_core::AshError> {
+    let context: ash_core::StoredContext = serde_json::from_str(context).map_err(|e| {
+        ash_core::AshError::new(
+            ash_core::AshErrorCode::MalformedRequest,
+            format!("Invalid stored context: {}", e),
+        )
+    })?;
+    store.insert(context);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests_context_store {
+    use super::*;
+
+    const CONTEXT: &str = r#"{
+        "contextId": "ctx_1",
+        "binding": "POST /api/transfer",
+        "mode": "balanced",
+        "issuedAt": 1000,
+        "expiresAt": 2000
+    }"#;
+
+    #[test]
+    fn test_consume_twice_is_already_consumed() {
+        let store = WasmInMemoryContextStore::new();
+        store.insert(CONTEXT).unwrap();
+
+        assert_eq!(store.consume("ctx_1", 1500.0), "first_use");
+        assert_eq!(store.consume("ctx_1", 1600.0), "already_consumed");
+    }
+
+    #[test]
+    fn test_consume_expired_and_not_found() {
+        let store = WasmInMemoryContextStore::new();
+        store.insert(CONTEXT).unwrap();
+
+        assert_eq!(store.consume("ctx_1", 2000.0), "expired");
+        assert_eq!(store.consume("ctx_2", 1500.0), "not_found");
+    }
+
+    #[test]
+    fn test_insert_rejects_invalid_context() {
+        let store = ash_core::MemoryContextStore::new();
+        let err = insert_context(&store, r#"{"contextId":"ctx_1"}"#).unwrap_err();
+        assert_eq!(err.code(), ash_core::AshErrorCode::MalformedRequest);
+    }
+}
+
+// =========================================================================
+// Structured Errors (`*Checked` variants)
+// =========================================================================
+//
+// The functions above throw `AshError` flattened to a string. The
+// `*Checked` variants below throw an `Error` that also carries the
+// machine-readable code and HTTP status:
+//
+//     try { ashCanonicalizeJsonChecked(body) }
+//     catch (e) { e.code === "ASH_CANONICALIZATION_FAILED"; e.status === 400 }
+
+/// Convert an `AshError` into a JS `Error` with `code` and `status` fields.
+fn to_js_error(e: ash_core::AshError) -> JsValue {
+    let error = js_sys::Error::new(e.message());
+    let set = |key: &str, value: JsValue| {
+        // Setting a property on a fresh, extensible Error cannot fail
+        let _ = js_sys::Reflect::set(&error, &JsValue::from_str(key), &value);
+    };
+    set("code", JsValue::from_str(e.code().as_str()));
+    set("status", JsValue::from(e.http_status()));
+    error.into()
+}
+
+/// Like `ashCanonicalizeJson`, but throws `{ code, message, status }`.
+/// @param input - JSON string to canonicalize
+/// @returns Canonical JSON string
+/// @throws Error with `code` (e.g. "ASH_CANONICALIZATION_FAILED") and `status`
+#[wasm_bindgen(js_name = "ashCanonicalizeJsonChecked")]
+pub fn ash_canonicalize_json_checked(input: &str) -> Result<String, JsValue> {
+    ash_core::canonicalize_json(input).map_err(to_js_error)
+}
+
+/// Like `ashCanonicalizeUrlencoded`, but throws `{ code, message, status }`.
+/// @param input - URL-encoded string to canonicalize
+/// @returns Canonical URL-encoded string
+/// @throws Error with `code` and `status`
+#[wasm_bindgen(js_name = "ashCanonicalizeUrlencodedChecked")]
+pub fn ash_canonicalize_urlencoded_checked(input: &str) -> Result<String, JsValue> {
+    ash_core::canonicalize_urlencoded(input).map_err(to_js_error)
+}
+
+/// Like `ashCanonicalizeQuery`, but throws `{ code, message, status }`.
+/// @param input - Query string to canonicalize
+/// @returns Canonical query string
+/// @throws Error with `code` and `status`
+#[wasm_bindgen(js_name = "ashCanonicalizeQueryChecked")]
+pub fn ash_canonicalize_query_checked(input: &str) -> Result<String, JsValue> {
+    ash_core::canonicalize_query(input).map_err(to_js_error)
+}
+
+/// Like `ashNormalizeBinding`, but throws `{ code, message, status }`.
+/// @param method - HTTP method (GET, POST, etc.)
+/// @param path - URL path
+/// @returns Canonical binding string
+/// @throws Error with `code` (e.g. "ASH_MALFORMED_REQUEST") and `status`
+#[wasm_bindgen(js_name = "ashNormalizeBindingChecked")]
+pub fn ash_normalize_binding_checked(method: &str, path: &str) -> Result<String, JsValue> {
+    ash_core::normalize_binding(method, path).map_err(to_js_error)
+}
+
+/// Like `ashCanonicalizeAndHash`, but throws `{ code, message, status }`.
+/// @param input - JSON string to canonicalize
+/// @returns SHA-256 hash of the canonical JSON (64 hex chars)
+/// @throws Error with `code` and `status`
+#[wasm_bindgen(js_name = "ashCanonicalizeAndHashChecked")]
+pub fn ash_canonicalize_and_hash_checked(input: &str) -> Result<String, JsValue> {
+    let canonical = ash_core::canonicalize_json(input).map_err(to_js_error)?;
+    Ok(ash_core::hash_body(&canonical))
+}
+
+// JS values only exist on a wasm32 host (`wasm-pack test --node`)
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests_structured_errors {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn field(error: &JsValue, key: &str) -> JsValue {
+        js_sys::Reflect::get(error, &JsValue::from_str(key)).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_checked_error_carries_code() {
+        let error = ash_canonicalize_json_checked("{oops").unwrap_err();
+
+        assert!(error.is_instance_of::<js_sys::Error>());
+        assert_eq!(
+            field(&error, "code").as_string().unwrap(),
+            "ASH_CANONICALIZATION_FAILED"
+        );
+        assert_eq!(field(&error, "status").as_f64().unwrap(), 400.0);
+        assert!(field(&error, "message")
+            .as_string()
+            .unwrap()
+            .starts_with("Invalid JSON"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_checked_success_unchanged() {
+        assert_eq!(
+            ash_normalize_binding_checked("post", "/api//x/").unwrap(),
+            ash_normalize_binding("post", "/api//x/").unwrap()
+        );
+        let error = ash_normalize_binding_checked("", "/x").unwrap_err();
+        assert_eq!(
+            field(&error, "code").as_string().unwrap(),
+            "ASH_MALFORMED_REQUEST"
+        );
+    }
+}